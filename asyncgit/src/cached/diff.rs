@@ -0,0 +1,103 @@
+use crate::{
+    error::Result,
+    sync::{self, diff::get_diff_cache_key},
+    FileDiff,
+};
+
+/// `sync::diff::get_diff` that reuses the last result as long as
+/// `HEAD`, the index entry and the file in the workdir are unchanged
+pub struct DiffCache {
+    last_result: Option<(u64, FileDiff)>,
+    repo_path: String,
+}
+
+impl DiffCache {
+    ///
+    pub fn new(path: &str) -> Self {
+        Self {
+            repo_path: path.to_string(),
+            last_result: None,
+        }
+    }
+
+    ///
+    pub fn get(
+        &mut self,
+        path: &str,
+        stage: bool,
+    ) -> Result<FileDiff> {
+        Ok(self.lookup(path, stage)?.0)
+    }
+
+    /// returns the diff and whether it came from the cache
+    fn lookup(
+        &mut self,
+        path: &str,
+        stage: bool,
+    ) -> Result<(FileDiff, bool)> {
+        let key =
+            get_diff_cache_key(self.repo_path.as_str(), path, stage)?;
+
+        if let Some((last_key, diff)) = self.last_result.as_ref() {
+            if *last_key == key {
+                return Ok((diff.clone(), true));
+            }
+        }
+
+        let diff = sync::diff::get_diff(
+            self.repo_path.as_str(),
+            path.to_string(),
+            stage,
+        )?;
+        self.last_result = Some((key, diff.clone()));
+
+        Ok((diff, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiffCache;
+    use crate::error::Result;
+    use crate::sync::{stage_add_file, tests::repo_init};
+    use std::{fs::File, io::Write, path::Path};
+
+    #[test]
+    fn test_cache_hit_and_miss() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"a\n")?;
+        stage_add_file(repo_path, file_path)?;
+        File::create(&root.join(file_path))?.write_all(b"b\n")?;
+
+        let mut cache = DiffCache::new(repo_path);
+
+        let (diff, hit) = cache.lookup("foo.txt", false)?;
+        assert_eq!(hit, false);
+        assert_eq!(diff.lines, 3);
+
+        let (_, hit) = cache.lookup("foo.txt", false)?;
+        assert_eq!(hit, true);
+
+        File::create(&root.join(file_path))?.write_all(b"b\nc\n")?;
+
+        let (diff, hit) = cache.lookup("foo.txt", false)?;
+        assert_eq!(hit, false);
+        assert_eq!(diff.lines, 4);
+
+        // switching between stage and workdir is a miss
+        let (_, hit) = cache.lookup("foo.txt", true)?;
+        assert_eq!(hit, false);
+
+        stage_add_file(repo_path, file_path)?;
+
+        let (diff, hit) = cache.lookup("foo.txt", true)?;
+        assert_eq!(hit, false);
+        assert_eq!(diff.lines, 3);
+
+        Ok(())
+    }
+}
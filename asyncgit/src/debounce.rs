@@ -0,0 +1,51 @@
+use crossbeam_channel::{unbounded, RecvTimeoutError, Sender};
+use std::{thread, time::Duration};
+
+/// coalesces bursts of jobs on a timer thread of its own: a job is
+/// only `run` once no newer one arrived for the debounce interval
+/// it was queued with, superseded ones are handed to `skip`.
+/// the thread ends once the `Debouncer` is dropped
+pub struct Debouncer<T> {
+    queue: Sender<(T, Duration)>,
+}
+
+impl<T: Send + 'static> Debouncer<T> {
+    ///
+    pub fn new<R, S>(run: R, skip: S) -> Self
+    where
+        R: Fn(T) + Send + 'static,
+        S: Fn(T) + Send + 'static,
+    {
+        let (queue, rx) = unbounded::<(T, Duration)>();
+
+        thread::spawn(move || {
+            while let Ok((mut job, mut interval)) = rx.recv() {
+                loop {
+                    match rx.recv_timeout(interval) {
+                        Ok((newer, newer_interval)) => {
+                            skip(job);
+                            job = newer;
+                            interval = newer_interval;
+                        }
+                        Err(RecvTimeoutError::Timeout) => {
+                            run(job);
+                            break;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => {
+                            skip(job);
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        Self { queue }
+    }
+
+    /// queues `job` to run after `interval` unless a newer one
+    /// arrives in the meantime
+    pub fn push(&self, job: T, interval: Duration) {
+        self.queue.send((job, interval)).expect("debounce queue");
+    }
+}
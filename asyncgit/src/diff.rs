@@ -1,4 +1,6 @@
 use crate::{
+    cached::DiffCache,
+    debounce::Debouncer,
     error::Result,
     hash,
     sync::{self, CommitId},
@@ -11,8 +13,13 @@ use std::{
         atomic::{AtomicUsize, Ordering},
         Arc, Mutex,
     },
+    time::Duration,
 };
 
+/// default for `AsyncDiff::set_debounce` and
+/// `AsyncStatus::set_debounce`
+pub const DIFF_DEBOUNCE: Duration = Duration::from_millis(100);
+
 ///
 #[derive(Hash, Clone, PartialEq)]
 pub enum DiffType {
@@ -46,21 +53,106 @@ struct LastResult<P, R> {
 pub struct AsyncDiff {
     current: Arc<Mutex<Request<u64, FileDiff>>>,
     last: Arc<Mutex<Option<LastResult<DiffParams, FileDiff>>>>,
-    sender: Sender<AsyncNotification>,
     pending: Arc<AtomicUsize>,
+    debouncer: Debouncer<(DiffParams, u64)>,
+    debounce: Duration,
+    /// number of diffs actually computed
+    #[cfg(test)]
+    runs: Arc<AtomicUsize>,
 }
 
 impl AsyncDiff {
     ///
     pub fn new(sender: Sender<AsyncNotification>) -> Self {
+        Self::with_repo(CWD, sender)
+    }
+
+    fn with_repo(
+        repo_path: &str,
+        sender: Sender<AsyncNotification>,
+    ) -> Self {
+        let current = Arc::new(Mutex::new(Request(0, None)));
+        let last = Arc::new(Mutex::new(None));
+        let cache = Arc::new(Mutex::new(DiffCache::new(repo_path)));
+        let pending = Arc::new(AtomicUsize::new(0));
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let debouncer = {
+            let current = Arc::clone(&current);
+            let last = Arc::clone(&last);
+            let cache = Arc::clone(&cache);
+            let pending = Arc::clone(&pending);
+            let runs = Arc::clone(&runs);
+            let sender = sender.clone();
+            let repo = repo_path.to_string();
+
+            let skip_pending = Arc::clone(&pending);
+            let skip_sender = sender.clone();
+
+            Debouncer::new(
+                move |(params, hash)| {
+                    let arc_current = Arc::clone(&current);
+                    let arc_last = Arc::clone(&last);
+                    let arc_cache = Arc::clone(&cache);
+                    let arc_pending = Arc::clone(&pending);
+                    let sender = sender.clone();
+                    let repo = repo.clone();
+
+                    runs.fetch_add(1, Ordering::Relaxed);
+
+                    rayon_core::spawn(move || {
+                        let notify = AsyncDiff::get_diff_helper(
+                            &repo,
+                            params,
+                            arc_last,
+                            arc_current,
+                            &arc_cache,
+                            hash,
+                        )
+                        .expect("error getting diff");
+
+                        arc_pending.fetch_sub(1, Ordering::Relaxed);
+
+                        sender
+                            .send(if notify {
+                                AsyncNotification::Diff
+                            } else {
+                                AsyncNotification::FinishUnchanged
+                            })
+                            .expect("error sending diff");
+                    });
+                },
+                move |_| {
+                    log::trace!("request superseded");
+
+                    skip_pending.fetch_sub(1, Ordering::Relaxed);
+
+                    skip_sender
+                        .send(AsyncNotification::FinishUnchanged)
+                        .expect("error sending diff");
+                },
+            )
+        };
+
         Self {
-            current: Arc::new(Mutex::new(Request(0, None))),
-            last: Arc::new(Mutex::new(None)),
-            sender,
-            pending: Arc::new(AtomicUsize::new(0)),
+            current,
+            last,
+            pending,
+            debouncer,
+            debounce: DIFF_DEBOUNCE,
+            #[cfg(test)]
+            runs,
         }
     }
 
+    /// requests wait this long before computing the diff and are
+    /// dropped if a newer request arrives in the meantime, so only
+    /// the last one of a burst (e.g. a formatter touching files)
+    /// is computed
+    pub fn set_debounce(&mut self, interval: Duration) {
+        self.debounce = interval;
+    }
+
     ///
     pub fn last(&mut self) -> Result<Option<(DiffParams, FileDiff)>> {
         let last = self.last.lock()?;
@@ -105,53 +197,35 @@ impl AsyncDiff {
             current.1 = None;
         }
 
-        let arc_current = Arc::clone(&self.current);
-        let arc_last = Arc::clone(&self.last);
-        let sender = self.sender.clone();
-        let arc_pending = Arc::clone(&self.pending);
-
         self.pending.fetch_add(1, Ordering::Relaxed);
 
-        rayon_core::spawn(move || {
-            let notify = AsyncDiff::get_diff_helper(
-                params,
-                arc_last,
-                arc_current,
-                hash,
-            )
-            .expect("error getting diff");
-
-            arc_pending.fetch_sub(1, Ordering::Relaxed);
-
-            sender
-                .send(if notify {
-                    AsyncNotification::Diff
-                } else {
-                    AsyncNotification::FinishUnchanged
-                })
-                .expect("error sending diff");
-        });
+        self.debouncer.push((params, hash), self.debounce);
 
         Ok(None)
     }
 
     fn get_diff_helper(
+        repo_path: &str,
         params: DiffParams,
         arc_last: Arc<
             Mutex<Option<LastResult<DiffParams, FileDiff>>>,
         >,
         arc_current: Arc<Mutex<Request<u64, FileDiff>>>,
+        arc_cache: &Mutex<DiffCache>,
         hash: u64,
     ) -> Result<bool> {
+        // the cache key and the diff share one repository handle
+        let _repo_cache = sync::RepoCache::new();
+
         let res = match params.diff_type {
             DiffType::Stage => {
-                sync::diff::get_diff(CWD, params.path.clone(), true)?
+                arc_cache.lock()?.get(params.path.as_str(), true)?
             }
             DiffType::WorkDir => {
-                sync::diff::get_diff(CWD, params.path.clone(), false)?
+                arc_cache.lock()?.get(params.path.as_str(), false)?
             }
             DiffType::Commit(id) => sync::diff::get_diff_commit(
-                CWD,
+                repo_path,
                 id,
                 params.path.clone(),
             )?,
@@ -189,3 +263,76 @@ impl AsyncDiff {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{AsyncDiff, DiffParams, DiffType, Request};
+    use crate::{
+        cached::DiffCache,
+        error::Result,
+        sync::{tests::repo_init, utils::repo_opens},
+    };
+    use crossbeam_channel::unbounded;
+    use std::{
+        fs::File,
+        io::Write,
+        sync::{atomic::Ordering, Arc, Mutex},
+    };
+
+    #[test]
+    fn test_single_repo_open() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join("foo"))?.write_all(b"foo")?;
+
+        let before = repo_opens();
+        AsyncDiff::get_diff_helper(
+            repo_path,
+            DiffParams {
+                path: String::from("foo"),
+                diff_type: DiffType::WorkDir,
+            },
+            Arc::new(Mutex::new(None)),
+            Arc::new(Mutex::new(Request(0, None))),
+            &Mutex::new(DiffCache::new(repo_path)),
+            0,
+        )?;
+
+        assert_eq!(repo_opens() - before, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_debounce() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join("foo"))?.write_all(b"foo")?;
+
+        let (sender, receiver) = unbounded();
+        let mut diff = AsyncDiff::with_repo(repo_path, sender);
+
+        let requests = 5;
+        for i in 0..requests {
+            let res = diff.request(DiffParams {
+                path: format!("foo{}", i % 2),
+                diff_type: DiffType::WorkDir,
+            })?;
+            assert_eq!(res.is_none(), true);
+        }
+
+        for _ in 0..requests {
+            receiver.recv().unwrap();
+        }
+
+        assert_eq!(diff.is_pending(), false);
+        assert_eq!(diff.runs.load(Ordering::Relaxed), 1);
+        assert_eq!(diff.last()?.unwrap().0.path, "foo0");
+
+        Ok(())
+    }
+}
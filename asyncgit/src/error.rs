@@ -1,18 +1,49 @@
 use thiserror::Error;
 
+/// errors of the sync and async git functions
 #[derive(Error, Debug)]
 pub enum Error {
+    ///
     #[error("`{0}`")]
     Generic(String),
 
+    ///
     #[error("git: no head found")]
     NoHead,
 
+    ///
+    #[error("git: nothing to commit")]
+    NothingToCommit,
+
+    ///
+    #[error(
+        "git: no identity configured (set user.email and user.name)"
+    )]
+    MissingIdentity,
+
+    ///
+    #[error("git: branch '{0}' has no upstream")]
+    NoUpstream(String),
+
+    ///
+    #[error("operation cancelled")]
+    Cancelled,
+
+    ///
+    #[error("hunk is outdated, the diff needs to be refreshed")]
+    HunkOutdated,
+
+    ///
     #[error("io error:{0}")]
     Io(#[from] std::io::Error),
 
+    ///
     #[error("git error:{0}")]
     Git(#[from] git2::Error),
+
+    ///
+    #[error("regex error:{0}")]
+    Regex(#[from] regex::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,100 @@
+use crate::{
+    error::Result,
+    sync::{self},
+    AsyncNotification, CWD,
+};
+use crossbeam_channel::Sender;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+///
+#[derive(Default, Clone, Debug)]
+struct GcState {
+    /// step of the running gc
+    progress: Option<String>,
+    /// error of the last finished run
+    error: Option<String>,
+}
+
+/// runs the steps of `git gc` in the background
+pub struct AsyncGc {
+    state: Arc<Mutex<GcState>>,
+    sender: Sender<AsyncNotification>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl AsyncGc {
+    ///
+    pub fn new(sender: &Sender<AsyncNotification>) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(GcState::default())),
+            sender: sender.clone(),
+            pending: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    ///
+    pub fn is_pending(&self) -> bool {
+        self.pending.load(Ordering::Relaxed) > 0
+    }
+
+    /// current step of the running gc
+    pub fn progress(&self) -> Result<Option<String>> {
+        Ok(self.state.lock()?.progress.clone())
+    }
+
+    /// git's output if the last gc failed
+    pub fn last_error(&self) -> Result<Option<String>> {
+        Ok(self.state.lock()?.error.clone())
+    }
+
+    ///
+    pub fn run(&mut self, aggressive: bool) -> Result<()> {
+        if self.is_pending() {
+            return Ok(());
+        }
+
+        log::trace!("run gc (aggressive: {})", aggressive);
+
+        *self.state.lock()? = GcState::default();
+
+        let arc_state = Arc::clone(&self.state);
+        let sender = self.sender.clone();
+        let arc_pending = Arc::clone(&self.pending);
+
+        self.pending.fetch_add(1, Ordering::Relaxed);
+
+        rayon_core::spawn(move || {
+            let progress_state = Arc::clone(&arc_state);
+            let progress_sender = sender.clone();
+
+            let res = sync::run_gc(CWD, aggressive, |line| {
+                if let Ok(mut state) = progress_state.lock() {
+                    state.progress = Some(line.to_string());
+                }
+
+                progress_sender
+                    .send(AsyncNotification::Gc)
+                    .expect("error sending");
+            });
+
+            if let Err(e) = res {
+                log::error!("gc failed: {}", e);
+
+                if let Ok(mut state) = arc_state.lock() {
+                    state.error = Some(e.to_string());
+                }
+            }
+
+            arc_pending.fetch_sub(1, Ordering::Relaxed);
+
+            sender
+                .send(AsyncNotification::Gc)
+                .expect("error sending");
+        });
+
+        Ok(())
+    }
+}
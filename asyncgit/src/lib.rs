@@ -8,17 +8,23 @@
 
 pub mod cached;
 mod commit_files;
+mod debounce;
 mod diff;
 mod error;
+mod gc;
 mod revlog;
+mod spellcheck;
 mod status;
 pub mod sync;
 mod tags;
 
 pub use crate::{
     commit_files::AsyncCommitFiles,
-    diff::{AsyncDiff, DiffParams, DiffType},
+    diff::{AsyncDiff, DiffParams, DiffType, DIFF_DEBOUNCE},
+    error::Error,
+    gc::AsyncGc,
     revlog::{AsyncLog, FetchStatus},
+    spellcheck::{AsyncSpellCheck, SpellCheckResult},
     status::{AsyncStatus, StatusParams},
     sync::{
         diff::{DiffLine, DiffLineType, FileDiff},
@@ -46,6 +52,10 @@ pub enum AsyncNotification {
     CommitFiles,
     ///
     Tags,
+    ///
+    Gc,
+    ///
+    SpellCheck,
 }
 
 /// current working director `./`
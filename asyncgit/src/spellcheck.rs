@@ -0,0 +1,78 @@
+use crate::{
+    error::Result,
+    sync::{self},
+    AsyncNotification, CWD,
+};
+use crossbeam_channel::Sender;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+/// outcome of a finished spell check,
+/// the findings or what went wrong
+pub type SpellCheckResult = std::result::Result<String, String>;
+
+/// runs the external commit message checker in the background
+pub struct AsyncSpellCheck {
+    last: Arc<Mutex<Option<SpellCheckResult>>>,
+    sender: Sender<AsyncNotification>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl AsyncSpellCheck {
+    ///
+    pub fn new(sender: &Sender<AsyncNotification>) -> Self {
+        Self {
+            last: Arc::new(Mutex::new(None)),
+            sender: sender.clone(),
+            pending: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    ///
+    pub fn is_pending(&self) -> bool {
+        self.pending.load(Ordering::Relaxed) > 0
+    }
+
+    /// result of the last finished check (only returned once)
+    pub fn take_last(&self) -> Result<Option<SpellCheckResult>> {
+        Ok(self.last.lock()?.take())
+    }
+
+    /// checks `msg` with `command`
+    pub fn run(&mut self, command: &str, msg: &str) -> Result<()> {
+        if self.is_pending() {
+            return Ok(());
+        }
+
+        log::trace!("run spell check: {}", command);
+
+        *self.last.lock()? = None;
+
+        let arc_last = Arc::clone(&self.last);
+        let sender = self.sender.clone();
+        let arc_pending = Arc::clone(&self.pending);
+        let command = command.to_string();
+        let msg = msg.to_string();
+
+        self.pending.fetch_add(1, Ordering::Relaxed);
+
+        rayon_core::spawn(move || {
+            let res = sync::spell_check(CWD, &command, &msg)
+                .map_err(|e| e.to_string());
+
+            if let Ok(mut last) = arc_last.lock() {
+                *last = Some(res);
+            }
+
+            arc_pending.fetch_sub(1, Ordering::Relaxed);
+
+            sender
+                .send(AsyncNotification::SpellCheck)
+                .expect("error sending");
+        });
+
+        Ok(())
+    }
+}
@@ -1,8 +1,12 @@
 use crate::{
+    debounce::Debouncer,
     error::Result,
     hash,
-    sync::{self, status::StatusType},
-    AsyncNotification, StatusItem, CWD,
+    sync::{
+        self,
+        status::{StatusOptions, StatusType},
+    },
+    AsyncNotification, StatusItem, CWD, DIFF_DEBOUNCE,
 };
 use crossbeam_channel::Sender;
 use std::{
@@ -11,7 +15,7 @@ use std::{
         atomic::{AtomicUsize, Ordering},
         Arc, Mutex,
     },
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 fn current_tick() -> u64 {
@@ -32,6 +36,7 @@ pub struct StatusParams {
     tick: u64,
     status_type: StatusType,
     include_untracked: bool,
+    options: StatusOptions,
 }
 
 impl StatusParams {
@@ -44,8 +49,14 @@ impl StatusParams {
             tick: current_tick(),
             status_type,
             include_untracked,
+            options: StatusOptions::default(),
         }
     }
+
+    /// use `options` to tune the status (e.g. for large repos)
+    pub fn with_options(self, options: StatusOptions) -> Self {
+        Self { options, ..self }
+    }
 }
 
 struct Request<R, A>(R, Option<A>);
@@ -54,21 +65,79 @@ struct Request<R, A>(R, Option<A>);
 pub struct AsyncStatus {
     current: Arc<Mutex<Request<u64, Status>>>,
     last: Arc<Mutex<Status>>,
-    sender: Sender<AsyncNotification>,
     pending: Arc<AtomicUsize>,
+    debouncer: Debouncer<(StatusParams, u64)>,
+    debounce: Duration,
 }
 
 impl AsyncStatus {
     ///
     pub fn new(sender: Sender<AsyncNotification>) -> Self {
+        let current = Arc::new(Mutex::new(Request(0, None)));
+        let last = Arc::new(Mutex::new(Status::default()));
+        let pending = Arc::new(AtomicUsize::new(0));
+
+        let debouncer = {
+            let current = Arc::clone(&current);
+            let last = Arc::clone(&last);
+            let pending = Arc::clone(&pending);
+            let sender = sender.clone();
+
+            let skip_pending = Arc::clone(&pending);
+            let skip_sender = sender.clone();
+
+            Debouncer::new(
+                move |(params, hash_request): (StatusParams, u64)| {
+                    let arc_current = Arc::clone(&current);
+                    let arc_last = Arc::clone(&last);
+                    let arc_pending = Arc::clone(&pending);
+                    let sender = sender.clone();
+
+                    rayon_core::spawn(move || {
+                        Self::fetch_helper(
+                            params.status_type,
+                            params.include_untracked,
+                            params.options,
+                            hash_request,
+                            arc_current,
+                            arc_last,
+                        )
+                        .expect("failed to fetch status");
+
+                        arc_pending.fetch_sub(1, Ordering::Relaxed);
+
+                        sender
+                            .send(AsyncNotification::Status)
+                            .expect("error sending status");
+                    });
+                },
+                move |_| {
+                    log::trace!("status request superseded");
+
+                    skip_pending.fetch_sub(1, Ordering::Relaxed);
+
+                    skip_sender
+                        .send(AsyncNotification::FinishUnchanged)
+                        .expect("error sending status");
+                },
+            )
+        };
+
         Self {
-            current: Arc::new(Mutex::new(Request(0, None))),
-            last: Arc::new(Mutex::new(Status::default())),
-            sender,
-            pending: Arc::new(AtomicUsize::new(0)),
+            current,
+            last,
+            pending,
+            debouncer,
+            debounce: DIFF_DEBOUNCE,
         }
     }
 
+    /// fetches wait this long before the status is computed and are
+    /// dropped if a newer one arrives in the meantime
+    pub fn set_debounce(&mut self, interval: Duration) {
+        self.debounce = interval;
+    }
+
     ///
     pub fn last(&mut self) -> Result<Status> {
         let last = self.last.lock()?;
@@ -85,11 +154,6 @@ impl AsyncStatus {
         &mut self,
         params: StatusParams,
     ) -> Result<Option<Status>> {
-        if self.is_pending() {
-            log::trace!("request blocked, still pending");
-            return Ok(None);
-        }
-
         let hash_request = hash(&params);
 
         log::trace!(
@@ -110,31 +174,9 @@ impl AsyncStatus {
             current.1 = None;
         }
 
-        let arc_current = Arc::clone(&self.current);
-        let arc_last = Arc::clone(&self.last);
-        let sender = self.sender.clone();
-        let arc_pending = Arc::clone(&self.pending);
-        let status_type = params.status_type;
-        let include_untracked = params.include_untracked;
-
         self.pending.fetch_add(1, Ordering::Relaxed);
 
-        rayon_core::spawn(move || {
-            Self::fetch_helper(
-                status_type,
-                include_untracked,
-                hash_request,
-                arc_current,
-                arc_last,
-            )
-            .expect("failed to fetch status");
-
-            arc_pending.fetch_sub(1, Ordering::Relaxed);
-
-            sender
-                .send(AsyncNotification::Status)
-                .expect("error sending status");
-        });
+        self.debouncer.push((params, hash_request), self.debounce);
 
         Ok(None)
     }
@@ -142,11 +184,18 @@ impl AsyncStatus {
     fn fetch_helper(
         status_type: StatusType,
         include_untracked: bool,
+        options: StatusOptions,
         hash_request: u64,
         arc_current: Arc<Mutex<Request<u64, Status>>>,
         arc_last: Arc<Mutex<Status>>,
     ) -> Result<()> {
-        let res = Self::get_status(status_type, include_untracked)?;
+        let _repo_cache = sync::RepoCache::new();
+
+        let res = Self::get_status(
+            status_type,
+            include_untracked,
+            options,
+        )?;
         log::trace!(
             "status fetched: {} (type: {:?}, untracked: {})",
             hash_request,
@@ -172,12 +221,14 @@ impl AsyncStatus {
     fn get_status(
         status_type: StatusType,
         include_untracked: bool,
+        options: StatusOptions,
     ) -> Result<Status> {
         Ok(Status {
-            items: sync::status::get_status(
+            items: sync::status::get_status_with_options(
                 CWD,
                 status_type,
                 include_untracked,
+                options,
             )?,
         })
     }
@@ -0,0 +1,415 @@
+//! git bisect compatible with the state files written by git
+
+use super::{
+    checkout::{checkout_branch_repo, checkout_commit_repo},
+    utils::{get_head_repo, repo},
+    CommitId,
+};
+use crate::error::{Error, Result};
+use git2::{Oid, Repository, Sort};
+use scopetime::scope_time;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+const BISECT_START: &str = "BISECT_START";
+const BISECT_LOG: &str = "BISECT_LOG";
+const BISECT_TERMS: &str = "BISECT_TERMS";
+const BISECT_NAMES: &str = "BISECT_NAMES";
+const BISECT_EXPECTED_REV: &str = "BISECT_EXPECTED_REV";
+const BISECT_FILES: [&str; 5] = [
+    BISECT_START,
+    BISECT_LOG,
+    BISECT_TERMS,
+    BISECT_NAMES,
+    BISECT_EXPECTED_REV,
+];
+const REF_BAD: &str = "refs/bisect/bad";
+const REF_GOOD_PREFIX: &str = "refs/bisect/good-";
+
+/// verdict on the currently checked out commit
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BisectTerm {
+    ///
+    Good,
+    ///
+    Bad,
+}
+
+impl BisectTerm {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Good => "good",
+            Self::Bad => "bad",
+        }
+    }
+}
+
+/// progress of a bisect
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BisectState {
+    /// `id` was checked out to be tested,
+    /// `remaining` commits are still suspects
+    Testing {
+        ///
+        id: CommitId,
+        ///
+        remaining: usize,
+    },
+    /// the first bad commit was found
+    Found(CommitId),
+}
+
+/// starts a bisect between a `bad` and an older `good` commit and
+/// checks out the first commit to test
+pub fn bisect_start(
+    repo_path: &str,
+    bad: CommitId,
+    good: CommitId,
+) -> Result<BisectState> {
+    scope_time!("bisect_start");
+
+    let repo = repo(repo_path)?;
+
+    if is_bisecting(&repo) {
+        return Err(Error::Generic("already bisecting".into()));
+    }
+
+    // validate before any state is written
+    for id in &[bad, good] {
+        repo.find_commit((*id).into())?;
+    }
+
+    if bad == good
+        || repo.graph_descendant_of(good.into(), bad.into())?
+    {
+        return Err(Error::Generic(
+            "bad commit is reachable from a good commit".into(),
+        ));
+    }
+
+    let head = repo.head()?;
+    let start = if head.is_branch() {
+        head.shorthand().unwrap_or_default().to_string()
+    } else {
+        get_head_repo(&repo)?.to_string()
+    };
+
+    let res = start_bisect(&repo, &start, bad, good);
+
+    if res.is_err() {
+        // leave no half started bisect behind
+        remove_bisect_state(&repo)?;
+    }
+
+    res
+}
+
+fn start_bisect(
+    repo: &Repository,
+    start: &str,
+    bad: CommitId,
+    good: CommitId,
+) -> Result<BisectState> {
+    fs::write(git_file(repo, BISECT_START), format!("{}\n", start))?;
+    fs::write(git_file(repo, BISECT_TERMS), "bad\ngood\n")?;
+    fs::write(git_file(repo, BISECT_NAMES), "\n")?;
+    append_log(
+        repo,
+        &format!(
+            "git bisect start '{}' '{}'\n",
+            bad.to_string(),
+            good.to_string()
+        ),
+    )?;
+
+    mark(repo, bad, BisectTerm::Bad)?;
+    mark(repo, good, BisectTerm::Good)?;
+
+    next_step(repo)
+}
+
+/// marks the currently checked out commit and
+/// checks out the next one to test
+pub fn bisect_mark(
+    repo_path: &str,
+    term: BisectTerm,
+) -> Result<BisectState> {
+    scope_time!("bisect_mark");
+
+    let repo = repo(repo_path)?;
+
+    if !is_bisecting(&repo) {
+        return Err(Error::Generic("not bisecting".into()));
+    }
+
+    mark(&repo, get_head_repo(&repo)?, term)?;
+
+    next_step(&repo)
+}
+
+/// ends the bisect and returns to where it was started from
+pub fn bisect_reset(repo_path: &str) -> Result<()> {
+    scope_time!("bisect_reset");
+
+    let repo = repo(repo_path)?;
+
+    if !is_bisecting(&repo) {
+        return Err(Error::Generic("not bisecting".into()));
+    }
+
+    let start = fs::read_to_string(git_file(&repo, BISECT_START))?;
+    let start = start.trim();
+
+    if repo.find_branch(start, git2::BranchType::Local).is_ok() {
+        checkout_branch_repo(&repo, start)?;
+    } else {
+        checkout_commit_repo(&repo, Oid::from_str(start)?.into())?;
+    }
+
+    remove_bisect_state(&repo)
+}
+
+///
+pub fn is_bisect_in_progress(repo_path: &str) -> Result<bool> {
+    let repo = repo(repo_path)?;
+
+    Ok(is_bisecting(&repo))
+}
+
+fn is_bisecting(repo: &Repository) -> bool {
+    git_file(repo, BISECT_START).exists()
+}
+
+fn remove_bisect_state(repo: &Repository) -> Result<()> {
+    for r in repo.references_glob("refs/bisect/*")? {
+        r?.delete()?;
+    }
+
+    for file in &BISECT_FILES {
+        let path = git_file(repo, file);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn git_file(repo: &Repository, name: &str) -> PathBuf {
+    repo.path().join(name)
+}
+
+fn append_log(repo: &Repository, line: &str) -> Result<()> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(git_file(repo, BISECT_LOG))?
+        .write_all(line.as_bytes())?;
+
+    Ok(())
+}
+
+fn commit_summary(repo: &Repository, id: CommitId) -> Result<String> {
+    let commit = repo.find_commit(id.into())?;
+    Ok(format!(
+        "[{}] {}",
+        id.to_string(),
+        commit.summary().unwrap_or_default()
+    ))
+}
+
+fn mark(
+    repo: &Repository,
+    id: CommitId,
+    term: BisectTerm,
+) -> Result<()> {
+    let refname = match term {
+        BisectTerm::Bad => REF_BAD.to_string(),
+        BisectTerm::Good => {
+            format!("{}{}", REF_GOOD_PREFIX, id.to_string())
+        }
+    };
+
+    repo.reference(&refname, id.into(), true, "bisect")?;
+
+    append_log(
+        repo,
+        &format!(
+            "# {}: {}\ngit bisect {} {}\n",
+            term.as_str(),
+            commit_summary(repo, id)?,
+            term.as_str(),
+            id.to_string()
+        ),
+    )
+}
+
+/// suspects are commits reachable from bad but not from any good,
+/// newest first (the bad commit itself is always the first)
+fn suspects(repo: &Repository) -> Result<Vec<CommitId>> {
+    let bad = repo.refname_to_id(REF_BAD)?;
+
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL)?;
+    walk.push(bad)?;
+
+    for r in repo.references_glob(&format!("{}*", REF_GOOD_PREFIX))? {
+        if let Some(id) = r?.target() {
+            walk.hide(id)?;
+        }
+    }
+
+    walk.map(|id| Ok(id?.into())).collect()
+}
+
+fn next_step(repo: &Repository) -> Result<BisectState> {
+    let suspects = suspects(repo)?;
+
+    match suspects.len() {
+        0 => Err(Error::Generic(
+            "bad commit is reachable from a good commit".into(),
+        )),
+        1 => {
+            let id = suspects[0];
+            append_log(
+                repo,
+                &format!(
+                    "# first bad commit: {}\n",
+                    commit_summary(repo, id)?
+                ),
+            )?;
+            Ok(BisectState::Found(id))
+        }
+        len => {
+            let id = suspects[len / 2];
+
+            checkout_commit_repo(repo, id)?;
+            fs::write(
+                git_file(repo, BISECT_EXPECTED_REV),
+                format!("{}\n", id.to_string()),
+            )?;
+
+            Ok(BisectState::Testing {
+                id,
+                remaining: len - 1,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bisect_mark, bisect_reset, bisect_start,
+        is_bisect_in_progress, BisectState, BisectTerm,
+    };
+    use crate::error::Result;
+    use crate::sync::{
+        commit, get_head, stage_add_file, tests::repo_init_empty,
+        CommitId,
+    };
+    use git2::Oid;
+    use std::{fs::File, io::Write, path::Path};
+
+    #[test]
+    fn test_bisect() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let ids = (0..7)
+            .map(|i| {
+                File::create(&root.join(file_path))
+                    .unwrap()
+                    .write_all(format!("{}", i).as_bytes())
+                    .unwrap();
+                stage_add_file(repo_path, file_path).unwrap();
+                commit(repo_path, &format!("c{}", i)).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let first_bad = 4;
+
+        let mut state = bisect_start(repo_path, ids[6], ids[0])?;
+
+        assert_eq!(is_bisect_in_progress(repo_path)?, true);
+        assert_eq!(repo.path().join("BISECT_START").exists(), true);
+        assert_eq!(
+            repo.find_reference("refs/bisect/bad").is_ok(),
+            true
+        );
+
+        let mut steps = 0;
+        while let BisectState::Testing { id, .. } = state {
+            assert_eq!(get_head(repo_path)?, id);
+
+            let idx = ids.iter().position(|c| *c == id).unwrap();
+            let term = if idx < first_bad {
+                BisectTerm::Good
+            } else {
+                BisectTerm::Bad
+            };
+
+            state = bisect_mark(repo_path, term)?;
+
+            steps += 1;
+            assert!(steps < 7);
+        }
+
+        assert_eq!(state, BisectState::Found(ids[first_bad]));
+        assert!(steps <= 3);
+
+        bisect_reset(repo_path)?;
+
+        assert_eq!(is_bisect_in_progress(repo_path)?, false);
+        assert_eq!(get_head(repo_path)?, ids[6]);
+        assert_eq!(repo.head()?.shorthand(), Some("master"));
+        assert_eq!(repo.references_glob("refs/bisect/*")?.count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bisect_start_invalid() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let ids = (0..2)
+            .map(|i| {
+                File::create(&root.join("foo"))
+                    .unwrap()
+                    .write_all(format!("{}", i).as_bytes())
+                    .unwrap();
+                stage_add_file(repo_path, Path::new("foo")).unwrap();
+                commit(repo_path, &format!("c{}", i)).unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        // good and bad swapped
+        assert_eq!(
+            bisect_start(repo_path, ids[0], ids[1]).is_err(),
+            true
+        );
+        // unknown commit
+        assert_eq!(
+            bisect_start(
+                repo_path,
+                CommitId::new(Oid::zero()),
+                ids[0]
+            )
+            .is_err(),
+            true
+        );
+
+        assert_eq!(is_bisect_in_progress(repo_path)?, false);
+        assert_eq!(repo.path().join("BISECT_LOG").exists(), false);
+        assert_eq!(repo.references_glob("refs/bisect/*")?.count(), 0);
+
+        Ok(())
+    }
+}
@@ -2,8 +2,13 @@
 
 use crate::{
     error::{Error, Result},
-    sync::utils,
+    sync::{
+        config::get_config_string_repo,
+        utils::{self, is_ancestor_repo},
+        CommitId,
+    },
 };
+use git2::{BranchType, ErrorCode, Repository, Sort};
 use scopetime::scope_time;
 
 /// returns the branch-name head is currently pointing to
@@ -27,10 +32,300 @@ pub(crate) fn get_branch_name(repo_path: &str) -> Result<String> {
     Err(Error::NoHead)
 }
 
+/// name of the branch the first commit of a repo ends up on:
+/// `init.defaultBranch`, the branch `HEAD` points to or `master`
+pub fn default_branch_name(repo_path: &str) -> Result<String> {
+    scope_time!("default_branch_name");
+
+    let repo = utils::repo(repo_path)?;
+
+    if let Some(name) =
+        get_config_string_repo(&repo.config()?, "init.defaultBranch")?
+    {
+        return Ok(name);
+    }
+
+    let head = repo.find_reference("HEAD")?;
+    let name = head
+        .symbolic_target()
+        .and_then(|target| target.strip_prefix("refs/heads/"))
+        .unwrap_or("master")
+        .to_string();
+
+    Ok(name)
+}
+
+/// commits on the local `branch` that are not on its upstream
+/// (newest first), fails with `Error::NoUpstream` if none is set
+pub fn get_unpushed_commits(
+    repo_path: &str,
+    branch: &str,
+) -> Result<Vec<CommitId>> {
+    scope_time!("get_unpushed_commits");
+
+    let repo = utils::repo(repo_path)?;
+
+    let branch = repo.find_branch(branch, BranchType::Local)?;
+
+    let upstream = match branch.upstream() {
+        Ok(upstream) => upstream,
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            return Err(Error::NoUpstream(
+                branch.name()?.unwrap_or_default().to_string(),
+            ));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let (local_id, upstream_id) =
+        match (branch.get().target(), upstream.get().target()) {
+            (Some(local), Some(upstream)) => (local, upstream),
+            _ => {
+                return Err(Error::Generic(
+                    "branch target not found".into(),
+                ))
+            }
+        };
+
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(Sort::TOPOLOGICAL)?;
+    walk.push(local_id)?;
+    walk.hide(upstream_id)?;
+
+    walk.map(|id| Ok(id?.into())).collect()
+}
+
+/// url of the remote the upstream of the local `branch` belongs to,
+/// as configured (ssh and https urls are not normalized),
+/// None if it has no upstream or the remote has no url
+pub fn get_upstream_url(
+    repo_path: &str,
+    branch: &str,
+) -> Result<Option<String>> {
+    scope_time!("get_upstream_url");
+
+    let repo = utils::repo(repo_path)?;
+
+    get_upstream_url_repo(&repo, branch)
+}
+
+pub(crate) fn get_upstream_url_repo(
+    repo: &Repository,
+    branch: &str,
+) -> Result<Option<String>> {
+    let refname = format!("refs/heads/{}", branch);
+
+    let remote = match repo.branch_upstream_remote(&refname) {
+        Ok(remote) => remote,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let remote = match remote.as_str() {
+        Some(remote) => repo.find_remote(remote)?,
+        None => return Ok(None),
+    };
+
+    let url = remote.url().map(String::from);
+
+    Ok(url)
+}
+
+/// where a detached `HEAD` is relative to the local branches
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetachedInfo {
+    /// commit `HEAD` points to
+    pub commit: CommitId,
+    /// closest branch that either contains `commit` or that
+    /// `commit` is based on, None if no branch is related
+    pub nearest_branch: Option<String>,
+    /// commits `HEAD` is ahead of `nearest_branch`
+    pub ahead: usize,
+    /// commits `HEAD` is behind `nearest_branch`
+    pub behind: usize,
+}
+
+/// None unless `HEAD` is detached
+pub fn detached_head_info(
+    repo_path: &str,
+) -> Result<Option<DetachedInfo>> {
+    scope_time!("detached_head_info");
+
+    let repo = utils::repo(repo_path)?;
+
+    if !repo.head_detached()? {
+        return Ok(None);
+    }
+
+    let head = repo.head()?.peel_to_commit()?.id();
+
+    let mut nearest: Option<(String, usize, usize)> = None;
+
+    for b in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = b?;
+
+        let (name, tip) =
+            match (branch.name()?, branch.get().target()) {
+                (Some(name), Some(tip)) => (name.to_string(), tip),
+                _ => continue,
+            };
+
+        // only branches on the same line of history count
+        if !is_ancestor_repo(&repo, tip.into(), head.into())?
+            && !is_ancestor_repo(&repo, head.into(), tip.into())?
+        {
+            continue;
+        }
+
+        let (ahead, behind) = repo.graph_ahead_behind(head, tip)?;
+
+        let closer = nearest.as_ref().map_or(true, |n| {
+            ahead + behind < n.1 + n.2
+                || (ahead + behind == n.1 + n.2 && name < n.0)
+        });
+
+        if closer {
+            nearest = Some((name, ahead, behind));
+        }
+    }
+
+    let (nearest_branch, ahead, behind) = match nearest {
+        Some((name, ahead, behind)) => (Some(name), ahead, behind),
+        None => (None, 0, 0),
+    };
+
+    Ok(Some(DetachedInfo {
+        commit: head.into(),
+        nearest_branch,
+        ahead,
+        behind,
+    }))
+}
+
+/// local branches (and remote ones if `include_remote`) whose tip is
+/// `commit` or a descendant of it, sorted by name
+pub fn branches_containing(
+    repo_path: &str,
+    commit: CommitId,
+    include_remote: bool,
+) -> Result<Vec<String>> {
+    scope_time!("branches_containing");
+
+    let repo = utils::repo(repo_path)?;
+
+    let filter = if include_remote {
+        None
+    } else {
+        Some(BranchType::Local)
+    };
+
+    let mut res = Vec::new();
+
+    for b in repo.branches(filter)? {
+        let (branch, _) = b?;
+
+        // symbolic refs like `origin/HEAD` have no direct target
+        let (name, tip) =
+            match (branch.name()?, branch.get().target()) {
+                (Some(name), Some(tip)) => (name.to_string(), tip),
+                _ => continue,
+            };
+
+        if is_ancestor_repo(&repo, commit, tip.into())? {
+            res.push(name);
+        }
+    }
+
+    res.sort();
+
+    Ok(res)
+}
+
+/// remote-tracking branches of `remote` (like `origin/feature`)
+/// sorted by name, the symbolic `origin/HEAD` is left out
+pub fn get_remote_branches(
+    repo_path: &str,
+    remote: &str,
+) -> Result<Vec<String>> {
+    scope_time!("get_remote_branches");
+
+    let repo = utils::repo(repo_path)?;
+
+    let prefix = format!("{}/", remote);
+    let mut res = Vec::new();
+
+    for b in repo.branches(Some(BranchType::Remote))? {
+        let (branch, _) = b?;
+
+        if branch.get().target().is_none() {
+            continue;
+        }
+
+        if let Some(name) = branch.name()? {
+            if name.starts_with(&prefix) {
+                res.push(name.to_string());
+            }
+        }
+    }
+
+    res.sort();
+
+    Ok(res)
+}
+
+/// local branches fully merged into the branch `into` (`HEAD` if
+/// None), neither the target nor the current branch are included
+pub fn merged_branches(
+    repo_path: &str,
+    into: Option<&str>,
+) -> Result<Vec<String>> {
+    scope_time!("merged_branches");
+
+    let repo = utils::repo(repo_path)?;
+
+    let (target_name, target) = match into {
+        Some(name) => {
+            let branch = repo.find_branch(name, BranchType::Local)?;
+            (
+                Some(name.to_string()),
+                branch.get().peel_to_commit()?.id(),
+            )
+        }
+        None => (None, repo.head()?.peel_to_commit()?.id()),
+    };
+
+    let mut res = Vec::new();
+
+    for b in repo.branches(Some(BranchType::Local))? {
+        let (branch, _) = b?;
+
+        let name = match branch.name()? {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+
+        if branch.is_head() || target_name.as_ref() == Some(&name) {
+            continue;
+        }
+
+        if let Some(id) = branch.get().target() {
+            if is_ancestor_repo(&repo, id.into(), target.into())? {
+                res.push(name);
+            }
+        }
+    }
+
+    Ok(res)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::sync::tests::{repo_init, repo_init_empty};
+    use crate::sync::{
+        commit, stage_add_file,
+        tests::{repo_init, repo_init_empty},
+    };
+    use std::{fs::File, io::Write, path::Path};
 
     #[test]
     fn test_smoke() {
@@ -55,4 +350,206 @@ mod tests {
             Err(Error::NoHead)
         ));
     }
+
+    #[test]
+    fn test_default_branch_name() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        repo.config()?.set_str("init.defaultBranch", "main")?;
+
+        assert_eq!(default_branch_name(repo_path)?, "main");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unpushed_commits() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let head = repo.head()?.peel_to_commit()?;
+        repo.branch("feature", &head, false)?;
+
+        repo.remote("origin", "https://example.com/repo.git")?;
+        repo.reference(
+            "refs/remotes/origin/master",
+            head.id(),
+            false,
+            "fake fetch",
+        )?;
+        repo.find_branch("master", BranchType::Local)?
+            .set_upstream(Some("origin/master"))?;
+
+        assert_eq!(
+            get_unpushed_commits(repo_path, "master")?,
+            vec![]
+        );
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path)?;
+        let c1 = commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?.write_all(b"b")?;
+        stage_add_file(repo_path, file_path)?;
+        let c2 = commit(repo_path, "c2")?;
+
+        assert_eq!(
+            get_unpushed_commits(repo_path, "master")?,
+            vec![c2, c1]
+        );
+
+        assert!(matches!(
+            get_unpushed_commits(repo_path, "feature"),
+            Err(Error::NoUpstream(name)) if name == "feature"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_upstream_url() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        assert_eq!(get_upstream_url(repo_path, "master")?, None);
+
+        let head = repo.head()?.peel_to_commit()?;
+        repo.remote("origin", "git@github.com:org/repo.git")?;
+        repo.reference(
+            "refs/remotes/origin/master",
+            head.id(),
+            false,
+            "fake fetch",
+        )?;
+        repo.find_branch("master", BranchType::Local)?
+            .set_upstream(Some("origin/master"))?;
+
+        assert_eq!(
+            get_upstream_url(repo_path, "master")?.as_deref(),
+            Some("git@github.com:org/repo.git")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merged_branches() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let head = repo.head()?.peel_to_commit()?;
+        repo.branch("merged", &head, false)?;
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path)?;
+        let c1 = commit(repo_path, "c1")?;
+
+        // `unmerged` is ahead of `master`
+        let c1 = repo.find_commit(c1.into())?;
+        repo.branch("develop", &c1, false)?;
+        repo.branch("unmerged", &c1, false)?;
+        repo.set_head("refs/heads/unmerged")?;
+        File::create(&root.join(file_path))?.write_all(b"b")?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c2")?;
+        repo.set_head("refs/heads/develop")?;
+
+        assert_eq!(
+            merged_branches(repo_path, Some("master"))?,
+            vec!["merged"]
+        );
+        assert_eq!(
+            merged_branches(repo_path, None)?,
+            vec!["master", "merged"]
+        );
+        assert_eq!(
+            merged_branches(repo_path, Some("unmerged"))?,
+            vec!["master", "merged"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detached_head_info() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        assert_eq!(detached_head_info(repo_path)?, None);
+
+        let base = repo.head()?.peel_to_commit()?;
+        repo.branch("main", &base, false)?;
+
+        let mut last = base.id().into();
+        for i in 0..3 {
+            File::create(&root.join("foo.txt"))?
+                .write_all(format!("{}", i).as_bytes())?;
+            stage_add_file(repo_path, Path::new("foo.txt"))?;
+            last = commit(repo_path, "c")?;
+        }
+
+        repo.set_head_detached(last.into())?;
+
+        let info = detached_head_info(repo_path)?.unwrap();
+
+        assert_eq!(info.commit, last);
+        // master contains the commit itself
+        assert_eq!(info.nearest_branch.as_deref(), Some("master"));
+        assert_eq!((info.ahead, info.behind), (0, 0));
+
+        repo.find_branch("master", BranchType::Local)?.delete()?;
+
+        let info = detached_head_info(repo_path)?.unwrap();
+
+        assert_eq!(info.nearest_branch.as_deref(), Some("main"));
+        assert_eq!((info.ahead, info.behind), (3, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_branches_containing() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let base = repo.head()?.peel_to_commit()?;
+        repo.branch("old", &base, false)?;
+
+        File::create(&root.join("foo.txt"))?.write_all(b"fix")?;
+        stage_add_file(repo_path, Path::new("foo.txt"))?;
+        let fix = commit(repo_path, "fix")?;
+
+        let head = repo.head()?.peel_to_commit()?;
+        repo.branch("release", &head, false)?;
+        repo.reference(
+            "refs/remotes/origin/release",
+            head.id(),
+            false,
+            "test",
+        )?;
+
+        assert_eq!(
+            branches_containing(repo_path, fix, false)?,
+            vec!["master", "release"]
+        );
+        assert_eq!(
+            branches_containing(repo_path, fix, true)?,
+            vec!["master", "origin/release", "release"]
+        );
+        assert_eq!(
+            branches_containing(repo_path, base.id().into(), false)?,
+            vec!["master", "old", "release"]
+        );
+
+        Ok(())
+    }
 }
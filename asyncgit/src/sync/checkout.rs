@@ -0,0 +1,198 @@
+use super::{utils::repo, CommitId};
+use crate::error::{Error, Result};
+use git2::{build::CheckoutBuilder, BranchType, Repository};
+use scopetime::scope_time;
+
+/// checkout a commit detaching `HEAD`,
+/// fails if local changes would be overwritten
+pub fn checkout_commit(repo_path: &str, id: CommitId) -> Result<()> {
+    scope_time!("checkout_commit");
+
+    let repo = repo(repo_path)?;
+
+    checkout_commit_repo(&repo, id)
+}
+
+/// checkout the local branch `name`,
+/// fails if local changes would be overwritten
+pub fn checkout_branch(repo_path: &str, name: &str) -> Result<()> {
+    scope_time!("checkout_branch");
+
+    let repo = repo(repo_path)?;
+
+    checkout_branch_repo(&repo, name)
+}
+
+/// creates the local branch `local_name` tracking `remote_branch`
+/// (like `origin/feature`) and checks it out. fails if the local
+/// branch exists already or local changes would be overwritten
+pub fn checkout_remote_branch(
+    repo_path: &str,
+    remote_branch: &str,
+    local_name: &str,
+) -> Result<()> {
+    scope_time!("checkout_remote_branch");
+
+    let repo = repo(repo_path)?;
+
+    if repo.find_branch(local_name, BranchType::Local).is_ok() {
+        return Err(Error::Generic(format!(
+            "branch '{}' already exists",
+            local_name
+        )));
+    }
+
+    let commit = repo
+        .find_branch(remote_branch, BranchType::Remote)?
+        .get()
+        .peel_to_commit()?;
+
+    let mut branch = repo.branch(local_name, &commit, false)?;
+    branch.set_upstream(Some(remote_branch))?;
+
+    // do not leave the new branch behind if we cannot switch to it
+    if let Err(e) = checkout_branch_repo(&repo, local_name) {
+        branch.delete()?;
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+pub(crate) fn checkout_commit_repo(
+    repo: &Repository,
+    id: CommitId,
+) -> Result<()> {
+    let commit = repo.find_commit(id.into())?;
+
+    repo.checkout_tree(
+        commit.as_object(),
+        Some(CheckoutBuilder::new().safe()),
+    )?;
+    repo.set_head_detached(id.into())?;
+
+    Ok(())
+}
+
+pub(crate) fn checkout_branch_repo(
+    repo: &Repository,
+    name: &str,
+) -> Result<()> {
+    let refname = format!("refs/heads/{}", name);
+    let commit = repo.find_reference(&refname)?.peel_to_commit()?;
+
+    repo.checkout_tree(
+        commit.as_object(),
+        Some(CheckoutBuilder::new().safe()),
+    )?;
+    repo.set_head(&refname)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        checkout_branch, checkout_commit, checkout_remote_branch,
+    };
+    use crate::error::Result;
+    use crate::sync::{
+        commit, get_head, get_remote_branches, stage_add_file,
+        tests::{repo_init, repo_init_empty},
+    };
+    use git2::{build::RepoBuilder, BranchType};
+    use std::{
+        fs::{self, File},
+        io::Write,
+        path::Path,
+    };
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_checkout() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path)?;
+        let c1 = commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?.write_all(b"b")?;
+        stage_add_file(repo_path, file_path)?;
+        let c2 = commit(repo_path, "c2")?;
+
+        checkout_commit(repo_path, c1)?;
+
+        assert_eq!(get_head(repo_path)?, c1);
+        assert_eq!(repo.head_detached()?, true);
+        assert_eq!(fs::read_to_string(root.join(file_path))?, "a");
+
+        checkout_branch(repo_path, "master")?;
+
+        assert_eq!(get_head(repo_path)?, c2);
+        assert_eq!(repo.head_detached()?, false);
+        assert_eq!(fs::read_to_string(root.join(file_path))?, "b");
+
+        // local changes are not overwritten
+        File::create(&root.join(file_path))?.write_all(b"c")?;
+        assert_eq!(checkout_commit(repo_path, c1).is_err(), true);
+        assert_eq!(fs::read_to_string(root.join(file_path))?, "c");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkout_remote_branch() -> Result<()> {
+        let (_remote_td, remote) = repo_init().unwrap();
+        let remote_root = remote.path().parent().unwrap();
+        let remote_path = remote_root.as_os_str().to_str().unwrap();
+
+        let head = remote.head()?.peel_to_commit()?;
+        remote.branch("feature", &head, false)?;
+        checkout_branch(remote_path, "feature")?;
+        File::create(&remote_root.join("foo"))?.write_all(b"a")?;
+        stage_add_file(remote_path, Path::new("foo"))?;
+        let tip = commit(remote_path, "feature")?;
+        checkout_branch(remote_path, "master")?;
+
+        let td = TempDir::new()?;
+        let repo =
+            RepoBuilder::new().clone(remote_path, td.path())?;
+        let repo_path = td.path().to_str().unwrap();
+
+        assert_eq!(
+            get_remote_branches(repo_path, "origin")?,
+            vec!["origin/feature", "origin/master"]
+        );
+        assert_eq!(
+            get_remote_branches(repo_path, "upstream")?.is_empty(),
+            true
+        );
+
+        checkout_remote_branch(repo_path, "origin/feature", "feat")?;
+
+        assert_eq!(get_head(repo_path)?, tip);
+        assert_eq!(td.path().join("foo").exists(), true);
+
+        let branch = repo.find_branch("feat", BranchType::Local)?;
+        assert_eq!(branch.is_head(), true);
+        assert_eq!(
+            branch.upstream()?.name()?,
+            Some("origin/feature")
+        );
+
+        assert_eq!(
+            checkout_remote_branch(
+                repo_path,
+                "origin/master",
+                "feat"
+            )
+            .is_err(),
+            true
+        );
+
+        Ok(())
+    }
+}
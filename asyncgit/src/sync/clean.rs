@@ -0,0 +1,273 @@
+use super::utils::{repo, work_dir};
+use crate::error::Result;
+use git2::{Repository, Status, StatusOptions, StatusShow};
+use scopetime::scope_time;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// removes untracked files like `git clean` and returns their paths
+/// (relative to the workdir, untracked dirs end with a `/`).
+///
+/// untracked dirs are only removed if `include_dirs` is set,
+/// ignored files only if `include_ignored` is set and nothing is
+/// deleted on a `dry_run`. tracked files are never touched.
+///
+/// symlinks are removed, never followed. untracked nested git repos
+/// are kept unless `force` is set (like `git clean -ff`)
+pub fn clean_workdir(
+    repo_path: &str,
+    include_dirs: bool,
+    include_ignored: bool,
+    force: bool,
+    dry_run: bool,
+) -> Result<Vec<PathBuf>> {
+    scope_time!("clean_workdir");
+
+    let repo = repo(repo_path)?;
+    let work_dir = work_dir(&repo)?;
+
+    let mut options = StatusOptions::default();
+    options
+        .show(StatusShow::Workdir)
+        .include_untracked(true)
+        .recurse_untracked_dirs(false)
+        .include_ignored(include_ignored)
+        .recurse_ignored_dirs(false)
+        .exclude_submodules(true);
+
+    let statuses = repo.statuses(Some(&mut options))?;
+
+    let mut res = Vec::new();
+
+    for e in statuses.iter() {
+        let status = e.status();
+        if !status.intersects(Status::WT_NEW | Status::IGNORED) {
+            continue;
+        }
+
+        let path = match e.path() {
+            Some(path) => path,
+            None => continue,
+        };
+
+        let is_dir = path.ends_with('/');
+        if is_dir && !include_dirs {
+            continue;
+        }
+
+        if !include_ignored && repo.is_path_ignored(path)? {
+            continue;
+        }
+
+        let abs_path = work_dir.join(path);
+
+        if is_dir && !force && is_nested_repo(&abs_path) {
+            continue;
+        }
+
+        if !dry_run {
+            if is_dir {
+                remove_dir(
+                    &repo,
+                    work_dir,
+                    &abs_path,
+                    include_ignored,
+                    force,
+                )?;
+            } else {
+                fs::remove_file(abs_path)?;
+            }
+        }
+
+        res.push(PathBuf::from(path));
+    }
+
+    Ok(res)
+}
+
+fn is_nested_repo(dir: &Path) -> bool {
+    fs::symlink_metadata(dir.join(".git")).is_ok()
+}
+
+/// removes an untracked dir, keeping ignored files in it
+/// (and therefore their parent dirs) unless `include_ignored`
+/// and nested repos unless `force`. symlinks are unlinked,
+/// never followed
+fn remove_dir(
+    repo: &Repository,
+    work_dir: &Path,
+    dir: &Path,
+    include_ignored: bool,
+    force: bool,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        let rel_path = path.strip_prefix(work_dir).unwrap_or(&path);
+        if !include_ignored && repo.is_path_ignored(rel_path)? {
+            continue;
+        }
+
+        // `file_type` does not traverse symlinks
+        if entry.file_type()?.is_dir() {
+            if !force && is_nested_repo(&path) {
+                continue;
+            }
+            remove_dir(
+                repo,
+                work_dir,
+                &path,
+                include_ignored,
+                force,
+            )?;
+        } else {
+            fs::remove_file(path)?;
+        }
+    }
+
+    if fs::read_dir(dir)?.next().is_none() {
+        fs::remove_dir(dir)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clean_workdir;
+    use crate::error::Result;
+    use crate::sync::{
+        commit, stage_add_file,
+        tests::{get_statuses, repo_init},
+    };
+    use git2::Repository;
+    use std::{
+        fs::{self, File},
+        io::Write,
+        path::{Path, PathBuf},
+    };
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_clean() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join("tracked.txt"))?.write_all(b"a")?;
+        File::create(&root.join(".gitignore"))?
+            .write_all(b"*.log\n")?;
+        stage_add_file(repo_path, Path::new("tracked.txt"))?;
+        stage_add_file(repo_path, Path::new(".gitignore"))?;
+        commit(repo_path, "c1")?;
+
+        // a modified tracked file must survive
+        File::create(&root.join("tracked.txt"))?.write_all(b"b")?;
+        File::create(&root.join("untracked.txt"))?.write_all(b"c")?;
+        File::create(&root.join("foo.log"))?.write_all(b"d")?;
+        fs::create_dir_all(root.join("dir/sub"))?;
+        File::create(&root.join("dir/sub/file.txt"))?
+            .write_all(b"e")?;
+        File::create(&root.join("dir/keep.log"))?.write_all(b"f")?;
+
+        let preview =
+            clean_workdir(repo_path, true, false, false, true)?;
+        assert_eq!(
+            preview,
+            vec![
+                PathBuf::from("dir/"),
+                PathBuf::from("untracked.txt")
+            ]
+        );
+        assert_eq!(root.join("untracked.txt").exists(), true);
+        assert_eq!(root.join("dir/sub/file.txt").exists(), true);
+
+        assert_eq!(
+            clean_workdir(repo_path, false, false, false, false)?,
+            vec![PathBuf::from("untracked.txt")]
+        );
+        assert_eq!(root.join("untracked.txt").exists(), false);
+        assert_eq!(root.join("dir/sub/file.txt").exists(), true);
+
+        clean_workdir(repo_path, true, false, false, false)?;
+        assert_eq!(root.join("dir/sub").exists(), false);
+        assert_eq!(root.join("dir/keep.log").exists(), true);
+        assert_eq!(root.join("foo.log").exists(), true);
+        assert_eq!(get_statuses(repo_path), (1, 0));
+
+        clean_workdir(repo_path, true, true, false, false)?;
+        assert_eq!(root.join("dir").exists(), false);
+        assert_eq!(root.join("foo.log").exists(), false);
+        assert_eq!(root.join("tracked.txt").exists(), true);
+        assert_eq!(get_statuses(repo_path), (1, 0));
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_clean_keeps_symlink_targets() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let outside = TempDir::new()?;
+        File::create(&outside.path().join("precious.txt"))?
+            .write_all(b"a")?;
+
+        fs::create_dir_all(root.join("dir"))?;
+        std::os::unix::fs::symlink(
+            outside.path(),
+            root.join("link"),
+        )?;
+        std::os::unix::fs::symlink(
+            outside.path(),
+            root.join("dir/link"),
+        )?;
+
+        clean_workdir(repo_path, true, true, false, false)?;
+
+        assert_eq!(
+            fs::symlink_metadata(root.join("link")).is_err(),
+            true
+        );
+        assert_eq!(root.join("dir").exists(), false);
+        assert_eq!(
+            outside.path().join("precious.txt").exists(),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clean_nested_repo() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        Repository::init(root.join("nested"))?;
+        File::create(&root.join("nested/file.txt"))?
+            .write_all(b"a")?;
+        fs::create_dir_all(root.join("dir"))?;
+        Repository::init(root.join("dir/inner"))?;
+        File::create(&root.join("dir/other.txt"))?.write_all(b"b")?;
+
+        clean_workdir(repo_path, true, true, false, false)?;
+
+        assert_eq!(root.join("nested/file.txt").exists(), true);
+        assert_eq!(root.join("nested/.git").exists(), true);
+        assert_eq!(root.join("dir/other.txt").exists(), false);
+        assert_eq!(root.join("dir/inner/.git").exists(), true);
+
+        clean_workdir(repo_path, true, true, true, false)?;
+
+        assert_eq!(root.join("nested").exists(), false);
+        assert_eq!(root.join("dir").exists(), false);
+
+        Ok(())
+    }
+}
@@ -1,13 +1,20 @@
-use super::{get_head, utils::repo, CommitId};
-use crate::error::Result;
-use git2::{ErrorCode, ObjectType, Repository, Signature};
+use super::{
+    config::get_config_string_repo, get_head, utils::repo, CommitId,
+    CommitSignature,
+};
+use crate::error::{Error, Result};
+use git2::{
+    ObjectType, Oid, Repository, RepositoryState, Signature, Time,
+};
 use scopetime::scope_time;
+use std::fs;
 
-///
+/// amends the commit `id` with the current stage,
+/// keeps the original message if `msg` is `None`
 pub fn amend(
     repo_path: &str,
     id: CommitId,
-    msg: &str,
+    msg: Option<&str>,
 ) -> Result<CommitId> {
     scope_time!("commit");
 
@@ -23,61 +30,264 @@ pub fn amend(
         None,
         None,
         None,
-        Some(msg),
+        msg,
         Some(&tree),
     )?;
 
     Ok(CommitId::new(new_id))
 }
 
+/// rewrites the author of commit `id` (the tip of `HEAD`) keeping
+/// tree, message and committer, the original author time is kept
+/// if `time` (seconds since epoch) is None
+pub fn amend_author(
+    repo_path: &str,
+    id: CommitId,
+    name: &str,
+    email: &str,
+    time: Option<i64>,
+) -> Result<CommitId> {
+    scope_time!("amend_author");
+
+    let repo = repo(repo_path)?;
+    let commit = repo.find_commit(id.into())?;
+
+    let original = commit.author().when();
+    let time = time.map_or(original, |time| {
+        Time::new(time, original.offset_minutes())
+    });
+
+    let author = Signature::new(name, email, &time)?;
+
+    let new_id = commit.amend(
+        Some("HEAD"),
+        Some(&author),
+        None,
+        None,
+        None,
+        None,
+    )?;
+
+    Ok(CommitId::new(new_id))
+}
+
 /// Wrap Repository::signature to allow unknown user.name.
 ///
 /// See <https://github.com/extrawurst/gitui/issues/79>.
+/// Fails with `Error::MissingIdentity` if no `user.email` is set.
 fn signature_allow_undefined_name(
     repo: &Repository,
-) -> std::result::Result<Signature<'_>, git2::Error> {
-    match repo.signature() {
-        Err(e) if e.code() == ErrorCode::NotFound => {
-            let config = repo.config()?;
-            Signature::now(
-                config.get_str("user.name").unwrap_or("unknown"),
-                config.get_str("user.email")?,
-            )
-        }
+) -> Result<Signature<'static>> {
+    let config = repo.config()?;
+    let non_empty = |key| -> Result<Option<String>> {
+        Ok(get_config_string_repo(&config, key)?
+            .filter(|v| !v.trim().is_empty()))
+    };
 
-        v => v,
-    }
+    let email =
+        non_empty("user.email")?.ok_or(Error::MissingIdentity)?;
+    let name = non_empty("user.name")?
+        .unwrap_or_else(|| String::from("unknown"));
+
+    Ok(Signature::now(&name, &email)?)
+}
+
+/// optional settings for `commit_with_options`
+#[derive(Default, Clone, Debug)]
+pub struct CommitOptions {
+    /// use this instead of the configured identity as author
+    pub author: Option<CommitSignature>,
+    /// use this instead of the configured identity as committer
+    pub committer: Option<CommitSignature>,
+    /// create the commit even if nothing changed
+    pub allow_empty: bool,
+    /// parents of the new commit (more than one creates a merge)
+    /// instead of the current `HEAD`, which is moved to it anyway
+    pub parents: Option<Vec<CommitId>>,
+}
+
+fn to_signature<'a>(
+    s: &CommitSignature,
+) -> std::result::Result<Signature<'a>, git2::Error> {
+    Signature::new(
+        &s.name,
+        &s.email,
+        &Time::new(s.time, s.time_offset),
+    )
 }
 
 /// this does not run any git hooks
 pub fn commit(repo_path: &str, msg: &str) -> Result<CommitId> {
+    commit_with_options(repo_path, msg, &CommitOptions::default())
+}
+
+/// like `commit` but allows overriding defaults via `options`
+pub fn commit_with_options(
+    repo_path: &str,
+    msg: &str,
+    options: &CommitOptions,
+) -> Result<CommitId> {
     scope_time!("commit");
 
     let repo = repo(repo_path)?;
 
-    let signature = signature_allow_undefined_name(&repo)?;
+    // the configured identity is not needed if both are overridden
+    let signature = match (&options.author, &options.committer) {
+        (Some(_), Some(_)) => None,
+        _ => Some(signature_allow_undefined_name(&repo)?),
+    };
+    let author = match &options.author {
+        Some(author) => to_signature(author)?,
+        None => signature.clone().ok_or(Error::MissingIdentity)?,
+    };
+    let committer = match &options.committer {
+        Some(committer) => to_signature(committer)?,
+        None => signature.ok_or(Error::MissingIdentity)?,
+    };
     let mut index = repo.index()?;
     let tree_id = index.write_tree()?;
     let tree = repo.find_tree(tree_id)?;
 
-    let parents = if let Ok(id) = get_head(repo_path) {
+    // no explicit parents at all means the usual `HEAD` commit
+    let explicit_parents =
+        options.parents.as_ref().filter(|ids| !ids.is_empty());
+
+    let parents = if let Some(ids) = explicit_parents {
+        ids.iter()
+            .map(|id| {
+                repo.find_commit((*id).into()).map_err(|_| {
+                    Error::Generic(format!(
+                        "parent commit {} not found",
+                        id.to_string()
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?
+    } else if let Ok(id) = get_head(repo_path) {
         vec![repo.find_commit(id.into())?]
     } else {
         Vec::new()
     };
 
+    // a merge may very well keep the tree of its first parent
+    if !options.allow_empty && parents.len() <= 1 {
+        let unchanged = match parents.first() {
+            Some(parent) => parent.tree_id() == tree_id,
+            None => tree.is_empty(),
+        };
+
+        if unchanged {
+            return Err(Error::NothingToCommit);
+        }
+    }
+
     let parents = parents.iter().collect::<Vec<_>>();
 
-    Ok(repo
-        .commit(
-            Some("HEAD"),
-            &signature,
-            &signature,
-            msg,
-            &tree,
-            parents.as_slice(),
-        )?
-        .into())
+    // libgit2 refuses to update `HEAD` unless it is the first parent
+    let update_ref = if explicit_parents.is_some() {
+        None
+    } else {
+        Some("HEAD")
+    };
+
+    let id = repo.commit(
+        update_ref,
+        &author,
+        &committer,
+        msg,
+        &tree,
+        parents.as_slice(),
+    )?;
+
+    if update_ref.is_none() {
+        move_head(&repo, id, msg)?;
+    }
+
+    Ok(id.into())
+}
+
+/// points `HEAD` (or the branch it refers to) at `id`
+fn move_head(repo: &Repository, id: Oid, msg: &str) -> Result<()> {
+    let head = repo.find_reference("HEAD")?;
+
+    match head.symbolic_target() {
+        Some(branch) => {
+            let log_msg = format!(
+                "commit: {}",
+                msg.lines().next().unwrap_or_default()
+            );
+            repo.reference(branch, id, true, &log_msg)?;
+        }
+        None => repo.set_head_detached(id)?,
+    }
+
+    Ok(())
+}
+
+/// message git prepared for the next commit: `MERGE_MSG` during a
+/// merge (cherry-pick, revert) or `SQUASH_MSG` after a squash merge
+pub fn get_merge_message(repo_path: &str) -> Result<Option<String>> {
+    scope_time!("get_merge_message");
+
+    let repo = repo(repo_path)?;
+
+    let merge_msg = repo.path().join("MERGE_MSG");
+    if repo.state() != RepositoryState::Clean && merge_msg.exists() {
+        return Ok(Some(fs::read_to_string(merge_msg)?));
+    }
+
+    let squash_msg = repo.path().join("SQUASH_MSG");
+    if squash_msg.exists() {
+        return Ok(Some(fs::read_to_string(squash_msg)?));
+    }
+
+    Ok(None)
+}
+
+/// commits being merged into `HEAD` (from `MERGE_HEAD`),
+/// empty if no merge is in progress
+pub fn merge_heads(repo_path: &str) -> Result<Vec<CommitId>> {
+    scope_time!("merge_heads");
+
+    let repo = repo(repo_path)?;
+
+    if repo.state() != RepositoryState::Merge {
+        return Ok(Vec::new());
+    }
+
+    let merge_head =
+        fs::read_to_string(repo.path().join("MERGE_HEAD"))?;
+
+    merge_head
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(|l| Ok(CommitId::new(Oid::from_str(l)?)))
+        .collect()
+}
+
+/// after a commit concluded the merge (cherry-pick, revert) in
+/// progress: drops `MERGE_HEAD`, `MERGE_MSG` and friends as well as
+/// a prepared `SQUASH_MSG` like git does on commit.
+/// a rebase or bisect in progress is left alone
+pub fn cleanup_merge_state(repo_path: &str) -> Result<()> {
+    scope_time!("cleanup_merge_state");
+
+    let repo = repo(repo_path)?;
+
+    match repo.state() {
+        RepositoryState::Merge
+        | RepositoryState::Revert
+        | RepositoryState::CherryPick => repo.cleanup_state()?,
+        _ => (),
+    }
+
+    let squash_msg = repo.path().join("SQUASH_MSG");
+    if squash_msg.exists() {
+        fs::remove_file(squash_msg)?;
+    }
+
+    Ok(())
 }
 
 /// Tag a commit.
@@ -104,17 +314,29 @@ pub fn tag(
 #[cfg(test)]
 mod tests {
 
-    use crate::error::Result;
+    use crate::error::{Error, Result};
     use crate::sync::{
-        commit, get_commit_details, get_commit_files, stage_add_file,
+        commit, get_commit_details, get_commit_files,
+        get_commit_parents, stage_add_file,
         tags::get_tags,
         tests::{get_statuses, repo_init, repo_init_empty},
         utils::get_head,
-        LogWalker,
+        CommitId, CommitSignature, LogWalker,
+    };
+    use commit::{
+        amend, amend_author, cleanup_merge_state,
+        commit_with_options, get_merge_message, merge_heads, tag,
+        CommitOptions,
+    };
+    use git2::{
+        build::CheckoutBuilder, BranchType, Oid, Repository,
+        RepositoryState,
+    };
+    use std::{
+        fs::{self, File},
+        io::Write,
+        path::Path,
     };
-    use commit::{amend, tag};
-    use git2::Repository;
-    use std::{fs::File, io::Write, path::Path};
 
     fn count_commits(repo: &Repository, max: usize) -> usize {
         let mut items = Vec::new();
@@ -190,7 +412,7 @@ mod tests {
 
         stage_add_file(repo_path, file_path2)?;
 
-        let new_id = amend(repo_path, id, "amended")?;
+        let new_id = amend(repo_path, id, Some("amended"))?;
 
         assert_eq!(count_commits(&repo, 10), 1);
 
@@ -208,6 +430,49 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_amend_author() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init_empty()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"test1")?;
+        stage_add_file(repo_path, file_path)?;
+        let id = commit(repo_path, "commit msg\n\nbody")?;
+
+        let new_id =
+            amend_author(repo_path, id, "other", "other@foo", None)?;
+
+        assert_eq!(get_head(repo_path)?, new_id);
+        assert_eq!(count_commits(&repo, 10), 1);
+
+        let old = repo.find_commit(id.into())?;
+        let new = repo.find_commit(new_id.into())?;
+
+        assert_eq!(new.author().name(), Some("other"));
+        assert_eq!(new.author().email(), Some("other@foo"));
+        assert_eq!(
+            new.author().when().seconds(),
+            old.author().when().seconds()
+        );
+        assert_eq!(new.tree_id(), old.tree_id());
+        assert_eq!(new.message(), old.message());
+        assert_eq!(new.committer().name(), old.committer().name());
+
+        let newer_id = amend_author(
+            repo_path,
+            new_id,
+            "other",
+            "other@foo",
+            Some(42),
+        )?;
+        let newer = repo.find_commit(newer_id.into())?;
+        assert_eq!(newer.author().when().seconds(), 42);
+
+        Ok(())
+    }
+
     #[test]
     fn test_tag() -> Result<()> {
         let file_path = Path::new("foo");
@@ -245,4 +510,340 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_commit_with_author_override() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init_empty()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"test")?;
+
+        stage_add_file(repo_path, file_path)?;
+
+        let author = CommitSignature {
+            name: String::from("author"),
+            email: String::from("author@example.com"),
+            time: 1_000_000,
+            time_offset: 120,
+        };
+
+        let id = commit_with_options(
+            repo_path,
+            "commit msg",
+            &CommitOptions {
+                author: Some(author.clone()),
+                ..CommitOptions::default()
+            },
+        )?;
+
+        let details = get_commit_details(repo_path, id)?;
+
+        assert_eq!(details.author, author);
+
+        let committer = details.committer.unwrap();
+        assert_eq!(committer.name, "name");
+        assert_eq!(committer.email, "email");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_amend_keep_message() -> Result<()> {
+        let file_path1 = Path::new("foo");
+        let file_path2 = Path::new("foo2");
+        let (_td, repo) = repo_init_empty()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path1))?.write_all(b"test1")?;
+
+        stage_add_file(repo_path, file_path1)?;
+        let id = commit(repo_path, "commit msg")?;
+
+        File::create(&root.join(file_path2))?.write_all(b"test2")?;
+
+        stage_add_file(repo_path, file_path2)?;
+
+        let new_id = amend(repo_path, id, None)?;
+
+        assert_eq!(count_commits(&repo, 10), 1);
+
+        let details = get_commit_details(repo_path, new_id)?;
+        assert_eq!(details.message.unwrap().subject, "commit msg");
+
+        let old_tree = repo.find_commit(id.into())?.tree_id();
+        let new_tree = repo.find_commit(new_id.into())?.tree_id();
+        assert_ne!(old_tree, new_tree);
+
+        assert_eq!(get_commit_files(repo_path, new_id)?.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_nothing() -> Result<()> {
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        assert!(matches!(
+            commit(repo_path, "empty"),
+            Err(Error::NothingToCommit)
+        ));
+
+        assert_eq!(count_commits(&repo, 10), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_nothing_in_empty_repo() -> Result<()> {
+        let (_td, repo) = repo_init_empty()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        assert!(matches!(
+            commit(repo_path, "empty"),
+            Err(Error::NothingToCommit)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_allow_empty() -> Result<()> {
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let id = commit_with_options(
+            repo_path,
+            "empty",
+            &CommitOptions {
+                allow_empty: true,
+                ..CommitOptions::default()
+            },
+        )?;
+
+        assert_eq!(count_commits(&repo, 10), 2);
+        assert_eq!(get_commit_files(repo_path, id)?.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_message() -> Result<()> {
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        assert_eq!(get_merge_message(repo_path)?, None);
+
+        let msg = "Merge branch 'feature'\n\n# Conflicts:\n#\tfoo\n";
+        fs::write(repo.path().join("MERGE_MSG"), msg)?;
+
+        // left over without an ongoing merge
+        assert_eq!(get_merge_message(repo_path)?, None);
+
+        let head = get_head(repo_path)?;
+        fs::write(
+            repo.path().join("MERGE_HEAD"),
+            format!("{}\n", head.to_string()),
+        )?;
+
+        assert_eq!(
+            get_merge_message(repo_path)?.as_deref(),
+            Some(msg)
+        );
+
+        fs::remove_file(repo.path().join("MERGE_HEAD"))?;
+        fs::write(repo.path().join("SQUASH_MSG"), "Squashed commit")?;
+
+        assert_eq!(
+            get_merge_message(repo_path)?.as_deref(),
+            Some("Squashed commit")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_with_parents() -> Result<()> {
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let base = repo.head()?.peel_to_commit()?;
+        repo.branch("feature", &base, false)?;
+
+        File::create(&root.join("foo"))?.write_all(b"foo")?;
+        stage_add_file(repo_path, Path::new("foo"))?;
+        let ours = commit(repo_path, "ours")?;
+
+        repo.set_head("refs/heads/feature")?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+        File::create(&root.join("bar"))?.write_all(b"bar")?;
+        stage_add_file(repo_path, Path::new("bar"))?;
+        let theirs = commit(repo_path, "theirs")?;
+
+        // resolve the merge by taking both files
+        repo.set_head("refs/heads/master")?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+        File::create(&root.join("bar"))?.write_all(b"bar")?;
+        stage_add_file(repo_path, Path::new("bar"))?;
+
+        let missing = CommitId::new(Oid::zero());
+        let options = |parents: Vec<CommitId>| CommitOptions {
+            parents: Some(parents),
+            ..CommitOptions::default()
+        };
+
+        assert_eq!(
+            commit_with_options(
+                repo_path,
+                "merge",
+                &options(vec![ours, missing])
+            )
+            .is_err(),
+            true
+        );
+
+        let merge = commit_with_options(
+            repo_path,
+            "merge",
+            &options(vec![ours, theirs]),
+        )?;
+
+        assert_eq!(get_head(repo_path)?, merge);
+        assert_eq!(
+            get_commit_parents(repo_path, merge)?,
+            vec![ours, theirs]
+        );
+        assert_eq!(
+            repo.find_branch("master", BranchType::Local)?
+                .get()
+                .target(),
+            Some(merge.into())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_with_empty_parents() -> Result<()> {
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let head = get_head(repo_path)?;
+
+        File::create(&root.join("foo"))?.write_all(b"foo")?;
+        stage_add_file(repo_path, Path::new("foo"))?;
+
+        let id = commit_with_options(
+            repo_path,
+            "empty parents",
+            &CommitOptions {
+                parents: Some(Vec::new()),
+                ..CommitOptions::default()
+            },
+        )?;
+
+        assert_eq!(get_head(repo_path)?, id);
+        assert_eq!(get_commit_parents(repo_path, id)?, vec![head]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_heads() -> Result<()> {
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let base = repo.head()?.peel_to_commit()?;
+        repo.branch("feature", &base, false)?;
+
+        File::create(&root.join("foo"))?.write_all(b"foo")?;
+        stage_add_file(repo_path, Path::new("foo"))?;
+        commit(repo_path, "ours")?;
+
+        repo.set_head("refs/heads/feature")?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+        File::create(&root.join("bar"))?.write_all(b"bar")?;
+        stage_add_file(repo_path, Path::new("bar"))?;
+        let theirs = commit(repo_path, "theirs")?;
+
+        repo.set_head("refs/heads/master")?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+
+        assert_eq!(merge_heads(repo_path)?, vec![]);
+
+        let annotated = repo.find_annotated_commit(theirs.into())?;
+        repo.merge(&[&annotated], None, None)?;
+
+        assert_eq!(merge_heads(repo_path)?, vec![theirs]);
+        assert_eq!(get_merge_message(repo_path)?.is_some(), true);
+
+        let ours = get_head(repo_path)?;
+        let merge = commit_with_options(
+            repo_path,
+            "merge",
+            &CommitOptions {
+                parents: Some(vec![ours, theirs]),
+                ..CommitOptions::default()
+            },
+        )?;
+        cleanup_merge_state(repo_path)?;
+
+        assert_eq!(get_head(repo_path)?, merge);
+        assert_eq!(repo.state(), RepositoryState::Clean);
+        assert_eq!(merge_heads(repo_path)?, vec![]);
+        assert_eq!(get_merge_message(repo_path)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_missing_identity() -> Result<()> {
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        // local config shadows whatever the machine has configured
+        let mut config = repo.config()?;
+        config.set_str("user.name", "")?;
+        config.set_str("user.email", "")?;
+
+        File::create(&root.join("foo"))?.write_all(b"a")?;
+        stage_add_file(repo_path, Path::new("foo"))?;
+
+        assert!(matches!(
+            commit(repo_path, "c1"),
+            Err(Error::MissingIdentity)
+        ));
+
+        let identity = CommitSignature {
+            name: String::from("name"),
+            email: String::from("name@mail"),
+            time: 0,
+            time_offset: 0,
+        };
+
+        commit_with_options(
+            repo_path,
+            "c1",
+            &CommitOptions {
+                author: Some(identity.clone()),
+                committer: Some(identity),
+                ..CommitOptions::default()
+            },
+        )?;
+
+        assert_eq!(get_statuses(repo_path), (0, 0));
+
+        Ok(())
+    }
 }
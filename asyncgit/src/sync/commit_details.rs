@@ -1,10 +1,15 @@
-use super::{commits_info::get_message, utils::repo, CommitId};
+use super::{
+    commits_info::get_message,
+    mailmap::{read_mailmap, resolve_signature},
+    utils::repo,
+    CommitId,
+};
 use crate::error::Result;
 use git2::Signature;
 use scopetime::scope_time;
 
 ///
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CommitSignature {
     ///
     pub name: String,
@@ -12,6 +17,8 @@ pub struct CommitSignature {
     pub email: String,
     /// time in secs since Unix epoch
     pub time: i64,
+    /// timezone of `time` in minutes east of UTC
+    pub time_offset: i32,
 }
 
 impl CommitSignature {
@@ -22,6 +29,7 @@ impl CommitSignature {
             email: s.email().unwrap_or("").to_string(),
 
             time: s.when().seconds(),
+            time_offset: s.when().offset_minutes(),
         }
     }
 }
@@ -64,6 +72,21 @@ impl CommitMessage {
     }
 }
 
+/// splits a commit message into its subject (first line) and body
+/// (everything after the blank line(s) following the subject),
+/// both trimmed and with line endings normalized to `\n`
+pub fn split_commit_message(msg: &str) -> (String, String) {
+    let msg = msg.replace("\r\n", "\n");
+    let msg = msg.trim_start();
+
+    let mut lines = msg.splitn(2, '\n');
+
+    let subject = lines.next().unwrap_or_default().trim_end();
+    let body = lines.next().unwrap_or_default().trim();
+
+    (subject.to_string(), body.to_string())
+}
+
 ///
 pub struct CommitDetails {
     ///
@@ -87,8 +110,16 @@ pub fn get_commit_details(
 
     let commit = repo.find_commit(id.into())?;
 
-    let author = CommitSignature::from(commit.author());
-    let committer = CommitSignature::from(commit.committer());
+    let mailmap = read_mailmap(&repo);
+
+    let author = CommitSignature::from(resolve_signature(
+        mailmap.as_ref(),
+        &commit.author(),
+    ));
+    let committer = CommitSignature::from(resolve_signature(
+        mailmap.as_ref(),
+        &commit.committer(),
+    ));
     let committer = if author == committer {
         None
     } else {
@@ -111,7 +142,7 @@ pub fn get_commit_details(
 #[cfg(test)]
 mod tests {
 
-    use super::get_commit_details;
+    use super::{get_commit_details, split_commit_message};
     use crate::error::Result;
     use crate::sync::{
         commit, stage_add_file, tests::repo_init_empty,
@@ -145,4 +176,25 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_split_commit_message() {
+        assert_eq!(
+            split_commit_message("subject"),
+            (String::from("subject"), String::new())
+        );
+
+        assert_eq!(
+            split_commit_message("subject\n\nbody\nmore body\n"),
+            (
+                String::from("subject"),
+                String::from("body\nmore body")
+            )
+        );
+
+        assert_eq!(
+            split_commit_message("subject  \r\n\r\nbody\r\nend\r\n"),
+            (String::from("subject"), String::from("body\nend"))
+        );
+    }
 }
@@ -1,23 +1,102 @@
 use super::{stash::is_stash_commit, utils::repo, CommitId};
-use crate::{error::Result, StatusItem, StatusItemType};
-use git2::{Diff, DiffDelta, DiffOptions, Repository};
+use crate::{
+    error::{Error, Result},
+    StatusItem, StatusItemType,
+};
+use git2::{
+    BranchType, Diff, DiffDelta, DiffFindOptions, DiffOptions, Oid,
+    Repository, Tree,
+};
 use scopetime::scope_time;
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// what the changes of a merge commit are compared against
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MergeDiffMode {
+    /// only what the merge introduced on top of its first parent
+    /// (`--first-parent` semantics), the default
+    FirstParent,
+    /// only files that differ from every parent (like `git diff -c`),
+    /// their content is shown against the first parent
+    Combined,
+    /// against the parent at this index
+    SpecificParent(usize),
+}
+
+impl Default for MergeDiffMode {
+    fn default() -> Self {
+        Self::FirstParent
+    }
+}
 
 /// get all files that are part of a commit
 pub fn get_commit_files(
     repo_path: &str,
     id: CommitId,
 ) -> Result<Vec<StatusItem>> {
+    get_commit_files_with_mode(
+        repo_path,
+        id,
+        MergeDiffMode::default(),
+    )
+}
+
+/// get all files that are part of a commit,
+/// `mode` only makes a difference for merge commits
+pub fn get_commit_files_with_mode(
+    repo_path: &str,
+    id: CommitId,
+    mode: MergeDiffMode,
+) -> Result<Vec<StatusItem>> {
+    get_commit_files_with_progress(repo_path, id, mode, |_| true)
+}
+
+/// get all files that are part of a commit, fails with
+/// `Error::Cancelled` once `cancel` is set (from another thread)
+pub fn get_commit_files_cancellable(
+    repo_path: &str,
+    id: CommitId,
+    cancel: &AtomicBool,
+) -> Result<Vec<StatusItem>> {
+    get_commit_files_with_progress(
+        repo_path,
+        id,
+        MergeDiffMode::default(),
+        |_| !cancel.load(Ordering::Relaxed),
+    )
+}
+
+/// like `get_commit_files_with_mode` but reports the progress
+/// (`0.0` to `1.0`) for every file, returning false from `progress`
+/// cancels the call with `Error::Cancelled`
+pub fn get_commit_files_with_progress<F>(
+    repo_path: &str,
+    id: CommitId,
+    mode: MergeDiffMode,
+    mut progress: F,
+) -> Result<Vec<StatusItem>>
+where
+    F: FnMut(f32) -> bool,
+{
     scope_time!("get_commit_files");
 
     let repo = repo(repo_path)?;
 
-    let diff = get_commit_diff(&repo, id, None)?;
+    let diff = get_commit_diff_with_mode(&repo, id, None, mode)?;
 
     let mut res = Vec::new();
+    let mut cancelled = false;
+
+    let result = diff.foreach(
+        &mut |delta: DiffDelta<'_>, file_progress| {
+            if !progress(file_progress) {
+                cancelled = true;
+                return false;
+            }
 
-    diff.foreach(
-        &mut |delta: DiffDelta<'_>, _progress| {
             res.push(StatusItem {
                 path: delta
                     .new_file()
@@ -31,7 +110,53 @@ pub fn get_commit_files(
         None,
         None,
         None,
-    )?;
+    );
+
+    if cancelled {
+        return Err(Error::Cancelled);
+    }
+
+    result?;
+
+    Ok(res)
+}
+
+/// files that differ between the working tree (including the index)
+/// and the tip of the local `branch`, seen from the branch
+pub fn diff_files_vs_branch(
+    repo_path: &str,
+    branch: &str,
+    find_renames: bool,
+) -> Result<Vec<StatusItem>> {
+    scope_time!("diff_files_vs_branch");
+
+    let repo = repo(repo_path)?;
+
+    let tree = repo
+        .find_branch(branch, BranchType::Local)?
+        .get()
+        .peel_to_tree()?;
+
+    let mut diff =
+        repo.diff_tree_to_workdir_with_index(Some(&tree), None)?;
+
+    if find_renames {
+        diff.find_similar(Some(
+            DiffFindOptions::new().renames(true),
+        ))?;
+    }
+
+    let res = diff
+        .deltas()
+        .map(|delta| StatusItem {
+            path: delta
+                .new_file()
+                .path()
+                .map(|p| p.to_str().unwrap_or("").to_string())
+                .unwrap_or_default(),
+            status: StatusItemType::from(delta.status()),
+        })
+        .collect();
 
     Ok(res)
 }
@@ -41,23 +166,87 @@ pub(crate) fn get_commit_diff(
     repo: &Repository,
     id: CommitId,
     pathspec: Option<String>,
+) -> Result<Diff<'_>> {
+    get_commit_diff_with_mode(
+        repo,
+        id,
+        pathspec,
+        MergeDiffMode::default(),
+    )
+}
+
+///
+pub(crate) fn get_commit_diff_with_mode(
+    repo: &Repository,
+    id: CommitId,
+    pathspec: Option<String>,
+    mode: MergeDiffMode,
 ) -> Result<Diff<'_>> {
     // scope_time!("get_commit_diff");
 
     let commit = repo.find_commit(id.into())?;
     let commit_tree = commit.tree()?;
-    let parent = if commit.parent_count() > 0 {
-        Some(repo.find_commit(commit.parent_id(0)?)?.tree()?)
+
+    let parent_index = match mode {
+        MergeDiffMode::SpecificParent(idx) => idx,
+        _ => 0,
+    };
+
+    let parent = if commit.parent_count() > parent_index {
+        Some(
+            repo.find_commit(commit.parent_id(parent_index)?)?
+                .tree()?,
+        )
+    } else if commit.parent_count() > 0 {
+        return Err(Error::Generic(format!(
+            "commit has no parent {}",
+            parent_index
+        )));
     } else {
         None
     };
 
-    let mut opt = pathspec.as_ref().map(|p| {
+    let mut pathspecs = pathspec.iter().cloned().collect::<Vec<_>>();
+    let mut exact_paths = false;
+
+    if mode == MergeDiffMode::Combined && commit.parent_count() > 1 {
+        let changed = paths_changed_against_all_parents(
+            repo,
+            &commit_tree,
+            &commit.parent_ids().collect::<Vec<_>>(),
+        )?;
+
+        pathspecs = if pathspec.is_some() {
+            pathspecs
+                .into_iter()
+                .filter(|p| changed.contains(p))
+                .collect()
+        } else {
+            changed.into_iter().collect()
+        };
+        exact_paths = true;
+
+        if pathspecs.is_empty() {
+            // nothing survived, return an empty diff
+            return Ok(repo.diff_tree_to_tree(
+                Some(&commit_tree),
+                Some(&commit_tree),
+                None,
+            )?);
+        }
+    }
+
+    let mut opt = if pathspecs.is_empty() {
+        None
+    } else {
         let mut opts = DiffOptions::new();
-        opts.pathspec(p);
+        for p in &pathspecs {
+            opts.pathspec(p);
+        }
+        opts.disable_pathspec_match(exact_paths);
         opts.show_binary(true);
-        opts
-    });
+        Some(opts)
+    };
 
     let mut diff = repo.diff_tree_to_tree(
         parent.as_ref(),
@@ -65,10 +254,13 @@ pub(crate) fn get_commit_diff(
         opt.as_mut(),
     )?;
 
-    if is_stash_commit(
-        repo.path().to_str().expect("repo path utf8 err"),
-        &id,
-    )? {
+    let repo_path = repo
+        .path()
+        .to_str()
+        .ok_or_else(|| Error::Generic("repo path utf8 err".into()))?;
+
+    // stashes keep untracked files in a third parent (see #130)
+    if is_stash_commit(repo_path, &id)? {
         if let Ok(untracked_commit) = commit.parent_id(2) {
             let untracked_diff = get_commit_diff(
                 repo,
@@ -83,18 +275,66 @@ pub(crate) fn get_commit_diff(
     Ok(diff)
 }
 
+/// paths of `tree` that differ from the trees of all `parents`
+fn paths_changed_against_all_parents(
+    repo: &Repository,
+    tree: &Tree,
+    parents: &[Oid],
+) -> Result<HashSet<String>> {
+    let mut res: Option<HashSet<String>> = None;
+
+    for parent in parents {
+        let parent_tree = repo.find_commit(*parent)?.tree()?;
+        let diff = repo.diff_tree_to_tree(
+            Some(&parent_tree),
+            Some(tree),
+            None,
+        )?;
+
+        let changed = diff
+            .deltas()
+            .filter_map(|delta| {
+                delta
+                    .new_file()
+                    .path()
+                    .or_else(|| delta.old_file().path())
+                    .and_then(|p| p.to_str())
+                    .map(String::from)
+            })
+            .collect::<HashSet<_>>();
+
+        res = Some(match res {
+            Some(prev) => {
+                prev.intersection(&changed).cloned().collect()
+            }
+            None => changed,
+        });
+    }
+
+    Ok(res.unwrap_or_default())
+}
+
 #[cfg(test)]
 mod tests {
-    use super::get_commit_files;
+    use super::{
+        diff_files_vs_branch, get_commit_files,
+        get_commit_files_cancellable, get_commit_files_with_mode,
+        get_commit_files_with_progress, MergeDiffMode,
+    };
     use crate::{
-        error::Result,
+        error::{Error, Result},
         sync::{
-            commit, stage_add_file, stash_save,
-            tests::{get_statuses, repo_init},
+            commit, get_diff_commit, stage_add_all, stage_add_file,
+            stage_items, stash_save,
+            tests::{get_statuses, repo_init, repo_init_empty},
+            CommitId,
         },
         StatusItemType,
     };
-    use std::{fs::File, io::Write, path::Path};
+    use git2::Signature;
+    use std::{
+        fs::File, io::Write, path::Path, sync::atomic::AtomicBool,
+    };
 
     #[test]
     fn test_smoke() -> Result<()> {
@@ -166,4 +406,210 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_stashed_untracked_content() -> Result<()> {
+        let file_path = Path::new("file1.txt");
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?
+            .write_all(b"untracked content\n")?;
+
+        let id = stash_save(repo_path, None, true, false)?;
+
+        let diff =
+            get_diff_commit(repo_path, id, "file1.txt".into())?;
+
+        assert_eq!(diff.lines, 2);
+        assert_eq!(
+            diff.hunks[0].lines[1].content.trim_end(),
+            "untracked content"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_diff_modes() -> Result<()> {
+        let (_td, repo) = repo_init_empty()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let write = |name: &str, content: &str| {
+            File::create(&root.join(name))
+                .unwrap()
+                .write_all(content.as_bytes())
+                .unwrap();
+            stage_add_file(repo_path, Path::new(name)).unwrap();
+        };
+        let index_tree = || -> Result<git2::Tree> {
+            let mut index = repo.index()?;
+            index.read(true)?;
+            Ok(repo.find_tree(index.write_tree()?)?)
+        };
+        let sig = Signature::now("name", "email")?;
+
+        write("a", "a0");
+        write("b", "b0");
+        let base =
+            repo.find_commit(commit(repo_path, "base")?.into())?;
+
+        // second parent only touches `b`
+        write("b", "b1");
+        let side =
+            repo.find_commit(commit(repo_path, "side")?.into())?;
+
+        // first parent only touches `a`
+        write("a", "a1");
+        write("b", "b0");
+        let tree = index_tree()?;
+        let main = repo.find_commit(repo.commit(
+            None,
+            &sig,
+            &sig,
+            "main",
+            &tree,
+            &[&base],
+        )?)?;
+
+        // the merge itself adds `c`
+        write("b", "b1");
+        write("c", "c");
+        let tree = index_tree()?;
+        let merge = CommitId::new(repo.commit(
+            None,
+            &sig,
+            &sig,
+            "merge",
+            &tree,
+            &[&main, &side],
+        )?);
+
+        let paths = |mode| -> Result<Vec<String>> {
+            Ok(get_commit_files_with_mode(repo_path, merge, mode)?
+                .into_iter()
+                .map(|item| item.path)
+                .collect())
+        };
+
+        assert_eq!(
+            paths(MergeDiffMode::FirstParent)?,
+            vec!["b", "c"]
+        );
+        assert_eq!(
+            get_commit_files(repo_path, merge)?.len(),
+            paths(MergeDiffMode::FirstParent)?.len()
+        );
+        assert_eq!(
+            paths(MergeDiffMode::SpecificParent(1))?,
+            vec!["a", "c"]
+        );
+        assert_eq!(paths(MergeDiffMode::Combined)?, vec!["c"]);
+        assert_eq!(
+            paths(MergeDiffMode::SpecificParent(2)).is_err(),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_progress() -> Result<()> {
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        for i in 0..20 {
+            File::create(&root.join(format!("file{}.txt", i)))?
+                .write_all(b"a")?;
+        }
+        stage_add_all(repo_path, "*")?;
+        let id = commit(repo_path, "many files")?;
+
+        let mut reported = Vec::new();
+        let files = get_commit_files_with_progress(
+            repo_path,
+            id,
+            MergeDiffMode::default(),
+            |p| {
+                reported.push(p);
+                true
+            },
+        )?;
+
+        assert_eq!(files.len(), 20);
+        assert_eq!(reported.len(), 20);
+        assert_eq!(reported.windows(2).all(|w| w[0] <= w[1]), true);
+
+        let mut calls = 0;
+        let res = get_commit_files_with_progress(
+            repo_path,
+            id,
+            MergeDiffMode::default(),
+            |_| {
+                calls += 1;
+                calls < 5
+            },
+        );
+
+        assert!(matches!(res, Err(Error::Cancelled)));
+        assert_eq!(calls, 5);
+
+        let cancel = AtomicBool::new(true);
+        assert!(matches!(
+            get_commit_files_cancellable(repo_path, id, &cancel),
+            Err(Error::Cancelled)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_files_vs_branch() -> Result<()> {
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        for f in &["a.txt", "b.txt", "c.txt"] {
+            File::create(&root.join(f))?
+                .write_all(format!("{} content\n", f).as_bytes())?;
+        }
+        stage_add_all(repo_path, "*")?;
+        commit(repo_path, "base")?;
+
+        let base = repo.head()?.peel_to_commit()?;
+        repo.branch("other", &base, false)?;
+
+        assert_eq!(
+            diff_files_vs_branch(repo_path, "other", false)?,
+            vec![]
+        );
+
+        // one change committed, one only in the working tree
+        File::create(&root.join("a.txt"))?.write_all(b"changed")?;
+        stage_add_file(repo_path, Path::new("a.txt"))?;
+        commit(repo_path, "change a")?;
+        File::create(&root.join("b.txt"))?.write_all(b"changed")?;
+
+        let files = diff_files_vs_branch(repo_path, "other", false)?;
+        assert_eq!(
+            files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(),
+            vec!["a.txt", "b.txt"]
+        );
+
+        std::fs::rename(root.join("c.txt"), root.join("d.txt"))?;
+        stage_items(
+            repo_path,
+            &[String::from("c.txt"), String::from("d.txt")],
+        )?;
+
+        let files = diff_files_vs_branch(repo_path, "other", true)?;
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[2].path, "d.txt");
+        assert_eq!(files[2].status, StatusItemType::Renamed);
+
+        Ok(())
+    }
 }
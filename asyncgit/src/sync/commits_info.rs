@@ -1,6 +1,9 @@
-use super::utils::repo;
+use super::{
+    mailmap::{read_mailmap, resolve_signature},
+    utils::repo,
+};
 use crate::error::Result;
-use git2::{Commit, Error, Oid};
+use git2::{Commit, Error, Oid, Repository};
 use scopetime::scope_time;
 
 /// identifies a single commit
@@ -17,6 +20,17 @@ impl CommitId {
     pub(crate) fn get_oid(self) -> Oid {
         self.0
     }
+
+    /// shortest unambiguous abbreviation in `repo`
+    /// (at least `core.abbrev` long)
+    pub(crate) fn get_short_id_repo(
+        self,
+        repo: &Repository,
+    ) -> Result<String> {
+        let short = repo.find_object(self.0, None)?.short_id()?;
+
+        Ok(short.as_str().unwrap_or_default().to_string())
+    }
 }
 
 impl ToString for CommitId {
@@ -50,6 +64,28 @@ pub struct CommitInfo {
     pub id: CommitId,
 }
 
+/// shortest unambiguous abbreviation of `commit`
+/// (at least `core.abbrev` long)
+pub fn short_id(repo_path: &str, commit: CommitId) -> Result<String> {
+    scope_time!("short_id");
+
+    let repo = repo(repo_path)?;
+
+    commit.get_short_id_repo(&repo)
+}
+
+/// text to copy for `commit`: the full hash or, if `short`,
+/// its first 7 chars (without checking for ambiguity)
+pub fn format_commit_ref(commit: CommitId, short: bool) -> String {
+    let id = commit.to_string();
+
+    if short {
+        id.chars().take(7).collect()
+    } else {
+        id
+    }
+}
+
 ///
 pub fn get_commits_info(
     repo_path: &str,
@@ -60,6 +96,8 @@ pub fn get_commits_info(
 
     let repo = repo(repo_path)?;
 
+    let mailmap = read_mailmap(&repo);
+
     let commits = ids
         .iter()
         .map(|id| repo.find_commit((*id).into()))
@@ -69,8 +107,10 @@ pub fn get_commits_info(
     let res = commits
         .map(|c: Commit| {
             let message = get_message(&c, Some(message_length_limit));
-            let author = if let Some(name) = c.author().name() {
-                String::from(name)
+            let sig =
+                resolve_signature(mailmap.as_ref(), &c.author());
+            let author = if let Some(name) = sig.name() {
+                name.to_string()
             } else {
                 String::from("<unknown>")
             };
@@ -86,6 +126,51 @@ pub fn get_commits_info(
     Ok(res)
 }
 
+/// parents of commit `id` in order (first parent first),
+/// empty for a root commit
+pub fn get_commit_parents(
+    repo_path: &str,
+    id: CommitId,
+) -> Result<Vec<CommitId>> {
+    scope_time!("get_commit_parents");
+
+    let repo = repo(repo_path)?;
+
+    let commit = repo.find_commit(id.into())?;
+
+    let parents = commit.parent_ids().map(CommitId::new).collect();
+
+    Ok(parents)
+}
+
+/// subject `get_commit_subjects` returns for unknown commits
+pub const INVALID_COMMIT_SUBJECT: &str = "<invalid commit>";
+
+/// first line of the message of each commit in `ids` (in order),
+/// unknown commits are an error unless `placeholder_invalid`
+pub fn get_commit_subjects(
+    repo_path: &str,
+    ids: &[CommitId],
+    placeholder_invalid: bool,
+) -> Result<Vec<String>> {
+    scope_time!("get_commit_subjects");
+
+    let repo = repo(repo_path)?;
+
+    ids.iter()
+        .map(|id| match repo.find_commit((*id).into()) {
+            Ok(c) => Ok(c
+                .summary_bytes()
+                .map(|s| String::from_utf8_lossy(s).to_string())
+                .unwrap_or_default()),
+            Err(_) if placeholder_invalid => {
+                Ok(INVALID_COMMIT_SUBJECT.to_string())
+            }
+            Err(e) => Err(e.into()),
+        })
+        .collect()
+}
+
 ///
 pub fn get_message(
     c: &Commit,
@@ -117,13 +202,21 @@ fn limit_str(s: &str, limit: usize) -> &str {
 #[cfg(test)]
 mod tests {
 
-    use super::{get_commits_info, limit_str};
+    use super::{
+        format_commit_ref, get_commit_parents, get_commit_subjects,
+        get_commits_info, limit_str, short_id, CommitId,
+        INVALID_COMMIT_SUBJECT,
+    };
     use crate::error::Result;
     use crate::sync::{
-        commit, stage_add_file, tests::repo_init_empty,
+        commit, stage_add_file,
+        tests::{repo_init, repo_init_empty},
         utils::get_head_repo,
     };
-    use std::{fs::File, io::Write, path::Path};
+    use git2::Oid;
+    use std::{
+        collections::HashMap, fs::File, io::Write, path::Path,
+    };
 
     #[test]
     fn test_log() -> Result<()> {
@@ -135,7 +228,7 @@ mod tests {
         File::create(&root.join(file_path))?.write_all(b"a")?;
         stage_add_file(repo_path, file_path).unwrap();
         let c1 = commit(repo_path, "commit1").unwrap();
-        File::create(&root.join(file_path))?.write_all(b"a")?;
+        File::create(&root.join(file_path))?.write_all(b"b")?;
         stage_add_file(repo_path, file_path).unwrap();
         let c2 = commit(repo_path, "commit2").unwrap();
 
@@ -150,6 +243,97 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_commit_subjects() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let mut ids = Vec::new();
+        for (i, msg) in
+            ["first", "second\n\nbody", "third"].iter().enumerate()
+        {
+            File::create(&root.join(file_path))?
+                .write_all(i.to_string().as_bytes())?;
+            stage_add_file(repo_path, file_path)?;
+            ids.push(commit(repo_path, msg)?);
+        }
+        ids.reverse();
+
+        assert_eq!(
+            get_commit_subjects(repo_path, &ids, false)?,
+            vec!["third", "second", "first"]
+        );
+
+        let invalid = CommitId::new(git2::Oid::zero());
+        ids.push(invalid);
+
+        assert_eq!(
+            get_commit_subjects(repo_path, &ids, false).is_err(),
+            true
+        );
+        assert_eq!(
+            get_commit_subjects(repo_path, &ids, true)?[3],
+            INVALID_COMMIT_SUBJECT
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_parents() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path)?;
+        let c1 = commit(repo_path, "c1")?;
+
+        assert_eq!(get_commit_parents(repo_path, c1)?, vec![]);
+
+        File::create(&root.join(file_path))?.write_all(b"b")?;
+        stage_add_file(repo_path, file_path)?;
+        let c2 = commit(repo_path, "c2")?;
+
+        let other = {
+            let base = repo.find_commit(c1.into())?;
+            let sig = repo.signature()?;
+            repo.commit(
+                None,
+                &sig,
+                &sig,
+                "other",
+                &base.tree()?,
+                &[&base],
+            )?
+        };
+
+        let merge = {
+            let head = repo.find_commit(c2.into())?;
+            let other = repo.find_commit(other)?;
+            let sig = repo.signature()?;
+            repo.commit(
+                Some("HEAD"),
+                &sig,
+                &sig,
+                "merge",
+                &head.tree()?,
+                &[&head, &other],
+            )?
+        };
+
+        assert_eq!(get_commit_parents(repo_path, c2)?, vec![c1]);
+        assert_eq!(
+            get_commit_parents(repo_path, merge.into())?,
+            vec![c2, other.into()]
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_invalid_utf8() -> Result<()> {
         let file_path = Path::new("foo");
@@ -185,4 +369,63 @@ mod tests {
         let test_dst = "导入按钮由选文";
         assert_eq!(limit_str(test_src, 20), test_dst);
     }
+
+    #[test]
+    fn test_short_id_unique() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        // smaller abbrev to provoke a shared prefix with few commits
+        let abbrev = 4;
+        repo.config()?.set_i32("core.abbrev", abbrev as i32)?;
+
+        let sig = repo.signature()?;
+        let tree = repo.head()?.peel_to_tree()?;
+
+        let mut by_prefix: HashMap<String, CommitId> = HashMap::new();
+        let (a, b) = (0..)
+            .find_map(|i| {
+                let id: CommitId = repo
+                    .commit(
+                        None,
+                        &sig,
+                        &sig,
+                        &i.to_string(),
+                        &tree,
+                        &[],
+                    )
+                    .unwrap()
+                    .into();
+                let prefix = id.to_string()[..abbrev].to_string();
+                by_prefix.insert(prefix, id).map(|other| (other, id))
+            })
+            .unwrap();
+
+        let short_a = short_id(repo_path, a)?;
+        let short_b = short_id(repo_path, b)?;
+
+        assert_ne!(short_a, short_b);
+        assert!(short_a.len() > abbrev);
+        assert!(short_b.len() > abbrev);
+        assert!(a.to_string().starts_with(&short_a));
+        assert!(b.to_string().starts_with(&short_b));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_commit_ref() -> Result<()> {
+        let id = CommitId::new(Oid::from_str(
+            "d6e4a1c7b8f9e0a1b2c3d4e5f60718293a4b5c6d",
+        )?);
+
+        assert_eq!(
+            format_commit_ref(id, false),
+            "d6e4a1c7b8f9e0a1b2c3d4e5f60718293a4b5c6d"
+        );
+        assert_eq!(format_commit_ref(id, true), "d6e4a1c");
+
+        Ok(())
+    }
 }
@@ -0,0 +1,138 @@
+use super::utils::repo;
+use crate::error::{Error, Result};
+use git2::{Config, ConfigLevel, ErrorCode};
+use scopetime::scope_time;
+use std::{env, path::PathBuf};
+
+/// config file a value is written to
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ConfigScope {
+    /// `.git/config` of the repo
+    Local,
+    /// the users `~/.gitconfig`
+    Global,
+}
+
+/// reads a config value respecting the usual precedence
+/// (system, global, local), returns None if it is not set
+pub fn get_config_string(
+    repo_path: &str,
+    key: &str,
+) -> Result<Option<String>> {
+    scope_time!("get_config_string");
+
+    let repo = repo(repo_path)?;
+
+    get_config_string_repo(&repo.config()?, key)
+}
+
+/// writes a config value into the config file of `scope`
+pub fn set_config_string(
+    repo_path: &str,
+    key: &str,
+    value: &str,
+    scope: ConfigScope,
+) -> Result<()> {
+    scope_time!("set_config_string");
+
+    let repo = repo(repo_path)?;
+
+    let mut config = match scope {
+        ConfigScope::Local => {
+            repo.config()?.open_level(ConfigLevel::Local)?
+        }
+        ConfigScope::Global => Config::open(&global_config_path()?)?,
+    };
+
+    config.set_str(key, value)?;
+
+    Ok(())
+}
+
+pub(crate) fn get_config_string_repo(
+    config: &Config,
+    key: &str,
+) -> Result<Option<String>> {
+    match config.get_string(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// existing global config or where git would create it
+fn global_config_path() -> Result<PathBuf> {
+    Config::find_global().or_else(|_| {
+        env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".gitconfig"))
+            .ok_or_else(|| {
+                Error::Generic("unable to find home dir".into())
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        get_config_string, get_config_string_repo, set_config_string,
+        ConfigScope,
+    };
+    use crate::error::Result;
+    use crate::sync::tests::repo_init;
+    use git2::{Config, ConfigLevel};
+    use std::fs;
+
+    #[test]
+    fn test_set_and_get_local() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        assert_eq!(get_config_string(repo_path, "foo.bar")?, None);
+
+        set_config_string(
+            repo_path,
+            "user.name",
+            "local name",
+            ConfigScope::Local,
+        )?;
+
+        assert_eq!(
+            get_config_string(repo_path, "user.name")?.as_deref(),
+            Some("local name")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_overrides_global() -> Result<()> {
+        let (td, repo) = repo_init().unwrap();
+
+        let global_path = td.path().join("global.gitconfig");
+        fs::write(
+            &global_path,
+            "[user]\n\tname = global name\n[foo]\n\tbar = baz\n",
+        )?;
+
+        let mut config = Config::new()?;
+        config.add_file(&global_path, ConfigLevel::Global, false)?;
+        config.add_file(
+            &repo.path().join("config"),
+            ConfigLevel::Local,
+            false,
+        )?;
+
+        assert_eq!(
+            get_config_string_repo(&config, "user.name")?.as_deref(),
+            Some("name")
+        );
+        assert_eq!(
+            get_config_string_repo(&config, "foo.bar")?.as_deref(),
+            Some("baz")
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,445 @@
+use super::utils::{repo, work_dir};
+use crate::error::{Error, Result};
+use git2::{Commit, Delta, ErrorCode, Index, Oid, Repository};
+use scopetime::scope_time;
+use std::{collections::BTreeSet, fs, path::Path};
+
+const MARKER_OURS: &str = "<<<<<<<";
+const MARKER_SEPARATOR: &str = "=======";
+const MARKER_THEIRS: &str = ">>>>>>>";
+
+/// number of conflict regions (complete `<<<<<<<`, `=======`,
+/// `>>>>>>>` triples) left in the working copy of `path`,
+/// partially resolved regions are not counted
+pub fn conflict_marker_count(
+    repo_path: &str,
+    path: &str,
+) -> Result<usize> {
+    scope_time!("conflict_marker_count");
+
+    let repo = repo(repo_path)?;
+
+    let content = fs::read(work_dir(&repo)?.join(path))?;
+
+    Ok(count_conflict_regions(&String::from_utf8_lossy(&content)))
+}
+
+/// paths that keep a rebase/merge from continuing: entries still
+/// conflicted in the index and staged files that conflicted in the
+/// operation in progress with conflict markers left in them.
+/// empty if it is safe to continue
+pub fn unresolved_conflicts(repo_path: &str) -> Result<Vec<String>> {
+    scope_time!("unresolved_conflicts");
+
+    let repo = repo(repo_path)?;
+    let index = repo.index()?;
+
+    let mut res = BTreeSet::new();
+
+    insert_conflict_paths(&index, &mut res)?;
+
+    let head = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    // nothing can be in progress on an unborn branch
+    let conflicted = match &head {
+        Some(head) => operation_conflicts(&repo, head)?,
+        None => BTreeSet::new(),
+    };
+
+    if conflicted.is_empty() {
+        return Ok(res.into_iter().collect());
+    }
+
+    let head_tree = match &head {
+        Some(head) => Some(head.tree()?),
+        None => None,
+    };
+    let diff = repo.diff_tree_to_index(
+        head_tree.as_ref(),
+        Some(&index),
+        None,
+    )?;
+
+    for delta in diff.deltas() {
+        match delta.status() {
+            Delta::Deleted | Delta::Conflicted => continue,
+            _ => (),
+        }
+
+        let path = match delta.new_file().path() {
+            Some(path) => path.to_string_lossy().to_string(),
+            None => continue,
+        };
+        if !conflicted.contains(&path) {
+            continue;
+        }
+
+        let blob = repo.find_blob(delta.new_file().id())?;
+        if blob.is_binary() {
+            continue;
+        }
+
+        let content = String::from_utf8_lossy(blob.content());
+        if count_conflict_regions(&content) > 0 {
+            res.insert(path);
+        }
+    }
+
+    Ok(res.into_iter().collect())
+}
+
+/// paths that conflict when replaying the merge, cherry-pick,
+/// revert or rebase step in progress onto `head`
+fn operation_conflicts(
+    repo: &Repository,
+    head: &Commit,
+) -> Result<BTreeSet<String>> {
+    let mut res = BTreeSet::new();
+
+    let head_ids = |name: &str| -> Result<Vec<Oid>> {
+        match fs::read_to_string(repo.path().join(name)) {
+            Ok(content) => content
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(|l| Ok(Oid::from_str(l)?))
+                .collect(),
+            Err(_) => Ok(Vec::new()),
+        }
+    };
+
+    let mainline =
+        |commit: &Commit| u32::from(commit.parent_count() > 1);
+
+    let mut indexes = Vec::new();
+
+    for id in head_ids("MERGE_HEAD")? {
+        let theirs = repo.find_commit(id)?;
+        indexes.push(repo.merge_commits(head, &theirs, None)?);
+    }
+
+    let mut picks = head_ids("CHERRY_PICK_HEAD")?;
+    picks.extend(head_ids("REBASE_HEAD")?);
+    if let Ok(mut rebase) = repo.open_rebase(None) {
+        let current = rebase.operation_current();
+        if let Some(op) = current.and_then(|i| rebase.nth(i)) {
+            picks.push(op.id());
+        }
+    }
+
+    for id in picks {
+        let pick = repo.find_commit(id)?;
+        indexes.push(repo.cherrypick_commit(
+            &pick,
+            head,
+            mainline(&pick),
+            None,
+        )?);
+    }
+
+    for id in head_ids("REVERT_HEAD")? {
+        let revert = repo.find_commit(id)?;
+        indexes.push(repo.revert_commit(
+            &revert,
+            head,
+            mainline(&revert),
+            None,
+        )?);
+    }
+
+    for index in &indexes {
+        insert_conflict_paths(index, &mut res)?;
+    }
+
+    Ok(res)
+}
+
+fn insert_conflict_paths(
+    index: &Index,
+    paths: &mut BTreeSet<String>,
+) -> Result<()> {
+    if index.has_conflicts() {
+        for conflict in index.conflicts()? {
+            let conflict = conflict?;
+            let entry =
+                conflict.our.or(conflict.their).or(conflict.ancestor);
+            if let Some(entry) = entry {
+                paths.insert(
+                    String::from_utf8_lossy(&entry.path).into(),
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// marks the conflict of `path` resolved: drops its conflict
+/// entries from the index and stages the working copy (or its
+/// removal if it was deleted). refuses to if conflict markers
+/// are left in the file
+pub fn mark_resolved(repo_path: &str, path: &str) -> Result<()> {
+    scope_time!("mark_resolved");
+
+    let repo = repo(repo_path)?;
+    let deleted = !work_dir(&repo)?.join(path).exists();
+
+    if !deleted {
+        let markers = conflict_marker_count(repo_path, path)?;
+        if markers > 0 {
+            return Err(Error::Generic(format!(
+                "'{}' still contains {} conflict(s)",
+                path, markers
+            )));
+        }
+    }
+
+    let mut index = repo.index()?;
+    let path = Path::new(path);
+
+    // ancestor, ours and theirs
+    for stage in 1..=3 {
+        if index.get_path(path, stage).is_some() {
+            index.remove(path, stage)?;
+        }
+    }
+
+    if deleted {
+        index.remove_path(path)?;
+    } else {
+        index.add_path(path)?;
+    }
+
+    index.write()?;
+
+    Ok(())
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.starts_with(marker)
+        && line[marker.len()..]
+            .chars()
+            .next()
+            .map_or(true, char::is_whitespace)
+}
+
+fn count_conflict_regions(content: &str) -> usize {
+    let mut count = 0;
+    let mut in_ours = false;
+    let mut in_theirs = false;
+
+    for line in content.lines() {
+        if is_marker(line, MARKER_OURS) {
+            in_ours = true;
+            in_theirs = false;
+        } else if in_ours && is_marker(line, MARKER_SEPARATOR) {
+            in_ours = false;
+            in_theirs = true;
+        } else if in_theirs && is_marker(line, MARKER_THEIRS) {
+            in_theirs = false;
+            count += 1;
+        }
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        conflict_marker_count, count_conflict_regions, mark_resolved,
+        unresolved_conflicts,
+    };
+    use crate::error::Result;
+    use crate::sync::{
+        commit, stage_add_file,
+        tests::{repo_init, repo_init_empty},
+    };
+    use git2::build::CheckoutBuilder;
+    use std::{fs, path::Path};
+
+    const CONFLICTED: &str = "start
+<<<<<<< HEAD
+ours 1
+=======
+theirs 1
+>>>>>>> feature
+middle
+<<<<<<< HEAD
+ours 2
+||||||| base
+base 2
+=======
+theirs 2
+>>>>>>> feature
+end
+";
+
+    #[test]
+    fn test_conflict_markers() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::write(root.join("foo.txt"), CONFLICTED)?;
+
+        assert_eq!(conflict_marker_count(repo_path, "foo.txt")?, 2);
+
+        // resolve the first region
+        let resolved = CONFLICTED.replacen(
+            "<<<<<<< HEAD\nours 1\n=======\ntheirs 1\n>>>>>>> feature\n",
+            "ours 1\n",
+            1,
+        );
+        fs::write(root.join("foo.txt"), resolved)?;
+
+        assert_eq!(conflict_marker_count(repo_path, "foo.txt")?, 1);
+
+        assert_eq!(
+            conflict_marker_count(repo_path, "missing").is_err(),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_partially_resolved() {
+        assert_eq!(
+            count_conflict_regions(
+                "<<<<<<< HEAD\nours\ntheirs\n>>>>>>> x\n=======\n"
+            ),
+            0
+        );
+        assert_eq!(
+            count_conflict_regions("=========\n>>>>>>>>\n"),
+            0
+        );
+        assert_eq!(
+            count_conflict_regions("<<<<<<<\na\n=======\nb\n>>>>>>>"),
+            1
+        );
+    }
+
+    #[test]
+    fn test_unresolved_conflicts() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let commit_files = |content: &str| {
+            fs::write(root.join("foo.txt"), content).unwrap();
+            fs::write(root.join("bar.txt"), content).unwrap();
+            stage_add_file(repo_path, Path::new("foo.txt")).unwrap();
+            stage_add_file(repo_path, Path::new("bar.txt")).unwrap();
+            commit(repo_path, content).unwrap()
+        };
+
+        commit_files("base\n");
+        let base = repo.head()?.peel_to_commit()?;
+        repo.branch("other", &base, false)?;
+
+        commit_files("ours\n");
+
+        repo.set_head("refs/heads/other")?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+        let theirs = commit_files("theirs\n");
+
+        repo.set_head("refs/heads/master")?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+
+        let theirs = repo.find_annotated_commit(theirs.into())?;
+        repo.merge(&[&theirs], None, None)?;
+
+        assert_eq!(
+            unresolved_conflicts(repo_path)?,
+            vec!["bar.txt", "foo.txt"]
+        );
+
+        // foo is resolved properly, bar staged with its markers
+        fs::write(root.join("foo.txt"), "resolved\n")?;
+        stage_add_file(repo_path, Path::new("foo.txt"))?;
+        stage_add_file(repo_path, Path::new("bar.txt"))?;
+
+        // never conflicted, so its markers are meant to be there
+        fs::write(root.join("baz.txt"), CONFLICTED)?;
+        stage_add_file(repo_path, Path::new("baz.txt"))?;
+
+        assert_eq!(conflict_marker_count(repo_path, "bar.txt")?, 1);
+        assert_eq!(unresolved_conflicts(repo_path)?, vec!["bar.txt"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unresolved_conflicts_unborn() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::write(root.join("foo.txt"), CONFLICTED)?;
+        stage_add_file(repo_path, Path::new("foo.txt"))?;
+
+        assert_eq!(unresolved_conflicts(repo_path)?.is_empty(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mark_resolved() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let commit_file = |content: &str| {
+            fs::write(root.join("foo.txt"), content).unwrap();
+            stage_add_file(repo_path, Path::new("foo.txt")).unwrap();
+            commit(repo_path, content).unwrap()
+        };
+
+        commit_file("base\n");
+        let base = repo.head()?.peel_to_commit()?;
+        repo.branch("other", &base, false)?;
+
+        commit_file("ours\n");
+
+        repo.set_head("refs/heads/other")?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+        let theirs = commit_file("theirs\n");
+
+        repo.set_head("refs/heads/master")?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+
+        let theirs = repo.find_annotated_commit(theirs.into())?;
+        repo.merge(&[&theirs], None, None)?;
+
+        assert_eq!(repo.index()?.has_conflicts(), true);
+
+        // markers are still in there
+        assert_eq!(
+            mark_resolved(repo_path, "foo.txt").is_err(),
+            true
+        );
+        assert_eq!(repo.index()?.has_conflicts(), true);
+
+        fs::write(root.join("foo.txt"), "resolved\n")?;
+        mark_resolved(repo_path, "foo.txt")?;
+
+        let mut index = repo.index()?;
+        index.read(true)?;
+        assert_eq!(index.has_conflicts(), false);
+        assert_eq!(unresolved_conflicts(repo_path)?.is_empty(), true);
+
+        let entry = index.get_path(Path::new("foo.txt"), 0).unwrap();
+        assert_eq!(
+            repo.find_blob(entry.id)?.content(),
+            b"resolved\n"
+        );
+
+        Ok(())
+    }
+}
@@ -2,16 +2,27 @@
 
 use super::{
     commit_files::get_commit_diff,
+    stash::get_stash_id,
+    status::StatusItemType,
     utils::{self, get_head_repo, work_dir},
     CommitId,
 };
 use crate::{error::Error, error::Result, hash};
 use git2::{
-    Delta, Diff, DiffDelta, DiffFormat, DiffHunk, DiffOptions, Patch,
+    AttrCheckFlags, AttrValue, Delta, Diff, DiffDelta, DiffFile,
+    DiffFormat, DiffHunk, FileMode, ObjectType, Oid, Patch,
     Repository,
 };
 use scopetime::scope_time;
-use std::{cell::RefCell, fs, path::Path, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    fs,
+    io::Write,
+    path::Path,
+    process::Command,
+    rc::Rc,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 /// type of diff of a single line
 #[derive(Copy, Clone, PartialEq, Hash, Debug)]
@@ -32,6 +43,18 @@ impl Default for DiffLineType {
     }
 }
 
+impl DiffLineType {
+    /// prefix of such a line in a patch, None for headers
+    const fn origin(self) -> Option<char> {
+        match self {
+            DiffLineType::Header => None,
+            DiffLineType::None => Some(' '),
+            DiffLineType::Add => Some('+'),
+            DiffLineType::Delete => Some('-'),
+        }
+    }
+}
+
 ///
 #[derive(Default, Clone, Hash, Debug)]
 pub struct DiffLine {
@@ -39,14 +62,69 @@ pub struct DiffLine {
     pub content: String,
     ///
     pub line_type: DiffLineType,
+    /// ends with spaces or tabs (like `git diff --check`)
+    pub trailing_whitespace: bool,
+    /// ends with `\r\n`
+    pub crlf: bool,
+}
+
+impl DiffLine {
+    /// `\ No newline at end of file` pseudo line
+    fn is_eof_marker(&self) -> bool {
+        self.content.starts_with("\n\\")
+    }
+
+    fn check_whitespace(&mut self) {
+        let content =
+            self.content.strip_suffix('\n').unwrap_or(&self.content);
+
+        self.crlf = content.ends_with('\r');
+
+        let content = content.strip_suffix('\r').unwrap_or(content);
+
+        self.trailing_whitespace =
+            content.ends_with(&[' ', '\t'][..]);
+    }
+}
+
+impl From<&git2::DiffLine<'_>> for DiffLine {
+    fn from(line: &git2::DiffLine) -> Self {
+        let line_type = match line.origin() {
+            'H' => DiffLineType::Header,
+            '<' | '-' => DiffLineType::Delete,
+            '>' | '+' => DiffLineType::Add,
+            _ => DiffLineType::None,
+        };
+
+        let mut res = Self {
+            content: String::from_utf8_lossy(line.content())
+                .to_string(),
+            line_type,
+            ..Self::default()
+        };
+
+        if line_type == DiffLineType::Add {
+            res.check_whitespace();
+        }
+
+        res
+    }
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Hash)]
-pub(crate) struct HunkHeader {
-    old_start: u32,
-    old_lines: u32,
-    new_start: u32,
-    new_lines: u32,
+/// parts of a hunk header line (`@@ -1,5 +1,6 @@ fn main()`)
+#[derive(Debug, Default, Clone, PartialEq, Hash)]
+pub struct HunkHeader {
+    ///
+    pub old_start: u32,
+    ///
+    pub old_lines: u32,
+    ///
+    pub new_start: u32,
+    ///
+    pub new_lines: u32,
+    /// text git puts after the ranges
+    /// (usually the enclosing function)
+    pub function_context: Option<String>,
 }
 
 impl From<DiffHunk<'_>> for HunkHeader {
@@ -56,17 +134,71 @@ impl From<DiffHunk<'_>> for HunkHeader {
             old_lines: h.old_lines(),
             new_start: h.new_start(),
             new_lines: h.new_lines(),
+            function_context: parse_function_context(
+                &String::from_utf8_lossy(h.header()),
+            ),
         }
     }
 }
 
+/// text following the closing `@@` of a hunk header line
+fn parse_function_context(header: &str) -> Option<String> {
+    let header = header.strip_prefix("@@")?;
+    let end = header.find("@@")?;
+    let context = header[end + 2..].trim();
+
+    if context.is_empty() {
+        None
+    } else {
+        Some(context.to_string())
+    }
+}
+
 /// single diff hunk
 #[derive(Default, Clone, Hash, Debug)]
 pub struct Hunk {
     /// hash of the hunk header
     pub header_hash: u64,
+    ///
+    pub header: HunkHeader,
     /// list of `DiffLine`s
     pub lines: Vec<DiffLine>,
+    /// the added and deleted lines only differ in whitespace
+    pub whitespace_only: bool,
+}
+
+impl Hunk {
+    fn new(header: HunkHeader, lines: Vec<DiffLine>) -> Self {
+        let whitespace_only = is_whitespace_only(&lines);
+
+        Self {
+            header_hash: hash(&header),
+            header,
+            lines,
+            whitespace_only,
+        }
+    }
+}
+
+/// true if there are changes and removing all whitespace from the
+/// deleted and added lines leaves the same content
+fn is_whitespace_only(lines: &[DiffLine]) -> bool {
+    let content = |line_type| {
+        lines
+            .iter()
+            .filter(|l| l.line_type == line_type)
+            .flat_map(|l| l.content.chars())
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+    };
+
+    let changed = lines.iter().any(|l| {
+        l.line_type == DiffLineType::Add
+            || l.line_type == DiffLineType::Delete
+    });
+
+    changed
+        && content(DiffLineType::Add) == content(DiffLineType::Delete)
 }
 
 /// collection of hunks, sum of all diff lines
@@ -82,6 +214,353 @@ pub struct FileDiff {
     pub sizes: (u64, u64),
     /// size delta in bytes
     pub size_delta: i64,
+    /// git considers the file content binary
+    pub is_binary: bool,
+    /// file exceeds `DiffOptions::max_file_size`, no hunks computed
+    pub too_large: bool,
+    /// file is a git lfs pointer (new version, old one if deleted)
+    pub lfs: Option<LfsPointer>,
+    /// kind of change, None if the diff is not about a single file
+    pub status: Option<StatusItemType>,
+    /// old and new git file mode (like `0o100644`), 0 if missing
+    pub modes: (u32, u32),
+}
+
+impl FileDiff {
+    /// unified diff patch of `path` (as `git diff` would print it)
+    /// that `git apply` accepts, binary content is not supported
+    pub fn to_unified_string(&self, path: &str) -> String {
+        let is_new = self.status == Some(StatusItemType::New);
+        let is_deleted = self.status == Some(StatusItemType::Deleted);
+        let (old_mode, new_mode) = self.modes;
+
+        let mut res = format!("diff --git a/{0} b/{0}\n", path);
+
+        // libgit2 insists on an index line for added/deleted files
+        if is_new {
+            res.push_str(&format!(
+                "new file mode {:o}\nindex 0000000..{}\n",
+                new_mode,
+                self.blob_id(DiffLineType::Add)
+            ));
+        } else if is_deleted {
+            res.push_str(&format!(
+                "deleted file mode {:o}\nindex {}..0000000\n",
+                old_mode,
+                self.blob_id(DiffLineType::Delete)
+            ));
+        } else if old_mode != new_mode {
+            res.push_str(&format!(
+                "old mode {:o}\nnew mode {:o}\n",
+                old_mode, new_mode
+            ));
+        }
+
+        // empty files and mode changes come without any hunk
+        if self.hunks.is_empty() {
+            return res;
+        }
+
+        if is_new {
+            res.push_str("--- /dev/null\n");
+        } else {
+            res.push_str(&format!("--- a/{}\n", path));
+        }
+
+        if is_deleted {
+            res.push_str("+++ /dev/null\n");
+        } else {
+            res.push_str(&format!("+++ b/{}\n", path));
+        }
+
+        for hunk in &self.hunks {
+            let h = &hunk.header;
+            res.push_str(&format!(
+                "@@ -{},{} +{},{} @@",
+                h.old_start, h.old_lines, h.new_start, h.new_lines
+            ));
+            if let Some(context) = &h.function_context {
+                res.push(' ');
+                res.push_str(context);
+            }
+            res.push('\n');
+
+            for line in &hunk.lines {
+                // the `\ No newline at end of file` marker lines
+                // are added back below
+                if line.is_eof_marker() {
+                    continue;
+                }
+
+                let origin = match line.line_type.origin() {
+                    Some(origin) => origin,
+                    None => continue,
+                };
+
+                res.push(origin);
+                res.push_str(&line.content);
+                if !line.content.ends_with('\n') {
+                    res.push_str("\n\\ No newline at end of file\n");
+                }
+            }
+        }
+
+        res
+    }
+
+    /// abbreviated blob id of the content made up of all lines
+    /// of `line_type` (the whole file for added/deleted ones)
+    fn blob_id(&self, line_type: DiffLineType) -> String {
+        let content = self
+            .hunks
+            .iter()
+            .flat_map(|h| h.lines.iter())
+            .filter(|l| {
+                l.line_type == line_type && !l.is_eof_marker()
+            })
+            .map(|l| l.content.as_str())
+            .collect::<String>();
+
+        Oid::hash_object(ObjectType::Blob, content.as_bytes())
+            .map(|id| id.to_string()[..7].to_string())
+            .unwrap_or_else(|_| String::from("0000000"))
+    }
+
+    /// content of the lines at `indices` (counted across all hunks
+    /// like `lines`) in diff order, hunk headers are left out.
+    /// `with_prefix` keeps the `+`/`-`/` ` origin of each line
+    pub fn selected_lines_text(
+        &self,
+        indices: &[usize],
+        with_prefix: bool,
+    ) -> String {
+        let mut res = String::new();
+
+        let lines = self.hunks.iter().flat_map(|h| h.lines.iter());
+
+        for (_, line) in
+            lines.enumerate().filter(|(idx, _)| indices.contains(idx))
+        {
+            let origin = match line.line_type.origin() {
+                Some(_) if line.is_eof_marker() => continue,
+                Some(origin) => origin,
+                None => continue,
+            };
+
+            if with_prefix {
+                res.push(origin);
+            }
+            res.push_str(&line.content);
+        }
+
+        res
+    }
+}
+
+/// content of a git lfs pointer file
+#[derive(Default, Clone, Hash, PartialEq, Debug)]
+pub struct LfsPointer {
+    /// sha256 of the actual object
+    pub oid: String,
+    /// size of the actual object in bytes
+    pub size: u64,
+}
+
+/// lfs pointer files are always smaller than this
+/// (see https://github.com/git-lfs/git-lfs/blob/main/docs/spec.md)
+const LFS_POINTER_MAX_SIZE: u64 = 1024;
+
+/// parses `content` as a git lfs pointer file
+pub fn parse_lfs_pointer(content: &[u8]) -> Option<LfsPointer> {
+    if content.len() as u64 >= LFS_POINTER_MAX_SIZE {
+        return None;
+    }
+
+    let content = std::str::from_utf8(content).ok()?;
+    let mut lines = content.lines();
+
+    if !lines
+        .next()?
+        .starts_with("version https://git-lfs.github.com/spec/")
+    {
+        return None;
+    }
+
+    let mut oid = None;
+    let mut size = None;
+
+    for line in lines {
+        if let Some(value) = line.strip_prefix("oid sha256:") {
+            oid = Some(value.trim().to_string());
+        } else if let Some(value) = line.strip_prefix("size ") {
+            size = value.trim().parse().ok();
+        }
+    }
+
+    Some(LfsPointer {
+        oid: oid?,
+        size: size?,
+    })
+}
+
+/// which tabs `expand_tabs` replaces
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TabExpansion {
+    /// only the indentation
+    Leading,
+    /// every tab of a line
+    All,
+}
+
+/// copy of `diff` with tabs in the line contents replaced by spaces
+/// up to the next multiple of `tab_width`, hunk headers are kept
+pub fn expand_tabs(
+    diff: &FileDiff,
+    tab_width: usize,
+    mode: TabExpansion,
+) -> FileDiff {
+    let mut res = diff.clone();
+
+    for line in res.hunks.iter_mut().flat_map(|h| h.lines.iter_mut())
+    {
+        if line.line_type != DiffLineType::Header {
+            line.content =
+                expand_line_tabs(&line.content, tab_width, mode);
+        }
+    }
+
+    res
+}
+
+fn expand_line_tabs(
+    line: &str,
+    tab_width: usize,
+    mode: TabExpansion,
+) -> String {
+    let tab_width = tab_width.max(1);
+    let mut res = String::with_capacity(line.len());
+    let mut column = 0;
+    let mut leading = true;
+
+    for c in line.chars() {
+        if c == '\t' && (leading || mode == TabExpansion::All) {
+            let spaces = tab_width - column % tab_width;
+            res.push_str(&" ".repeat(spaces));
+            column += spaces;
+            continue;
+        }
+
+        if c != ' ' {
+            leading = false;
+        }
+
+        res.push(c);
+        column += 1;
+    }
+
+    res
+}
+
+/// row of a side by side diff, `None` pads the side
+/// that has no counterpart line
+#[derive(Default, Clone, Hash, Debug)]
+pub struct SplitLine {
+    /// old version
+    pub left: Option<DiffLine>,
+    /// new version
+    pub right: Option<DiffLine>,
+}
+
+/// hunk laid out in two columns
+#[derive(Default, Clone, Hash, Debug)]
+pub struct SplitHunk {
+    /// hash of the hunk header
+    pub header_hash: u64,
+    ///
+    pub lines: Vec<SplitLine>,
+}
+
+/// `FileDiff` as old and new version side by side
+#[derive(Default, Clone, Hash, Debug)]
+pub struct SplitDiff {
+    ///
+    pub hunks: Vec<SplitHunk>,
+}
+
+impl From<&Hunk> for SplitHunk {
+    fn from(hunk: &Hunk) -> Self {
+        let mut lines = Vec::with_capacity(hunk.lines.len());
+        let mut deleted = Vec::new();
+        let mut added = Vec::new();
+
+        let flush = |lines: &mut Vec<SplitLine>,
+                     deleted: &mut Vec<DiffLine>,
+                     added: &mut Vec<DiffLine>| {
+            let rows = deleted.len().max(added.len());
+            let mut deleted = deleted.drain(..);
+            let mut added = added.drain(..);
+            for _ in 0..rows {
+                lines.push(SplitLine {
+                    left: deleted.next(),
+                    right: added.next(),
+                });
+            }
+        };
+
+        for line in &hunk.lines {
+            match line.line_type {
+                DiffLineType::Delete => {
+                    // a new block of changes starts
+                    if !added.is_empty() {
+                        flush(&mut lines, &mut deleted, &mut added);
+                    }
+                    deleted.push(line.clone());
+                }
+                DiffLineType::Add => added.push(line.clone()),
+                DiffLineType::None | DiffLineType::Header => {
+                    flush(&mut lines, &mut deleted, &mut added);
+                    lines.push(SplitLine {
+                        left: Some(line.clone()),
+                        right: Some(line.clone()),
+                    });
+                }
+            }
+        }
+
+        flush(&mut lines, &mut deleted, &mut added);
+
+        Self {
+            header_hash: hunk.header_hash,
+            lines,
+        }
+    }
+}
+
+impl From<&FileDiff> for SplitDiff {
+    fn from(diff: &FileDiff) -> Self {
+        Self {
+            hunks: diff.hunks.iter().map(SplitHunk::from).collect(),
+        }
+    }
+}
+
+/// options tweaking how a `FileDiff` is generated
+#[derive(Default, Clone, Copy, Hash, PartialEq, Debug)]
+pub struct DiffOptions {
+    /// represent binary files by a synthetic line describing the
+    /// size change instead of returning no hunks at all
+    pub binary_fallback: bool,
+    /// show the content of the file a new symlink points to
+    /// instead of the link target path itself
+    pub follow_symlinks: bool,
+    /// files exceeding this size (in bytes) on either side are not
+    /// diffed, the result is flagged `too_large` instead
+    pub max_file_size: Option<u64>,
+    /// run the `textconv` command of the diff driver configured in
+    /// gitattributes on both sides before diffing (spawns processes)
+    pub textconv: bool,
+    /// flag whitespace errors on all lines, not only added ones
+    pub whitespace_all_lines: bool,
 }
 
 pub(crate) fn get_diff_raw<'a>(
@@ -92,7 +571,7 @@ pub(crate) fn get_diff_raw<'a>(
 ) -> Result<Diff<'a>> {
     // scope_time!("get_diff_raw");
 
-    let mut opt = DiffOptions::new();
+    let mut opt = git2::DiffOptions::new();
     opt.pathspec(p);
     opt.reverse(reverse);
 
@@ -128,149 +607,536 @@ pub fn get_diff(
     repo_path: &str,
     p: String,
     stage: bool,
+) -> Result<FileDiff> {
+    get_diff_with_options(repo_path, p, stage, DiffOptions::default())
+}
+
+/// like `get_diff` but allows to customize the result via `options`
+pub fn get_diff_with_options(
+    repo_path: &str,
+    p: String,
+    stage: bool,
+    options: DiffOptions,
 ) -> Result<FileDiff> {
     scope_time!("get_diff");
 
     let repo = utils::repo(repo_path)?;
-    let work_dir = work_dir(&repo);
     let diff = get_diff_raw(&repo, &p, stage, false)?;
 
-    raw_diff_to_file_diff(&diff, work_dir)
+    raw_diff_to_file_diff(&repo, &diff, options)
 }
 
-/// returns diff of a specific file inside a commit
-/// see `get_commit_diff`
-pub fn get_diff_commit(
+/// single hunk of the diff of `p` (see `get_diff`) identified by
+/// its `header_hash`, diffing stops once the hunk is complete
+pub fn get_hunk(
     repo_path: &str,
-    id: CommitId,
     p: String,
-) -> Result<FileDiff> {
-    scope_time!("get_diff_commit");
+    stage: bool,
+    header_hash: u64,
+) -> Result<Option<Hunk>> {
+    scope_time!("get_hunk");
 
     let repo = utils::repo(repo_path)?;
-    let work_dir = work_dir(&repo);
-    let diff = get_commit_diff(&repo, id, Some(p))?;
+    let diff = get_diff_raw(&repo, &p, stage, false)?;
 
-    raw_diff_to_file_diff(&diff, work_dir)
-}
+    let untracked = diff
+        .deltas()
+        .any(|delta| delta.status() == Delta::Untracked);
+
+    // untracked content is diffed separately, see `get_diff`
+    if untracked {
+        let file_diff = raw_diff_to_file_diff(
+            &repo,
+            &diff,
+            DiffOptions::default(),
+        )?;
+
+        return Ok(file_diff
+            .hunks
+            .into_iter()
+            .find(|hunk| hunk.header_hash == header_hash));
+    }
 
-///
-fn raw_diff_to_file_diff<'a>(
-    diff: &'a Diff,
-    work_dir: &Path,
-) -> Result<FileDiff> {
-    let res = Rc::new(RefCell::new(FileDiff::default()));
-    {
-        let mut current_lines = Vec::new();
-        let mut current_hunk: Option<HunkHeader> = None;
+    let mut lines: Option<(HunkHeader, Vec<DiffLine>)> = None;
 
-        let res_cell = Rc::clone(&res);
-        let adder = move |header: &HunkHeader,
-                          lines: &Vec<DiffLine>| {
-            let mut res = res_cell.borrow_mut();
-            res.hunks.push(Hunk {
-                header_hash: hash(header),
-                lines: lines.clone(),
-            });
-            res.lines += lines.len();
+    let printed = diff.print(DiffFormat::Patch, |_, hunk, line| {
+        let hunk = match hunk {
+            Some(hunk) => hunk,
+            None => return true,
         };
 
-        let res_cell = Rc::clone(&res);
-        let mut put = |delta: DiffDelta,
-                       hunk: Option<DiffHunk>,
-                       line: git2::DiffLine| {
-            {
-                let mut res = res_cell.borrow_mut();
-                res.sizes = (
-                    delta.old_file().size(),
-                    delta.new_file().size(),
-                );
-                res.size_delta = (res.sizes.1 as i64)
-                    .saturating_sub(res.sizes.0 as i64);
+        let header = HunkHeader::from(hunk);
+        let matches = hash(&header) == header_hash;
+
+        match lines.as_mut() {
+            Some((_, found)) if matches => {
+                found.push(DiffLine::from(&line));
+                true
             }
-            if let Some(hunk) = hunk {
-                let hunk_header = HunkHeader::from(hunk);
+            // the next hunk started, we are done
+            Some(_) => false,
+            None if matches => {
+                lines = Some((header, vec![DiffLine::from(&line)]));
+                true
+            }
+            None => true,
+        }
+    });
 
-                match current_hunk {
-                    None => current_hunk = Some(hunk_header),
-                    Some(h) if h != hunk_header => {
-                        adder(&h, &current_lines);
-                        current_lines.clear();
-                        current_hunk = Some(hunk_header)
-                    }
-                    _ => (),
-                }
+    if lines.is_none() {
+        printed?;
+    }
 
-                let line_type = match line.origin() {
-                    'H' => DiffLineType::Header,
-                    '<' | '-' => DiffLineType::Delete,
-                    '>' | '+' => DiffLineType::Add,
-                    _ => DiffLineType::None,
-                };
+    Ok(lines.map(|(header, lines)| Hunk::new(header, lines)))
+}
 
-                let diff_line = DiffLine {
-                    content: String::from_utf8_lossy(line.content())
-                        .to_string(),
-                    line_type,
-                };
+/// like `get_diff_with_options` but stops and fails with
+/// `Error::Cancelled` once `cancel` is set (from another thread)
+pub fn get_diff_cancellable(
+    repo_path: &str,
+    p: String,
+    stage: bool,
+    options: DiffOptions,
+    cancel: &AtomicBool,
+) -> Result<FileDiff> {
+    get_diff_with_progress(repo_path, p, stage, options, |_| {
+        !cancel.load(Ordering::Relaxed)
+    })
+}
 
-                current_lines.push(diff_line);
-            }
-        };
+/// like `get_diff_with_options` but reports the number of lines
+/// diffed so far for every line, returning false from `progress`
+/// cancels the call with `Error::Cancelled`
+pub fn get_diff_with_progress<F>(
+    repo_path: &str,
+    p: String,
+    stage: bool,
+    options: DiffOptions,
+    progress: F,
+) -> Result<FileDiff>
+where
+    F: FnMut(usize) -> bool,
+{
+    scope_time!("get_diff_with_progress");
 
-        let new_file_diff = if diff.deltas().len() == 1 {
-            let delta: DiffDelta = diff
-                .deltas()
-                .next()
-                .expect("it's safe to unwrap here because we check first that diff.deltas has a single element");
+    let repo = utils::repo(repo_path)?;
+    let diff = get_diff_raw(&repo, &p, stage, false)?;
 
-            if delta.status() == Delta::Untracked {
-                let relative_path =
-                    delta.new_file().path().ok_or_else(|| {
-                        Error::Generic(
-                            "new file path is unspecified."
-                                .to_string(),
-                        )
-                    })?;
+    raw_diff_to_file_diff_with_progress(
+        &repo, &diff, options, progress,
+    )
+}
 
-                let newfile_path = work_dir.join(relative_path);
+/// aborting a git2 callback surfaces as an error
+fn cancelled_or(e: git2::Error, cancelled: &Cell<bool>) -> Error {
+    if cancelled.get() {
+        Error::Cancelled
+    } else {
+        e.into()
+    }
+}
 
-                if let Some(newfile_content) =
-                    new_file_content(&newfile_path)
-                {
-                    let mut patch = Patch::from_buffers(
-                        &[],
-                        None,
-                        newfile_content.as_slice(),
-                        Some(&newfile_path),
-                        None,
-                    )?;
+/// staged and unstaged diff of a single file at once
+/// (opening the repo only once), returned as `(staged, unstaged)`
+pub fn get_diff_full(
+    repo_path: &str,
+    p: String,
+) -> Result<(FileDiff, FileDiff)> {
+    scope_time!("get_diff_full");
 
-                    patch
-                    .print(&mut |delta, hunk:Option<DiffHunk>, line: git2::DiffLine| {
-                        put(delta,hunk,line);
-                        true
-                    })?;
+    let repo = utils::repo(repo_path)?;
 
-                    true
-                } else {
-                    false
-                }
-            } else {
-                false
-            }
-        } else {
-            false
-        };
+    let staged = get_diff_raw(&repo, &p, true, false)?;
+    let staged = raw_diff_to_file_diff(
+        &repo,
+        &staged,
+        DiffOptions::default(),
+    )?;
+
+    let unstaged = get_diff_raw(&repo, &p, false, false)?;
+    let unstaged = raw_diff_to_file_diff(
+        &repo,
+        &unstaged,
+        DiffOptions::default(),
+    )?;
+
+    Ok((staged, unstaged))
+}
 
-        if !new_file_diff {
-            diff.print(
-                DiffFormat::Patch,
-                move |delta, hunk, line: git2::DiffLine| {
-                    put(delta, hunk, line);
-                    true
-                },
-            )?;
+/// like `get_diff` but pairs deleted and added lines of each hunk
+/// to be shown side by side
+pub fn get_diff_split(
+    repo_path: &str,
+    p: String,
+    stage: bool,
+) -> Result<SplitDiff> {
+    Ok(SplitDiff::from(&get_diff(repo_path, p, stage)?))
+}
+
+/// like `get_diff` but for many files at once, computed in parallel
+/// on the rayon thread pool. results are in the order of `paths`.
+///
+/// every task opens its own `Repository` because git2 objects can
+/// not be shared between threads (libgit2 itself is built thread safe)
+pub fn get_diffs(
+    repo_path: &str,
+    paths: &[String],
+    stage: bool,
+) -> Result<Vec<FileDiff>> {
+    scope_time!("get_diffs");
+
+    let mut results: Vec<Option<Result<FileDiff>>> =
+        paths.iter().map(|_| None).collect();
+
+    rayon_core::scope(|s| {
+        for (p, res) in paths.iter().zip(results.iter_mut()) {
+            s.spawn(move |_| {
+                *res = Some(get_diff(repo_path, p.clone(), stage));
+            });
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|res| {
+            res.unwrap_or_else(|| {
+                Err(Error::Generic("diff not computed".into()))
+            })
+        })
+        .collect()
+}
+
+/// returns diff of a specific file inside a commit
+/// see `get_commit_diff`
+pub fn get_diff_commit(
+    repo_path: &str,
+    id: CommitId,
+    p: String,
+) -> Result<FileDiff> {
+    scope_time!("get_diff_commit");
+
+    let repo = utils::repo(repo_path)?;
+    let diff = get_commit_diff(&repo, id, Some(p))?;
+
+    raw_diff_to_file_diff(&repo, &diff, DiffOptions::default())
+}
+
+/// changes of the stash at `index` against the commit it is based on
+/// (including its untracked files), restricted to `path` if set
+pub fn get_stash_diff(
+    repo_path: &str,
+    index: usize,
+    path: Option<String>,
+) -> Result<FileDiff> {
+    scope_time!("get_stash_diff");
+
+    let mut repo = utils::open_repo(repo_path)?;
+    let id = get_stash_id(&mut repo, index)?;
+
+    let diff = get_commit_diff(&repo, id.into(), path)?;
+
+    raw_diff_to_file_diff(&repo, &diff, DiffOptions::default())
+}
+
+/// returns diff of a specific file in the workdir against its state
+/// in an arbitrary commit (untracked files are not supported here)
+pub fn get_diff_workdir_vs_commit(
+    repo_path: &str,
+    p: String,
+    id: CommitId,
+) -> Result<FileDiff> {
+    scope_time!("get_diff_workdir_vs_commit");
+
+    let repo = utils::repo(repo_path)?;
+
+    let tree = repo.find_commit(id.into())?.tree()?;
+
+    let mut opt = git2::DiffOptions::new();
+    opt.pathspec(p);
+
+    let diff = repo.diff_tree_to_workdir_with_index(
+        Some(&tree),
+        Some(&mut opt),
+    )?;
+
+    raw_diff_to_file_diff(&repo, &diff, DiffOptions::default())
+}
+
+/// hash of everything the diff of `p` is computed from: `HEAD`,
+/// the index entry and (when not diffing the stage) size and
+/// modification time of the file in the workdir
+pub(crate) fn get_diff_cache_key(
+    repo_path: &str,
+    p: &str,
+    stage: bool,
+) -> Result<u64> {
+    scope_time!("get_diff_cache_key");
+
+    let repo = utils::repo(repo_path)?;
+
+    let head = get_head_repo(&repo).ok();
+    let index_entry =
+        repo.index()?.get_path(Path::new(p), 0).map(|e| e.id);
+    let workdir_file = if stage {
+        None
+    } else {
+        fs::symlink_metadata(work_dir(&repo)?.join(p))
+            .ok()
+            .map(|meta| (meta.len(), meta.modified().ok()))
+    };
+
+    Ok(hash(&(p, stage, head, index_entry, workdir_file)))
+}
+
+/// size of a diff side without loading the actual content
+fn diff_file_size(repo: &Repository, file: &DiffFile) -> Result<u64> {
+    if file.size() > 0 || file.id().is_zero() {
+        return Ok(file.size());
+    }
+
+    let (size, _) = repo.odb()?.read_header(file.id())?;
+
+    Ok(size as u64)
+}
+
+/// returns a `FileDiff` flagged as `too_large` if any side of any
+/// delta exceeds `max_size`
+fn too_large_file_diff(
+    repo: &Repository,
+    diff: &Diff,
+    max_size: u64,
+) -> Result<Option<FileDiff>> {
+    let work_dir = repo.workdir();
+
+    for delta in diff.deltas() {
+        let old_size = diff_file_size(repo, &delta.old_file())?;
+        let new_size = if delta.status() == Delta::Untracked {
+            delta
+                .new_file()
+                .path()
+                .and_then(|p| {
+                    fs::symlink_metadata(work_dir?.join(p)).ok()
+                })
+                .map_or(0, |meta| meta.len())
+        } else {
+            diff_file_size(repo, &delta.new_file())?
+        };
+
+        if old_size > max_size || new_size > max_size {
+            return Ok(Some(FileDiff {
+                untracked: delta.status() == Delta::Untracked,
+                status: Some(delta.status().into()),
+                modes: (
+                    file_mode(delta.old_file().mode()),
+                    file_mode(delta.new_file().mode()),
+                ),
+                sizes: (old_size, new_size),
+                size_delta: (new_size as i64)
+                    .saturating_sub(old_size as i64),
+                too_large: true,
+                ..FileDiff::default()
+            }));
+        }
+    }
+
+    Ok(None)
+}
+
+///
+fn raw_diff_to_file_diff<'a>(
+    repo: &Repository,
+    diff: &'a Diff,
+    options: DiffOptions,
+) -> Result<FileDiff> {
+    raw_diff_to_file_diff_with_progress(repo, diff, options, |_| true)
+}
+
+/// fails with `Error::Cancelled` as soon as `progress` returns false
+fn raw_diff_to_file_diff_with_progress<F>(
+    repo: &Repository,
+    diff: &Diff,
+    options: DiffOptions,
+    mut progress: F,
+) -> Result<FileDiff>
+where
+    F: FnMut(usize) -> bool,
+{
+    if let Some(max_size) = options.max_file_size {
+        if let Some(res) = too_large_file_diff(repo, diff, max_size)?
+        {
+            return Ok(res);
+        }
+    }
+
+    // bare repos have no workdir, their diffs come from the odb only
+    let work_dir = repo.workdir();
+
+    let res = Rc::new(RefCell::new(FileDiff::default()));
+    {
+        let mut current_lines = Vec::new();
+        let mut current_hunk: Option<HunkHeader> = None;
+
+        let res_cell = Rc::clone(&res);
+        let adder = move |header: &HunkHeader,
+                          lines: &Vec<DiffLine>| {
+            let mut res = res_cell.borrow_mut();
+            res.hunks.push(Hunk::new(header.clone(), lines.clone()));
+            res.lines += lines.len();
+        };
+
+        let cancelled = Cell::new(false);
+        let mut lines_done = 0;
+
+        let res_cell = Rc::clone(&res);
+        let mut put = |delta: DiffDelta,
+                       hunk: Option<DiffHunk>,
+                       line: git2::DiffLine| {
+            {
+                let mut res = res_cell.borrow_mut();
+                res.sizes = (
+                    delta.old_file().size(),
+                    delta.new_file().size(),
+                );
+                res.size_delta = (res.sizes.1 as i64)
+                    .saturating_sub(res.sizes.0 as i64);
+                res.is_binary = delta.flags().is_binary();
+            }
+            if let Some(hunk) = hunk {
+                let hunk_header = HunkHeader::from(hunk);
+
+                match &current_hunk {
+                    None => current_hunk = Some(hunk_header),
+                    Some(h) if *h != hunk_header => {
+                        adder(h, &current_lines);
+                        current_lines.clear();
+                        current_hunk = Some(hunk_header)
+                    }
+                    _ => (),
+                }
+
+                let mut diff_line = DiffLine::from(&line);
+
+                if options.whitespace_all_lines
+                    && diff_line.line_type != DiffLineType::Header
+                {
+                    diff_line.check_whitespace();
+                }
+
+                current_lines.push(diff_line);
+            }
+
+            lines_done += 1;
+            if !progress(lines_done) {
+                cancelled.set(true);
+            }
+            !cancelled.get()
+        };
+
+        let mut textconv_diff = false;
+
+        let new_file_diff = if diff.deltas().len() == 1 {
+            let delta: DiffDelta = diff
+                .deltas()
+                .next()
+                .expect("it's safe to unwrap here because we check first that diff.deltas has a single element");
+
+            if delta.status() == Delta::Untracked {
+                let relative_path =
+                    delta.new_file().path().ok_or_else(|| {
+                        Error::Generic(
+                            "new file path is unspecified."
+                                .to_string(),
+                        )
+                    })?;
+
+                let newfile_path = work_dir
+                    .ok_or_else(|| {
+                        Error::Generic(
+                            "unable to query workdir".to_string(),
+                        )
+                    })?
+                    .join(relative_path);
+
+                if let Some(newfile_content) = new_file_content(
+                    &newfile_path,
+                    options.follow_symlinks,
+                ) {
+                    // libgit2 only honors the attribute for diffs
+                    // it creates itself, not for those from buffers
+                    let diff_attr = diff_attr(repo, relative_path);
+
+                    if diff_attr == Some(false) {
+                        let mut res = res.borrow_mut();
+                        res.sizes = (0, newfile_content.len() as u64);
+                        res.size_delta = res.sizes.1 as i64;
+                        res.is_binary = true;
+                    } else {
+                        let mut opts = git2::DiffOptions::new();
+                        opts.force_text(diff_attr == Some(true));
+
+                        let mut patch = Patch::from_buffers(
+                            &[],
+                            None,
+                            newfile_content.as_slice(),
+                            Some(&newfile_path),
+                            Some(&mut opts),
+                        )?;
+
+                        patch
+                        .print(&mut |delta, hunk:Option<DiffHunk>, line: git2::DiffLine| {
+                            put(delta,hunk,line)
+                        })
+                        .map_err(|e| cancelled_or(e, &cancelled))?;
+                    }
+
+                    true
+                } else {
+                    false
+                }
+            } else if options.textconv {
+                if let Some((old, new)) =
+                    textconv_buffers(repo, work_dir, &delta)
+                {
+                    let path = delta.new_file().path().map(|p| {
+                        work_dir.map_or_else(
+                            || p.to_path_buf(),
+                            |w| w.join(p),
+                        )
+                    });
+
+                    let mut patch = Patch::from_buffers(
+                        &old,
+                        path.as_deref(),
+                        &new,
+                        path.as_deref(),
+                        None,
+                    )?;
+
+                    patch
+                        .print(&mut |delta, hunk, line| {
+                            put(delta, hunk, line)
+                        })
+                        .map_err(|e| cancelled_or(e, &cancelled))?;
+
+                    textconv_diff = true;
+                }
+
+                false
+            } else {
+                false
+            }
+        } else {
+            false
+        };
+
+        if !new_file_diff && !textconv_diff {
+            diff.print(
+                DiffFormat::Patch,
+                |delta, hunk, line: git2::DiffLine| {
+                    put(delta, hunk, line)
+                },
+            )
+            .map_err(|e| cancelled_or(e, &cancelled))?;
         }
 
         if !current_lines.is_empty() {
@@ -283,15 +1149,200 @@ fn raw_diff_to_file_diff<'a>(
         if new_file_diff {
             res.borrow_mut().untracked = true;
         }
+
+        if options.binary_fallback {
+            add_binary_fallback(&mut res.borrow_mut());
+        }
+
+        if diff.deltas().len() == 1 {
+            if let Some(delta) = diff.deltas().next() {
+                let mut res = res.borrow_mut();
+                res.lfs = delta_lfs_pointer(repo, work_dir, &delta);
+                res.status = Some(delta.status().into());
+                res.modes = (
+                    file_mode(delta.old_file().mode()),
+                    file_mode(delta.new_file().mode()),
+                );
+            }
+        }
     }
     let res = Rc::try_unwrap(res).expect("rc error");
     Ok(res.into_inner())
 }
 
-fn new_file_content(path: &Path) -> Option<Vec<u8>> {
+/// the octal mode git prints for `mode`
+const fn file_mode(mode: FileMode) -> u32 {
+    match mode {
+        FileMode::Unreadable => 0,
+        FileMode::Tree => 0o040_000,
+        FileMode::Blob => 0o100_644,
+        FileMode::BlobExecutable => 0o100_755,
+        FileMode::Link => 0o120_000,
+        FileMode::Commit => 0o160_000,
+    }
+}
+
+/// content of `file` from the odb or the workdir
+fn diff_file_content(
+    repo: &Repository,
+    work_dir: Option<&Path>,
+    file: &DiffFile,
+) -> Option<Vec<u8>> {
+    if let Ok(blob) = repo.find_blob(file.id()) {
+        return Some(blob.content().to_vec());
+    }
+
+    let content =
+        work_dir.and_then(|w| Some(fs::read(w.join(file.path()?))));
+
+    match content {
+        Some(Ok(content)) => Some(content),
+        _ if file.id().is_zero() => Some(Vec::new()),
+        _ => None,
+    }
+}
+
+/// both sides of `delta` converted by the `textconv` command of
+/// its diff driver, None if there is none or it fails
+fn textconv_buffers(
+    repo: &Repository,
+    work_dir: Option<&Path>,
+    delta: &DiffDelta,
+) -> Option<(Vec<u8>, Vec<u8>)> {
+    let path = delta.new_file().path()?;
+
+    let driver = repo
+        .get_attr(path, "diff", AttrCheckFlags::default())
+        .ok()??;
+
+    let cmd = repo
+        .config()
+        .ok()?
+        .get_string(&format!("diff.{}.textconv", driver))
+        .ok()?;
+
+    let old = diff_file_content(repo, work_dir, &delta.old_file())?;
+    let new = diff_file_content(repo, work_dir, &delta.new_file())?;
+
+    Some((run_textconv(&cmd, &old)?, run_textconv(&cmd, &new)?))
+}
+
+/// runs `cmd` like git does: with a file containing `content`
+/// as its argument
+fn run_textconv(cmd: &str, content: &[u8]) -> Option<Vec<u8>> {
+    let mut file = tempfile::NamedTempFile::new().ok()?;
+    file.write_all(content).ok()?;
+
+    // closed so the command can open it on any platform,
+    // removed (ignoring errors) once dropped
+    let file = file.into_temp_path();
+
+    let output = if cfg!(target_os = "windows") {
+        Command::new("cmd")
+            .arg("/C")
+            .arg(format!("{} \"{}\"", cmd, file.display()))
+            .output()
+    } else {
+        Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} \"$@\"", cmd))
+            .arg("textconv")
+            .arg(&file)
+            .output()
+    }
+    .ok()?;
+
+    if output.status.success() {
+        Some(output.stdout)
+    } else {
+        log::error!(
+            "textconv failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        None
+    }
+}
+
+/// lfs pointer of the new side of `delta` (old side if deleted)
+fn delta_lfs_pointer(
+    repo: &Repository,
+    work_dir: Option<&Path>,
+    delta: &DiffDelta,
+) -> Option<LfsPointer> {
+    let file = if delta.status() == Delta::Deleted {
+        delta.old_file()
+    } else {
+        delta.new_file()
+    };
+
+    if file.size() >= LFS_POINTER_MAX_SIZE {
+        return None;
+    }
+
+    // workdir content is not necessarily in the odb
+    let content = match repo.find_blob(file.id()) {
+        Ok(blob) => blob.content().to_vec(),
+        Err(_) => {
+            let path = work_dir?.join(file.path()?);
+            if fs::metadata(&path).ok()?.len() >= LFS_POINTER_MAX_SIZE
+            {
+                return None;
+            }
+            fs::read(path).ok()?
+        }
+    };
+
+    parse_lfs_pointer(&content)
+}
+
+/// binary files come without any hunks,
+/// this adds a single one describing the size change instead
+fn add_binary_fallback(diff: &mut FileDiff) {
+    if !diff.is_binary || !diff.hunks.is_empty() {
+        return;
+    }
+
+    let lines = vec![DiffLine {
+        content: format!(
+            "Binary files differ, {} bytes \u{2192} {} bytes",
+            diff.sizes.0, diff.sizes.1
+        ),
+        line_type: DiffLineType::Header,
+        ..DiffLine::default()
+    }];
+
+    diff.hunks.push(Hunk::new(HunkHeader::default(), lines));
+    diff.lines = 1;
+}
+
+/// `diff` gitattribute of `path` overriding the content based
+/// binary detection: Some(false) for `-diff` (always binary),
+/// Some(true) for `diff` (always text), None otherwise
+fn diff_attr(repo: &Repository, path: &Path) -> Option<bool> {
+    let value = repo
+        .get_attr(path, "diff", AttrCheckFlags::default())
+        .ok()?;
+
+    match AttrValue::from_string(value) {
+        AttrValue::False => Some(false),
+        AttrValue::True => Some(true),
+        _ => None,
+    }
+}
+
+fn new_file_content(
+    path: &Path,
+    follow_symlinks: bool,
+) -> Option<Vec<u8>> {
     if let Ok(meta) = fs::symlink_metadata(path) {
         if meta.file_type().is_symlink() {
-            if let Ok(path) = fs::read_link(path) {
+            if follow_symlinks {
+                // `metadata` traverses the link and fails
+                // on missing targets or symlink loops
+                if fs::metadata(path).ok()?.is_file() {
+                    return fs::read(path).ok();
+                }
+            } else if let Ok(path) = fs::read_link(path) {
                 return Some(
                     path.to_str()?.to_string().as_bytes().into(),
                 );
@@ -308,18 +1359,35 @@ fn new_file_content(path: &Path) -> Option<Vec<u8>> {
 
 #[cfg(test)]
 mod tests {
-    use super::{get_diff, get_diff_commit};
-    use crate::error::Result;
+    use super::{
+        expand_tabs, get_diff, get_diff_cancellable, get_diff_commit,
+        get_diff_full, get_diff_split, get_diff_with_options,
+        get_diff_with_progress, get_diff_workdir_vs_commit,
+        get_diffs, get_hunk, get_stash_diff, new_file_content,
+        parse_function_context, parse_lfs_pointer,
+        raw_diff_to_file_diff, DiffLine, DiffLineType, DiffOptions,
+        FileDiff, TabExpansion,
+    };
     use crate::sync::{
-        commit, stage_add_file,
+        add_worktree, commit, stage_add_file, stash_save,
         status::{get_status, StatusType},
         tests::{get_statuses, repo_init, repo_init_empty},
     };
+    use crate::{
+        error::{Error, Result},
+        hash,
+    };
+    use git2::{
+        build::{CheckoutBuilder, RepoBuilder},
+        ApplyLocation, Diff,
+    };
     use std::{
         fs::{self, File},
         io::Write,
         path::Path,
+        sync::atomic::{AtomicBool, Ordering},
     };
+    use tempfile::TempDir;
 
     #[test]
     fn test_untracked_subfolder() {
@@ -443,44 +1511,172 @@ mod tests {
     }
 
     #[test]
-    fn test_diff_newfile_in_sub_dir_current_dir() {
-        let file_path = Path::new("foo/foo.txt");
-        let (_td, repo) = repo_init_empty().unwrap();
+    fn test_function_context() -> Result<()> {
+        assert_eq!(
+            parse_function_context("@@ -1,5 +1,6 @@ fn main()\n")
+                .as_deref(),
+            Some("fn main()")
+        );
+        assert_eq!(parse_function_context("@@ -1 +1 @@\n"), None);
+        assert_eq!(parse_function_context("no header"), None);
+
+        let (_td, repo) = repo_init().unwrap();
         let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
 
-        let sub_path = root.join("foo/");
+        let file_path = root.join("bar.txt");
+        let content = "fn main() {\n1\n2\n3\n4\n5\n6\n}\n";
 
-        fs::create_dir_all(&sub_path).unwrap();
-        File::create(&root.join(file_path))
-            .unwrap()
-            .write_all(b"test")
-            .unwrap();
+        File::create(&file_path)?.write_all(content.as_bytes())?;
+        stage_add_file(repo_path, Path::new("bar.txt"))?;
+        File::create(&file_path)?
+            .write_all(content.replace("6", "x").as_bytes())?;
 
-        let diff = get_diff(
-            sub_path.to_str().unwrap(),
-            String::from(file_path.to_str().unwrap()),
-            false,
-        )
-        .unwrap();
+        let diff = get_diff(repo_path, "bar.txt".into(), false)?;
 
-        assert_eq!(diff.hunks[0].lines[1].content, "test");
+        assert_eq!(diff.hunks[0].header.new_start, 4);
+        assert_eq!(
+            diff.hunks[0].header.function_context.as_deref(),
+            Some("fn main() {")
+        );
+
+        Ok(())
     }
 
     #[test]
-    fn test_diff_delta_size() -> Result<()> {
-        let file_path = Path::new("bar");
-        let (_td, repo) = repo_init_empty().unwrap();
+    fn test_diff_full() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
         let root = repo.path().parent().unwrap();
         let repo_path = root.as_os_str().to_str().unwrap();
 
-        File::create(&root.join(file_path))?.write_all(b"\x00")?;
-
-        stage_add_file(repo_path, file_path).unwrap();
+        let file_path = root.join("bar.txt");
 
-        commit(repo_path, "commit").unwrap();
+        File::create(&file_path)?.write_all(HUNK_A.as_bytes())?;
+        stage_add_file(repo_path, Path::new("bar.txt"))?;
+        commit(repo_path, "c1")?;
+
+        // stage the change at the start
+        let staged = HUNK_A.replace("2\n", "2   staged\n");
+        File::create(&file_path)?.write_all(staged.as_bytes())?;
+        stage_add_file(repo_path, Path::new("bar.txt"))?;
+
+        // leave the change at the end unstaged
+        let unstaged = staged.replace("0\n", "0   unstaged\n");
+        File::create(&file_path)?.write_all(unstaged.as_bytes())?;
+
+        let (staged, unstaged) =
+            get_diff_full(repo_path, "bar.txt".into())?;
+
+        let added = |diff: &FileDiff| {
+            diff.hunks
+                .iter()
+                .flat_map(|h| h.lines.iter())
+                .filter(|l| l.line_type == DiffLineType::Add)
+                .map(|l| l.content.trim_end().to_string())
+                .collect::<Vec<_>>()
+        };
 
-        File::create(&root.join(file_path))?
-            .write_all(b"\x00\x02")?;
+        assert_eq!(added(&staged), vec!["2   staged"]);
+        assert_eq!(added(&unstaged), vec!["0   unstaged"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_diff() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let file_path = root.join("bar.txt");
+
+        File::create(&file_path)?.write_all(HUNK_A.as_bytes())?;
+        stage_add_file(repo_path, Path::new("bar.txt"))?;
+        File::create(&file_path)?.write_all(HUNK_B.as_bytes())?;
+
+        let unified = get_diff(repo_path, "bar.txt".into(), false)?;
+        let split =
+            get_diff_split(repo_path, "bar.txt".into(), false)?;
+
+        assert_eq!(split.hunks.len(), 2);
+
+        fn content(
+            line: &Option<DiffLine>,
+        ) -> Option<(DiffLineType, &str)> {
+            line.as_ref().map(|l| (l.line_type, l.content.trim_end()))
+        }
+
+        for (hunk, expected) in split
+            .hunks
+            .iter()
+            .zip(&[("2", "2   newa"), ("0", "0   newb")])
+        {
+            let changed = hunk
+                .lines
+                .iter()
+                .filter(|l| content(&l.left) != content(&l.right))
+                .collect::<Vec<_>>();
+
+            // one removed line paired with its replacement
+            assert_eq!(changed.len(), 1);
+            assert_eq!(
+                content(&changed[0].left),
+                Some((DiffLineType::Delete, expected.0))
+            );
+            assert_eq!(
+                content(&changed[0].right),
+                Some((DiffLineType::Add, expected.1))
+            );
+        }
+
+        // the pair collapses into one row
+        assert_eq!(
+            split.hunks[0].lines.len(),
+            unified.hunks[0].lines.len() - 1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_newfile_in_sub_dir_current_dir() {
+        let file_path = Path::new("foo/foo.txt");
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+
+        let sub_path = root.join("foo/");
+
+        fs::create_dir_all(&sub_path).unwrap();
+        File::create(&root.join(file_path))
+            .unwrap()
+            .write_all(b"test")
+            .unwrap();
+
+        let diff = get_diff(
+            sub_path.to_str().unwrap(),
+            String::from(file_path.to_str().unwrap()),
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(diff.hunks[0].lines[1].content, "test");
+    }
+
+    #[test]
+    fn test_diff_delta_size() -> Result<()> {
+        let file_path = Path::new("bar");
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"\x00")?;
+
+        stage_add_file(repo_path, file_path).unwrap();
+
+        commit(repo_path, "commit").unwrap();
+
+        File::create(&root.join(file_path))?
+            .write_all(b"\x00\x02")?;
 
         let diff = get_diff(
             repo_path,
@@ -549,4 +1745,869 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_binary_fallback() -> Result<()> {
+        let file_path = Path::new("bar");
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?
+            .write_all(b"\x00\xc3\x28")?;
+
+        stage_add_file(repo_path, file_path).unwrap();
+
+        let diff = get_diff(
+            repo_path,
+            String::from(file_path.to_str().unwrap()),
+            true,
+        )?;
+
+        assert_eq!(diff.is_binary, true);
+        assert_eq!(diff.hunks.len(), 0);
+
+        let diff = get_diff_with_options(
+            repo_path,
+            String::from(file_path.to_str().unwrap()),
+            true,
+            DiffOptions {
+                binary_fallback: true,
+                ..DiffOptions::default()
+            },
+        )?;
+
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(
+            diff.hunks[0].lines[0].content,
+            "Binary files differ, 0 bytes \u{2192} 3 bytes"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lfs_pointer() -> Result<()> {
+        let file_path = Path::new("big.bin");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let pointer = "version https://git-lfs.github.com/spec/v1
+oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393
+size 12345
+";
+
+        File::create(&root.join(file_path))?
+            .write_all(pointer.as_bytes())?;
+
+        let diff = get_diff(
+            repo_path,
+            String::from(file_path.to_str().unwrap()),
+            false,
+        )?;
+
+        let lfs = diff.lfs.unwrap();
+        assert_eq!(lfs.size, 12345);
+        assert_eq!(
+            lfs.oid,
+            "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393"
+        );
+
+        stage_add_file(repo_path, file_path)?;
+
+        let diff = get_diff(
+            repo_path,
+            String::from(file_path.to_str().unwrap()),
+            true,
+        )?;
+        assert_eq!(diff.lfs.map(|lfs| lfs.size), Some(12345));
+
+        assert_eq!(parse_lfs_pointer(b"size 12345\n"), None);
+        assert_eq!(
+            parse_lfs_pointer(
+                b"version https://git-lfs.github.com/spec/v1\nsize 1\n"
+            ),
+            None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_textconv() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(".gitattributes"))?
+            .write_all(b"*.txt diff=upper\n")?;
+        File::create(&root.join(file_path))?.write_all(b"foo\n")?;
+        stage_add_file(repo_path, Path::new(".gitattributes"))?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?.write_all(b"bar\n")?;
+
+        let options = DiffOptions {
+            textconv: true,
+            ..DiffOptions::default()
+        };
+
+        let get = || {
+            get_diff_with_options(
+                repo_path,
+                String::from(file_path.to_str().unwrap()),
+                false,
+                options,
+            )
+        };
+
+        // no textconv configured for the driver
+        assert_eq!(get()?.hunks[0].lines[2].content, "bar\n");
+
+        repo.config()?
+            .set_str("diff.upper.textconv", "tr a-z A-Z <")?;
+
+        let diff = get()?;
+        assert_eq!(diff.hunks[0].lines[1].content, "FOO\n");
+        assert_eq!(diff.hunks[0].lines[2].content, "BAR\n");
+
+        // not applied unless asked for
+        let diff = get_diff(
+            repo_path,
+            String::from(file_path.to_str().unwrap()),
+            false,
+        )?;
+        assert_eq!(diff.hunks[0].lines[2].content, "bar\n");
+
+        // a failing textconv falls back to the raw diff
+        repo.config()?.set_str("diff.upper.textconv", "false")?;
+        assert_eq!(get()?.hunks[0].lines[2].content, "bar\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_cancel() -> Result<()> {
+        let file_path = Path::new("bar.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?
+            .write_all(HUNK_A.as_bytes())?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?
+            .write_all(HUNK_B.as_bytes())?;
+
+        let path = String::from(file_path.to_str().unwrap());
+        let cancel = AtomicBool::new(false);
+
+        let full = get_diff_cancellable(
+            repo_path,
+            path.clone(),
+            false,
+            DiffOptions::default(),
+            &cancel,
+        )?;
+        assert_eq!(full.hunks.len(), 2);
+
+        // cancel partway through the first hunk
+        let mut lines_seen = 0;
+        let res = get_diff_with_progress(
+            repo_path,
+            path.clone(),
+            false,
+            DiffOptions::default(),
+            |lines| {
+                lines_seen = lines;
+                if lines == 3 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+                !cancel.load(Ordering::Relaxed)
+            },
+        );
+
+        assert!(matches!(res, Err(Error::Cancelled)));
+        assert_eq!(lines_seen, 3);
+        assert_eq!(lines_seen < full.lines, true);
+
+        assert!(matches!(
+            get_diff_cancellable(
+                repo_path,
+                path,
+                false,
+                DiffOptions::default(),
+                &cancel,
+            ),
+            Err(Error::Cancelled)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_hunk() -> Result<()> {
+        let file_path = Path::new("bar.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?
+            .write_all(HUNK_A.as_bytes())?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?
+            .write_all(HUNK_B.as_bytes())?;
+
+        let p = String::from(file_path.to_str().unwrap());
+        let diff = get_diff(repo_path, p.clone(), false)?;
+        assert_eq!(diff.hunks.len(), 2);
+
+        for expected in &diff.hunks {
+            let hunk = get_hunk(
+                repo_path,
+                p.clone(),
+                false,
+                expected.header_hash,
+            )?
+            .unwrap();
+
+            assert_eq!(hunk.header, expected.header);
+            assert_eq!(hunk.lines.len(), expected.lines.len());
+            assert_eq!(
+                hunk.lines[hunk.lines.len() - 1].content,
+                expected.lines[expected.lines.len() - 1].content
+            );
+        }
+
+        assert_eq!(get_hunk(repo_path, p, false, 0)?.is_none(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_whitespace_flags() -> Result<()> {
+        let file_path = Path::new("bar.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?
+            .write_all(b"trailing \nclean\n")?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?
+            .write_all(b"trailing\nclean\nspaces \t\ncrlf\r\n")?;
+
+        let p = String::from(file_path.to_str().unwrap());
+        let diff = get_diff(repo_path, p.clone(), false)?;
+        let flags = |diff: &FileDiff| {
+            diff.hunks[0]
+                .lines
+                .iter()
+                .map(|l| (l.trailing_whitespace, l.crlf))
+                .collect::<Vec<_>>()
+        };
+
+        // header, -trailing, +trailing, clean, +spaces, +crlf
+        assert_eq!(
+            flags(&diff),
+            vec![
+                (false, false),
+                (false, false),
+                (false, false),
+                (false, false),
+                (true, false),
+                (false, true),
+            ]
+        );
+
+        let diff = get_diff_with_options(
+            repo_path,
+            p,
+            false,
+            DiffOptions {
+                whitespace_all_lines: true,
+                ..DiffOptions::default()
+            },
+        )?;
+        assert_eq!(diff.hunks[0].lines[1].trailing_whitespace, true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_tabs() -> Result<()> {
+        let file_path = Path::new("bar.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?
+            .write_all(b"\tfoo\n  \tbar\ta\n")?;
+
+        let diff = get_diff(
+            repo_path,
+            String::from(file_path.to_str().unwrap()),
+            false,
+        )?;
+
+        let contents = |diff: &FileDiff| {
+            diff.hunks[0]
+                .lines
+                .iter()
+                .skip(1)
+                .map(|l| l.content.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            contents(&expand_tabs(&diff, 4, TabExpansion::Leading)),
+            vec!["    foo\n", "    bar\ta\n"]
+        );
+        assert_eq!(
+            contents(&expand_tabs(&diff, 4, TabExpansion::All)),
+            vec!["    foo\n", "    bar a\n"]
+        );
+
+        // the original is untouched
+        assert_eq!(contents(&diff), vec!["\tfoo\n", "  \tbar\ta\n"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stash_diff() -> Result<()> {
+        let file_path = Path::new("bar.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"a\n")?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?.write_all(b"b\n")?;
+        File::create(&root.join("new.txt"))?.write_all(b"new\n")?;
+
+        stash_save(repo_path, None, true, false)?;
+
+        assert_eq!(get_statuses(repo_path), (0, 0));
+
+        let diff = get_stash_diff(
+            repo_path,
+            0,
+            Some(String::from(file_path.to_str().unwrap())),
+        )?;
+        let lines = diff.hunks[0]
+            .lines
+            .iter()
+            .map(|l| l.content.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(lines[1..], ["a\n", "b\n"]);
+
+        let diff = get_stash_diff(
+            repo_path,
+            0,
+            Some(String::from("new.txt")),
+        )?;
+        assert_eq!(diff.hunks[0].lines[1].content, "new\n");
+
+        // previewing does not touch the working tree
+        assert_eq!(get_statuses(repo_path), (0, 0));
+        assert_eq!(fs::read_to_string(root.join(file_path))?, "a\n");
+
+        assert_eq!(get_stash_diff(repo_path, 1, None).is_err(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_workdir_vs_commit() -> Result<()> {
+        let file_path = Path::new("bar.txt");
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"a\n")?;
+        stage_add_file(repo_path, file_path)?;
+        let first = commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?.write_all(b"a\nb\n")?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c2")?;
+
+        File::create(&root.join(file_path))?
+            .write_all(b"a\nb\nc\n")?;
+
+        let diff = get_diff_workdir_vs_commit(
+            repo_path,
+            String::from(file_path.to_str().unwrap()),
+            first,
+        )?;
+
+        let added = diff.hunks[0]
+            .lines
+            .iter()
+            .filter(|l| l.line_type == DiffLineType::Add)
+            .map(|l| l.content.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(added, vec!["b\n", "c\n"]);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_diff_new_symlink() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join("target.txt"))?
+            .write_all(b"content")?;
+        stage_add_file(repo_path, Path::new("target.txt"))?;
+        commit(repo_path, "c1")?;
+
+        std::os::unix::fs::symlink("target.txt", root.join("link"))?;
+
+        let diff = get_diff(repo_path, String::from("link"), false)?;
+
+        assert_eq!(diff.hunks[0].lines[1].content, "target.txt");
+
+        let diff = get_diff_with_options(
+            repo_path,
+            String::from("link"),
+            false,
+            DiffOptions {
+                follow_symlinks: true,
+                ..DiffOptions::default()
+            },
+        )?;
+
+        assert_eq!(diff.hunks[0].lines[1].content, "content");
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_broken_symlinks() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+
+        let missing = root.join("missing");
+        std::os::unix::fs::symlink("nothing.txt", &missing)?;
+
+        let looped = root.join("loop");
+        std::os::unix::fs::symlink("loop", &looped)?;
+
+        assert_eq!(new_file_content(&missing, true), None);
+        assert_eq!(new_file_content(&looped, true), None);
+        assert_eq!(
+            new_file_content(&missing, false),
+            Some(b"nothing.txt".to_vec())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_too_large() -> Result<()> {
+        let file_path = Path::new("bar.txt");
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let options = DiffOptions {
+            max_file_size: Some(10),
+            ..DiffOptions::default()
+        };
+
+        File::create(&root.join(file_path))?
+            .write_all(&[b'a'; 20])?;
+
+        let diff = get_diff_with_options(
+            repo_path,
+            String::from(file_path.to_str().unwrap()),
+            false,
+            options,
+        )?;
+
+        assert_eq!(diff.too_large, true);
+        assert_eq!(diff.untracked, true);
+        assert_eq!(diff.hunks.len(), 0);
+        assert_eq!(diff.sizes, (0, 20));
+
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+
+        let diff = get_diff_with_options(
+            repo_path,
+            String::from(file_path.to_str().unwrap()),
+            false,
+            options,
+        )?;
+
+        assert_eq!(diff.too_large, true);
+        assert_eq!(diff.hunks.len(), 0);
+        assert_eq!(diff.sizes, (20, 1));
+        assert_eq!(diff.size_delta, -19);
+
+        let diff = get_diff(
+            repo_path,
+            String::from(file_path.to_str().unwrap()),
+            false,
+        )?;
+
+        assert_eq!(diff.too_large, false);
+        assert_eq!(diff.hunks.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diffs_parallel() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let paths: Vec<String> =
+            (0..8).map(|i| format!("file{}.txt", i)).collect();
+
+        for (i, p) in paths.iter().enumerate() {
+            File::create(&root.join(p))?
+                .write_all("line\n".repeat(i + 1).as_bytes())?;
+        }
+
+        let diffs = get_diffs(repo_path, &paths, false)?;
+
+        assert_eq!(diffs.len(), paths.len());
+
+        for (i, (p, diff)) in
+            paths.iter().zip(diffs.iter()).enumerate()
+        {
+            let sequential = get_diff(repo_path, p.clone(), false)?;
+
+            assert_eq!(diff.lines, i + 2);
+            assert_eq!(hash(diff), hash(&sequential));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_whitespace_only_hunk() -> Result<()> {
+        let file_path = Path::new("bar.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?
+            .write_all(b"fn a() {\nfoo();\n}\n")?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        let p = String::from(file_path.to_str().unwrap());
+
+        File::create(&root.join(file_path))?
+            .write_all(b"fn a() {\n    foo();\n}\n")?;
+
+        let diff = get_diff(repo_path, p.clone(), false)?;
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(diff.hunks[0].whitespace_only, true);
+
+        let hunk = get_hunk(
+            repo_path,
+            p.clone(),
+            false,
+            diff.hunks[0].header_hash,
+        )?;
+        assert_eq!(hunk.map(|h| h.whitespace_only), Some(true));
+
+        File::create(&root.join(file_path))?
+            .write_all(b"fn a() {\n    bar();\n}\n")?;
+
+        let diff = get_diff(repo_path, p, false)?;
+        assert_eq!(diff.hunks[0].whitespace_only, false);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_file_in_linked_worktree() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let head = repo.head()?.peel_to_commit()?;
+        repo.branch("feature", &head, false)?;
+
+        let wt_dir = TempDir::new()?;
+        let wt_path = wt_dir.path().join("wt");
+        let wt_path = wt_path.to_str().unwrap();
+
+        add_worktree(repo_path, "wt", wt_path, "feature")?;
+
+        // same name in the main workdir must not be picked up
+        fs::write(root.join("new.txt"), "main\n")?;
+        fs::write(Path::new(wt_path).join("new.txt"), "linked\n")?;
+
+        let diff = get_diff(wt_path, "new.txt".to_string(), false)?;
+
+        assert_eq!(diff.untracked, true);
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(diff.hunks[0].lines[1].content, "linked\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_diff_in_bare_repo() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"a\nb\n")?;
+        stage_add_file(repo_path, file_path)?;
+        let id = commit(repo_path, "c1")?;
+
+        // `utils::repo` refuses bare repos, so go through git2
+        let bare_dir = TempDir::new()?;
+        let bare = RepoBuilder::new()
+            .bare(true)
+            .clone(repo_path, bare_dir.path())?;
+
+        let tree = bare.find_commit(id.into())?.tree()?;
+        let diff = bare.diff_tree_to_tree(None, Some(&tree), None)?;
+        let diff = raw_diff_to_file_diff(
+            &bare,
+            &diff,
+            DiffOptions::default(),
+        )?;
+
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(diff.lines, 3);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_untracked_diff_with_root_workdir() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+
+        // keep the file outside of the repo's git dir
+        let file_dir = TempDir::new()?;
+        let file = file_dir.path().canonicalize()?.join("foo.txt");
+        File::create(&file)?.write_all(b"test\nfoo")?;
+
+        repo.set_workdir(Path::new("/"), false)?;
+
+        let relative = file.strip_prefix("/").unwrap();
+        let mut opts = git2::DiffOptions::new();
+        opts.pathspec(relative)
+            .disable_pathspec_match(true)
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+
+        let diff =
+            repo.diff_index_to_workdir(None, Some(&mut opts))?;
+        let diff = raw_diff_to_file_diff(
+            &repo,
+            &diff,
+            DiffOptions::default(),
+        )?;
+
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(diff.hunks[0].lines[1].content, "test\n");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unified_string_applies() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let old = "fn a() {\n1\n2\n3\n4\n5\n6\n7\n8\n9\n}\nend";
+        let new = "fn a() {\n1\nx\n3\n4\n5\n6\n7\n8\ny\n}\nend\n";
+
+        fs::write(root.join("foo.txt"), old)?;
+        stage_add_file(repo_path, Path::new("foo.txt"))?;
+        commit(repo_path, "c1")?;
+
+        fs::write(root.join("foo.txt"), new)?;
+        fs::write(root.join("new.txt"), "a\nb")?;
+
+        let patch = [
+            get_diff(repo_path, "foo.txt".to_string(), false)?
+                .to_unified_string("foo.txt"),
+            get_diff(repo_path, "new.txt".to_string(), false)?
+                .to_unified_string("new.txt"),
+        ]
+        .concat();
+
+        assert_eq!(patch.contains("@@ -1,12 +1,12 @@"), true);
+        assert_eq!(
+            patch.contains("--- /dev/null\n+++ b/new.txt"),
+            true
+        );
+
+        // back to a fresh checkout
+        fs::remove_file(root.join("new.txt"))?;
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+        assert_eq!(fs::read_to_string(root.join("foo.txt"))?, old);
+
+        let diff = Diff::from_buffer(patch.as_bytes())?;
+        repo.apply(&diff, ApplyLocation::WorkDir, None)?;
+
+        assert_eq!(fs::read_to_string(root.join("foo.txt"))?, new);
+        assert_eq!(fs::read_to_string(root.join("new.txt"))?, "a\nb");
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_unified_string_modes() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::write(root.join("empty.txt"), "")?;
+        stage_add_file(repo_path, Path::new("empty.txt"))?;
+        commit(repo_path, "c1")?;
+
+        fs::remove_file(root.join("empty.txt"))?;
+        fs::write(root.join("run.sh"), "echo\n")?;
+        fs::set_permissions(
+            root.join("run.sh"),
+            fs::Permissions::from_mode(0o755),
+        )?;
+
+        let deleted =
+            get_diff(repo_path, "empty.txt".to_string(), false)?;
+        let added = get_diff(repo_path, "run.sh".to_string(), false)?;
+
+        assert_eq!(deleted.hunks.is_empty(), true);
+        assert_eq!(added.modes, (0, 0o100_755));
+
+        // exactly what `git diff` prints (libgit2 is not able to
+        // parse a patch without hunks, so it is not applied below)
+        assert_eq!(
+            deleted.to_unified_string("empty.txt"),
+            "diff --git a/empty.txt b/empty.txt\n\
+             deleted file mode 100644\n\
+             index e69de29..0000000\n"
+        );
+
+        let patch = added.to_unified_string("run.sh");
+        assert_eq!(
+            patch.starts_with(
+                "diff --git a/run.sh b/run.sh\n\
+                 new file mode 100755\n\
+                 index 0000000..fa11a6a\n"
+            ),
+            true
+        );
+
+        fs::remove_file(root.join("run.sh"))?;
+
+        let diff = Diff::from_buffer(patch.as_bytes())?;
+        repo.apply(&diff, ApplyLocation::WorkDir, None)?;
+
+        assert_eq!(
+            fs::metadata(root.join("run.sh"))?.permissions().mode()
+                & 0o111
+                != 0,
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_selected_lines_text() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::write(root.join(file_path), "a\nb\nc\n")?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        fs::write(root.join(file_path), "a\nx\nc\nd")?;
+
+        let diff = get_diff(repo_path, "foo.txt".to_string(), false)?;
+
+        // 0 is the hunk header, the last one the eof marker
+        let all = (0..diff.lines).collect::<Vec<_>>();
+
+        assert_eq!(
+            diff.selected_lines_text(&all, true),
+            " a\n-b\n+x\n c\n+d"
+        );
+        assert_eq!(
+            diff.selected_lines_text(&[3, 2], false),
+            "b\nx\n"
+        );
+        assert_eq!(diff.selected_lines_text(&[0, 99], true), "");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_attr_overrides_binary() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::write(
+            root.join(".gitattributes"),
+            "*.txt -diff\n*.bin diff\n",
+        )?;
+        fs::write(root.join("a.txt"), "a\n")?;
+        fs::write(root.join("a.bin"), b"\x00a\n")?;
+        stage_add_file(repo_path, Path::new(".gitattributes"))?;
+        stage_add_file(repo_path, Path::new("a.txt"))?;
+        stage_add_file(repo_path, Path::new("a.bin"))?;
+        commit(repo_path, "c1")?;
+
+        fs::write(root.join("a.txt"), "b\n")?;
+        fs::write(root.join("a.bin"), b"\x00b\n")?;
+        fs::write(root.join("new.txt"), "b\n")?;
+        fs::write(root.join("new.bin"), b"\x00b\n")?;
+
+        for file in &["a.txt", "new.txt"] {
+            let diff = get_diff(repo_path, file.to_string(), false)?;
+            assert_eq!(diff.is_binary, true);
+            assert_eq!(diff.hunks.len(), 0);
+        }
+
+        for file in &["a.bin", "new.bin"] {
+            let diff = get_diff(repo_path, file.to_string(), false)?;
+            assert_eq!(diff.is_binary, false);
+            assert_eq!(diff.hunks.len(), 1);
+        }
+
+        Ok(())
+    }
 }
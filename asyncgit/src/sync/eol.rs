@@ -0,0 +1,113 @@
+//! line ending normalization (gitattributes `text` and `eol`)
+
+use super::utils::{repo, work_dir};
+use crate::error::Result;
+use git2::{AttrCheckFlags, AttrValue, Repository};
+use scopetime::scope_time;
+use std::{fs, path::Path};
+
+/// content `path` would be committed with if git normalizes its line
+/// endings based on gitattributes (`text`, `text=auto`, `eol=...`),
+/// None if committing keeps the working copy as is
+pub fn eol_normalization(
+    repo_path: &str,
+    path: &str,
+) -> Result<Option<Vec<u8>>> {
+    scope_time!("eol_normalization");
+
+    let repo = repo(repo_path)?;
+
+    let content = fs::read(work_dir(&repo)?.join(path))?;
+
+    if !normalizes_eol(&repo, Path::new(path), &content)? {
+        return Ok(None);
+    }
+
+    let normalized = crlf_to_lf(&content);
+
+    if normalized == content {
+        Ok(None)
+    } else {
+        Ok(Some(normalized))
+    }
+}
+
+/// whether git converts `CRLF` to `LF` when adding `path`
+fn normalizes_eol(
+    repo: &Repository,
+    path: &Path,
+    content: &[u8],
+) -> Result<bool> {
+    let flags = AttrCheckFlags::default();
+
+    let text = repo.get_attr(path, "text", flags)?;
+    let eol = repo.get_attr(path, "eol", flags)?;
+
+    Ok(match AttrValue::from_string(text) {
+        AttrValue::True => true,
+        // text=auto only touches files git considers text
+        AttrValue::String("auto") => !content.contains(&0),
+        // `eol` implies `text`
+        AttrValue::Unspecified => matches!(
+            AttrValue::from_string(eol),
+            AttrValue::String("lf") | AttrValue::String("crlf")
+        ),
+        // explicitly unset or something unknown
+        _ => false,
+    })
+}
+
+fn crlf_to_lf(content: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(content.len());
+
+    for (i, c) in content.iter().enumerate() {
+        if *c == b'\r' && content.get(i + 1) == Some(&b'\n') {
+            continue;
+        }
+        res.push(*c);
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::eol_normalization;
+    use crate::error::Result;
+    use crate::sync::tests::repo_init;
+    use std::fs;
+
+    #[test]
+    fn test_eol_normalization() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::write(root.join("foo.txt"), "a\r\nb\r\n")?;
+        fs::write(root.join("foo.bin"), "a\r\nb\r\n")?;
+        fs::write(root.join("foo.md"), "a\r\n")?;
+
+        assert_eq!(eol_normalization(repo_path, "foo.txt")?, None);
+
+        fs::write(
+            root.join(".gitattributes"),
+            "*.txt eol=lf\n*.bin -text\n*.md text\n",
+        )?;
+
+        assert_eq!(
+            eol_normalization(repo_path, "foo.txt")?,
+            Some(b"a\nb\n".to_vec())
+        );
+        assert_eq!(eol_normalization(repo_path, "foo.bin")?, None);
+        assert_eq!(
+            eol_normalization(repo_path, "foo.md")?,
+            Some(b"a\n".to_vec())
+        );
+
+        // already normalized
+        fs::write(root.join("foo.txt"), "a\nb\n")?;
+        assert_eq!(eol_normalization(repo_path, "foo.txt")?, None);
+
+        Ok(())
+    }
+}
@@ -6,10 +6,11 @@ use crate::{
     error::{Error, Result},
     hash,
 };
-use git2::{ApplyLocation, ApplyOptions, Diff};
+use git2::{ApplyLocation, ApplyOptions, Diff, ErrorCode};
 use scopetime::scope_time;
 
-///
+/// fails with `Error::HunkOutdated` if the hunk is not part of the
+/// current diff anymore or does not apply cleanly to the index
 pub fn stage_hunk(
     repo_path: &str,
     file_path: String,
@@ -17,18 +18,52 @@ pub fn stage_hunk(
 ) -> Result<()> {
     scope_time!("stage_hunk");
 
+    stage_hunks(repo_path, &file_path, &[hunk_hash])
+}
+
+/// stages all hunks of `file_path` in `header_hashes` with a single
+/// apply of the current diff (hunks are always applied in file order,
+/// duplicates are ignored). nothing is staged and
+/// `Error::HunkOutdated` returned if any of them is outdated
+pub fn stage_hunks(
+    repo_path: &str,
+    file_path: &str,
+    header_hashes: &[u64],
+) -> Result<()> {
+    scope_time!("stage_hunks");
+
     let repo = repo(repo_path)?;
 
-    let diff = get_diff_raw(&repo, &file_path, false, false)?;
+    let diff = get_diff_raw(&repo, file_path, false, false)?;
 
-    let mut opt = ApplyOptions::new();
-    opt.hunk_callback(|hunk| {
-        let header =
-            HunkHeader::from(hunk.expect("hunk unavailable"));
-        hash(&header) == hunk_hash
-    });
+    if header_hashes
+        .iter()
+        .any(|hunk_hash| find_hunk_index(&diff, *hunk_hash).is_none())
+    {
+        return Err(Error::HunkOutdated);
+    }
 
-    repo.apply(&diff, ApplyLocation::Index, Some(&mut opt))?;
+    let apply = |check: bool| {
+        let mut opt = ApplyOptions::new();
+        opt.check(check);
+        opt.hunk_callback(|hunk| {
+            hunk.map_or(false, |hunk| {
+                header_hashes.contains(&hash(&HunkHeader::from(hunk)))
+            })
+        });
+
+        repo.apply(&diff, ApplyLocation::Index, Some(&mut opt))
+    };
+
+    // dry run first so nothing is touched if it does not apply
+    apply(true).map_err(|e| {
+        if e.code() == ErrorCode::ApplyFail {
+            Error::HunkOutdated
+        } else {
+            e.into()
+        }
+    })?;
+    apply(false)?;
 
     Ok(())
 }
@@ -106,7 +141,7 @@ pub fn unstage_hunk(
 
     let hunk_index = find_hunk_index(&diff, hunk_hash);
     if hunk_index.is_none() {
-        return Err(Error::Generic("hunk not found".to_string()));
+        return Err(Error::HunkOutdated);
     }
 
     let diff = get_diff_raw(&repo, &file_path, true, true)?;
@@ -142,7 +177,12 @@ mod tests {
     use super::*;
     use crate::{
         error::Result,
-        sync::{diff::get_diff, tests::repo_init_empty},
+        sync::{
+            commit,
+            diff::get_diff,
+            stage_add_file,
+            tests::{get_statuses, repo_init_empty},
+        },
     };
     use std::{
         fs::{self, File},
@@ -177,4 +217,87 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_stage_outdated_hunk() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init_empty()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?
+            .write_all(b"1\n2\n3\n4\n5\n6\n7\n8\n9\n")?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?
+            .write_all(b"1\n2\n3\n4\n5\n6\n7\n8\nnine\n")?;
+
+        let diff = get_diff(repo_path, "foo.txt".to_string(), false)?;
+        let hunk = diff.hunks[0].header_hash;
+
+        // the working tree changes after the diff was computed
+        File::create(&root.join(file_path))?
+            .write_all(b"0\n1\n2\n3\n4\n5\n6\n7\n8\nnine\n")?;
+
+        let res = stage_hunk(repo_path, "foo.txt".to_string(), hunk);
+        assert_eq!(matches!(res, Err(Error::HunkOutdated)), true);
+
+        // nothing got staged
+        assert_eq!(get_statuses(repo_path), (1, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_hunks() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init_empty()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let lines = |changed: &[usize]| {
+            (0..30)
+                .map(|i| {
+                    if changed.contains(&i) {
+                        format!("{} changed\n", i)
+                    } else {
+                        format!("{}\n", i)
+                    }
+                })
+                .collect::<String>()
+        };
+
+        File::create(&root.join(file_path))?
+            .write_all(lines(&[]).as_bytes())?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?
+            .write_all(lines(&[1, 15, 28]).as_bytes())?;
+
+        let diff = get_diff(repo_path, "foo.txt".to_string(), false)?;
+        assert_eq!(diff.hunks.len(), 3);
+
+        // out of order and with a duplicate
+        let hashes = [
+            diff.hunks[2].header_hash,
+            diff.hunks[0].header_hash,
+            diff.hunks[2].header_hash,
+        ];
+
+        stage_hunks(repo_path, "foo.txt", &hashes)?;
+
+        let mut index = repo.index()?;
+        index.read(true)?;
+        let entry = index.get_path(file_path, 0).unwrap();
+        let staged = repo.find_blob(entry.id)?;
+
+        assert_eq!(staged.content(), lines(&[1, 28]).as_bytes());
+
+        let diff = get_diff(repo_path, "foo.txt".to_string(), false)?;
+        assert_eq!(diff.hunks.len(), 1);
+
+        Ok(())
+    }
 }
@@ -1,15 +1,16 @@
 use super::utils::{repo, work_dir};
-use crate::error::Result;
+use crate::error::{Error, Result};
 use scopetime::scope_time;
 use std::{
-    fs::{File, OpenOptions},
+    fs::{self, File, OpenOptions},
     io::{Read, Seek, SeekFrom, Write},
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
 };
 
 static GITIGNORE: &str = ".gitignore";
 
 /// add file or path to root ignore file
+/// (creates the file if missing, does nothing if the pattern already exists)
 pub fn add_to_ignore(
     repo_path: &str,
     path_to_ignore: &str,
@@ -18,7 +19,16 @@ pub fn add_to_ignore(
 
     let repo = repo(repo_path)?;
 
-    let ignore_file = work_dir(&repo).join(GITIGNORE);
+    let ignore_file = work_dir(&repo)?.join(GITIGNORE);
+
+    // ignore patterns always use forward slashes
+    let path_to_ignore = path_to_ignore.replace('\\', "/");
+
+    if ignore_file.exists()
+        && file_contains_line(&ignore_file, &path_to_ignore)?
+    {
+        return Ok(());
+    }
 
     let optional_newline = ignore_file.exists()
         && !file_ends_with_newline(&ignore_file)?;
@@ -38,6 +48,52 @@ pub fn add_to_ignore(
     Ok(())
 }
 
+/// checks if `path` is excluded by any of the ignore rules of the repo
+/// (`path` can be relative to the workdir or absolute but inside of it)
+pub fn is_path_ignored(repo_path: &str, path: &str) -> Result<bool> {
+    scope_time!("is_path_ignored");
+
+    let repo = repo(repo_path)?;
+
+    let path =
+        relative_to_work_dir(work_dir(&repo)?, Path::new(path))?;
+
+    Ok(repo.is_path_ignored(path)?)
+}
+
+fn relative_to_work_dir(
+    work_dir: &Path,
+    path: &Path,
+) -> Result<PathBuf> {
+    let path = if path.is_absolute() {
+        path.strip_prefix(work_dir)
+            .map_err(|_| {
+                Error::Generic(format!(
+                    "path outside of repo: {}",
+                    path.display()
+                ))
+            })?
+            .to_path_buf()
+    } else {
+        path.to_path_buf()
+    };
+
+    if path.components().any(|c| c == Component::ParentDir) {
+        return Err(Error::Generic(format!(
+            "path outside of repo: {}",
+            path.display()
+        )));
+    }
+
+    Ok(path)
+}
+
+fn file_contains_line(file: &Path, line: &str) -> Result<bool> {
+    let content = fs::read_to_string(file)?;
+
+    Ok(content.lines().any(|l| l.trim_end() == line))
+}
+
 fn file_ends_with_newline(file: &PathBuf) -> Result<bool> {
     let mut file = File::open(file)?;
     let size = file.metadata()?.len();
@@ -46,15 +102,16 @@ fn file_ends_with_newline(file: &PathBuf) -> Result<bool> {
     let mut last_char = String::with_capacity(1);
     file.read_to_string(&mut last_char)?;
 
-    dbg!(&last_char);
-
     Ok(last_char == "\n")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::sync::tests::repo_init;
+    use crate::sync::{
+        status::{get_status, StatusType},
+        tests::repo_init,
+    };
     use io::BufRead;
     use std::{fs::File, io, path::Path};
 
@@ -126,4 +183,125 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_append_no_duplicates() -> Result<()> {
+        let ignore_file_path = Path::new(".gitignore");
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"test")?;
+
+        add_to_ignore(repo_path, file_path.to_str().unwrap())?;
+        add_to_ignore(repo_path, file_path.to_str().unwrap())?;
+
+        let lines = read_lines(&root.join(ignore_file_path))?
+            .collect::<io::Result<Vec<_>>>()?;
+        assert_eq!(lines, vec!["foo.txt"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_forward_slashes() -> Result<()> {
+        let ignore_file_path = Path::new(".gitignore");
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        add_to_ignore(repo_path, "foo\\bar.txt")?;
+
+        let mut lines = read_lines(&root.join(ignore_file_path))?;
+        assert_eq!(&lines.next().unwrap()?, "foo/bar.txt");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignored_not_untracked() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"test")?;
+
+        let untracked = |path: &str| {
+            get_status(repo_path, StatusType::WorkingDir, true)
+                .unwrap()
+                .iter()
+                .any(|item| item.path == path)
+        };
+
+        assert_eq!(untracked("foo.txt"), true);
+
+        add_to_ignore(repo_path, file_path.to_str().unwrap())?;
+
+        assert_eq!(untracked("foo.txt"), false);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_path_ignored() -> Result<()> {
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(".gitignore"))?
+            .write_all(b"*.log\n")?;
+        File::create(&root.join("foo.log"))?.write_all(b"test")?;
+        File::create(&root.join("foo.txt"))?.write_all(b"test")?;
+
+        assert_eq!(is_path_ignored(repo_path, "foo.log")?, true);
+        assert_eq!(is_path_ignored(repo_path, "foo.txt")?, false);
+
+        let abs_path = root.join("foo.log");
+        assert_eq!(
+            is_path_ignored(repo_path, abs_path.to_str().unwrap())?,
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_path_ignored_outside_repo() -> Result<()> {
+        let (_td, repo) = repo_init()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let outside = root.parent().unwrap().join("foo.log");
+
+        assert!(is_path_ignored(repo_path, "../foo.log").is_err());
+        assert!(is_path_ignored(
+            repo_path,
+            outside.to_str().unwrap()
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_relative_to_work_dir_at_root() -> Result<()> {
+        let work_dir = Path::new("/");
+
+        assert_eq!(
+            relative_to_work_dir(
+                work_dir,
+                Path::new("/foo/bar.log")
+            )?,
+            Path::new("foo/bar.log")
+        );
+        assert_eq!(
+            relative_to_work_dir(work_dir, Path::new("bar.log"))?,
+            Path::new("bar.log")
+        );
+
+        Ok(())
+    }
 }
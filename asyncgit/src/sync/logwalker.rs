@@ -67,7 +67,7 @@ mod tests {
         File::create(&root.join(file_path))?.write_all(b"a")?;
         stage_add_file(repo_path, file_path).unwrap();
         commit(repo_path, "commit1").unwrap();
-        File::create(&root.join(file_path))?.write_all(b"a")?;
+        File::create(&root.join(file_path))?.write_all(b"b")?;
         stage_add_file(repo_path, file_path).unwrap();
         let oid2 = commit(repo_path, "commit2").unwrap();
 
@@ -91,7 +91,7 @@ mod tests {
         File::create(&root.join(file_path))?.write_all(b"a")?;
         stage_add_file(repo_path, file_path).unwrap();
         commit(repo_path, "commit1").unwrap();
-        File::create(&root.join(file_path))?.write_all(b"a")?;
+        File::create(&root.join(file_path))?.write_all(b"b")?;
         stage_add_file(repo_path, file_path).unwrap();
         let oid2 = commit(repo_path, "commit2").unwrap();
 
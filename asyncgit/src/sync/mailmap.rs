@@ -0,0 +1,109 @@
+use git2::{Mailmap, Repository, Signature};
+
+/// the mailmap of the repo as git reads it: the `.mailmap` of the
+/// workdir (of `HEAD` in a bare repo), `mailmap.file` and
+/// `mailmap.blob` (see `git help mailmap`).
+/// None if it can not be read, a missing one is just empty
+pub(crate) fn read_mailmap(repo: &Repository) -> Option<Mailmap> {
+    repo.mailmap().ok()
+}
+
+/// `sig` with its canonical name and email according to `mailmap`
+pub(crate) fn resolve_signature(
+    mailmap: Option<&Mailmap>,
+    sig: &Signature,
+) -> Signature<'static> {
+    mailmap
+        .and_then(|mailmap| mailmap.resolve_signature(sig).ok())
+        .unwrap_or_else(|| sig.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::Result;
+    use crate::sync::{
+        get_commit_details, get_commits_info, tests::repo_init_empty,
+    };
+    use git2::Signature;
+    use std::{fs::File, io::Write};
+
+    #[test]
+    fn test_canonicalize_authors() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+
+        let sig1 = Signature::now("Jo", "jo@work")?;
+        let c1 = repo.commit(
+            Some("HEAD"),
+            &sig1,
+            &sig1,
+            "c1",
+            &tree,
+            &[],
+        )?;
+
+        let sig2 = Signature::now("jo", "jo@home")?;
+        let parent = repo.find_commit(c1)?;
+        let c2 = repo.commit(
+            Some("HEAD"),
+            &sig2,
+            &sig2,
+            "c2",
+            &tree,
+            &[&parent],
+        )?;
+
+        // no mailmap at all
+        let details = get_commit_details(repo_path, c1.into())?;
+        assert_eq!(details.author.name, "Jo");
+
+        File::create(&root.join(".mailmap"))?.write_all(
+            b"Jo Doe <jo@doe> <jo@work>\nJo Doe <jo@doe> <jo@home>\n",
+        )?;
+
+        let details1 = get_commit_details(repo_path, c1.into())?;
+        let details2 = get_commit_details(repo_path, c2.into())?;
+
+        assert_eq!(details1.author.name, "Jo Doe");
+        assert_eq!(details1.author.email, "jo@doe");
+        assert_eq!(details2.author.name, "Jo Doe");
+        assert_eq!(details2.author.email, "jo@doe");
+        assert_eq!(details2.committer.is_none(), true);
+
+        let infos =
+            get_commits_info(repo_path, &[c2.into(), c1.into()], 50)?;
+
+        assert_eq!(infos[0].author, "Jo Doe");
+        assert_eq!(infos[1].author, "Jo Doe");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mailmap_file_config() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+        let sig = Signature::now("Jo", "jo@work")?;
+        let c1 =
+            repo.commit(Some("HEAD"), &sig, &sig, "c1", &tree, &[])?;
+
+        let mailmap = repo.path().join("mailmap");
+        File::create(&mailmap)?
+            .write_all(b"Jo Doe <jo@doe> <jo@work>\n")?;
+        repo.config()?
+            .set_str("mailmap.file", mailmap.to_str().unwrap())?;
+
+        let details = get_commit_details(repo_path, c1.into())?;
+
+        assert_eq!(details.author.name, "Jo Doe");
+        assert_eq!(details.author.email, "jo@doe");
+
+        Ok(())
+    }
+}
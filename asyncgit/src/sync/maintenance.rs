@@ -0,0 +1,259 @@
+//! repository maintenance
+
+use super::utils::{repo, work_dir};
+use crate::error::{Error, Result};
+use scopetime::scope_time;
+use std::{fs, io::Read, path::Path, process::Command};
+
+const PACK_IDX_MAGIC: [u8; 4] = [0xff, b't', b'O', b'c'];
+const PACK_IDX_FANOUT_LEN: usize = 256 * 4;
+
+/// object statistics like `git count-objects -v`
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct RepoSizeStats {
+    /// number of loose objects
+    pub loose_objects: usize,
+    /// on-disk size of loose objects in bytes
+    pub loose_size: u64,
+    /// number of objects in packs
+    pub packed_objects: usize,
+    /// number of packs
+    pub packs: usize,
+    /// on-disk size of packs (including their index) in bytes
+    pub pack_size: u64,
+}
+
+/// counts the objects in `.git/objects`
+pub fn repo_stats(repo_path: &str) -> Result<RepoSizeStats> {
+    scope_time!("repo_stats");
+
+    let repo = repo(repo_path)?;
+
+    let objects = repo.path().join("objects");
+
+    let mut stats = RepoSizeStats::default();
+
+    for entry in fs::read_dir(&objects)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        // loose objects live in `xx/` fan-out dirs
+        let is_fanout = name.len() == 2
+            && name.chars().all(|c| c.is_ascii_hexdigit());
+
+        if !is_fanout || !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        for object in fs::read_dir(entry.path())? {
+            let object = object?;
+            if object.file_type()?.is_file() {
+                stats.loose_objects += 1;
+                stats.loose_size += object.metadata()?.len();
+            }
+        }
+    }
+
+    let pack_dir = objects.join("pack");
+    if pack_dir.is_dir() {
+        for entry in fs::read_dir(pack_dir)? {
+            let path = entry?.path();
+
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("pack") => {
+                    stats.pack_size += fs::metadata(&path)?.len();
+                }
+                Some("idx") => {
+                    stats.pack_size += fs::metadata(&path)?.len();
+                    stats.packs += 1;
+                    stats.packed_objects +=
+                        pack_idx_object_count(&path)?;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+/// the steps of `git gc` (without its auto detection), each run on
+/// its own because git reports no progress unless on a terminal
+fn gc_steps(aggressive: bool) -> Vec<Vec<&'static str>> {
+    let mut repack = vec![
+        "repack",
+        "-d",
+        "-l",
+        "-A",
+        "--unpack-unreachable=2.weeks.ago",
+    ];
+    if aggressive {
+        repack.extend(&["-f", "--depth=50", "--window=250"]);
+    }
+
+    vec![
+        vec!["pack-refs", "--all", "--prune"],
+        vec!["reflog", "expire", "--all"],
+        repack,
+        vec!["prune", "--expire", "2.weeks.ago"],
+        vec!["worktree", "prune", "--expire", "3.months.ago"],
+        vec!["rerere", "gc"],
+    ]
+}
+
+/// runs the steps of `git gc` (git2 is not able to repack),
+/// `progress` is called before each step with a line like
+/// `2/6: reflog expire`. fails with git's output if a step fails
+pub fn run_gc<F>(
+    repo_path: &str,
+    aggressive: bool,
+    mut progress: F,
+) -> Result<()>
+where
+    F: FnMut(&str),
+{
+    scope_time!("run_gc");
+
+    let repo = repo(repo_path)?;
+
+    let dir = if repo.is_bare() {
+        repo.path()
+    } else {
+        work_dir(&repo)?
+    };
+
+    let steps = gc_steps(aggressive);
+
+    for (i, args) in steps.iter().enumerate() {
+        // the command (and subcommand) without its options
+        let name = args
+            .iter()
+            .take(2)
+            .filter(|arg| !arg.starts_with('-'))
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        progress(&format!("{}/{}: {}", i + 1, steps.len(), name));
+
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()?;
+
+        if !output.status.success() {
+            return Err(Error::Generic(format!(
+                "git {} failed: {}{}",
+                args[0],
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// reads the object count from the fan-out table of a pack index
+fn pack_idx_object_count(path: &Path) -> Result<usize> {
+    let mut header = [0_u8; 8 + PACK_IDX_FANOUT_LEN];
+    fs::File::open(path)?.read_exact(&mut header)?;
+
+    // version 1 has no header and starts with the fan-out table
+    let fanout = if header[..4] == PACK_IDX_MAGIC {
+        &header[8..]
+    } else {
+        &header[..PACK_IDX_FANOUT_LEN]
+    };
+
+    // the last fan-out entry is the total number of objects
+    let last = &fanout[PACK_IDX_FANOUT_LEN - 4..];
+
+    Ok(u32::from_be_bytes([last[0], last[1], last[2], last[3]])
+        as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{repo_stats, run_gc};
+    use crate::error::Result;
+    use crate::sync::{commit, stage_add_file, tests::repo_init};
+    use git2::Buf;
+    use std::{fs::File, io::Write, path::Path, process::Command};
+
+    #[test]
+    fn test_repo_stats() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"foo")?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        let stats = repo_stats(repo_path)?;
+        assert_eq!(stats.loose_objects > 0, true);
+        assert_eq!(stats.loose_size > 0, true);
+        assert_eq!(stats.packs, 0);
+        assert_eq!(stats.packed_objects, 0);
+
+        // pack everything reachable from HEAD
+        let mut walk = repo.revwalk()?;
+        walk.push_head()?;
+        let mut builder = repo.packbuilder()?;
+        builder.insert_walk(&mut walk)?;
+        let mut buf = Buf::new();
+        builder.write_buf(&mut buf)?;
+
+        let odb = repo.odb()?;
+        let mut writer = odb.packwriter()?;
+        writer.write_all(&buf)?;
+        writer.commit()?;
+
+        let stats = repo_stats(repo_path)?;
+        assert_eq!(stats.packs, 1);
+        assert_eq!(stats.packed_objects, builder.object_count());
+        assert_eq!(stats.pack_size > 0, true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_gc() -> Result<()> {
+        // needs the git binary
+        if Command::new("git").arg("--version").output().is_err() {
+            return Ok(());
+        }
+
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        for i in 0..3 {
+            let file_path = format!("foo{}.txt", i);
+            File::create(&root.join(&file_path))?
+                .write_all(file_path.as_bytes())?;
+            stage_add_file(repo_path, Path::new(&file_path))?;
+            commit(repo_path, &file_path)?;
+        }
+
+        let before = repo_stats(repo_path)?;
+
+        let mut progress = Vec::new();
+        run_gc(repo_path, false, |line| {
+            progress.push(line.to_string())
+        })?;
+
+        let after = repo_stats(repo_path)?;
+        assert_eq!(after.loose_objects < before.loose_objects, true);
+        assert_eq!(after.packs > 0, true);
+
+        assert_eq!(progress.len(), 6);
+        assert_eq!(progress[1], "2/6: reflog expire");
+        assert_eq!(progress[2], "3/6: repack");
+
+        Ok(())
+    }
+}
@@ -1,42 +1,126 @@
 //! sync git api
 
+mod bisect;
 mod branch;
+mod checkout;
+mod clean;
 mod commit;
 mod commit_details;
 mod commit_files;
 mod commits_info;
+mod config;
+mod conflicts;
 pub mod diff;
+mod eol;
 mod hooks;
 mod hunks;
 mod ignore;
 mod logwalker;
+mod mailmap;
+mod maintenance;
+mod notes;
+mod patch;
+mod rebase;
+mod refs;
+mod remotes;
 mod reset;
+mod search;
+mod sparse;
+mod spellcheck;
 mod stash;
 pub mod status;
 mod tags;
+mod tree;
 pub mod utils;
+mod web;
+mod worktree;
 
 pub(crate) use branch::get_branch_name;
+pub use branch::{
+    branches_containing, default_branch_name, detached_head_info,
+    get_remote_branches, get_unpushed_commits, get_upstream_url,
+    merged_branches, DetachedInfo,
+};
+
+pub use bisect::{
+    bisect_mark, bisect_reset, bisect_start, is_bisect_in_progress,
+    BisectState, BisectTerm,
+};
+pub use checkout::{
+    checkout_branch, checkout_commit, checkout_remote_branch,
+};
+pub use clean::clean_workdir;
 
-pub use commit::{amend, commit, tag};
-pub use commit_details::{get_commit_details, CommitDetails};
-pub use commit_files::get_commit_files;
-pub use commits_info::{get_commits_info, CommitId, CommitInfo};
-pub use diff::get_diff_commit;
+pub use commit::{
+    amend, amend_author, cleanup_merge_state, commit,
+    commit_with_options, get_merge_message, merge_heads, tag,
+    CommitOptions,
+};
+pub use commit_details::{
+    get_commit_details, split_commit_message, CommitDetails,
+    CommitSignature,
+};
+pub use commit_files::{
+    diff_files_vs_branch, get_commit_files,
+    get_commit_files_cancellable, get_commit_files_with_mode,
+    get_commit_files_with_progress, MergeDiffMode,
+};
+pub use commits_info::{
+    format_commit_ref, get_commit_parents, get_commit_subjects,
+    get_commits_info, short_id, CommitId, CommitInfo,
+    INVALID_COMMIT_SUBJECT,
+};
+pub use config::{get_config_string, set_config_string, ConfigScope};
+pub use conflicts::{
+    conflict_marker_count, mark_resolved, unresolved_conflicts,
+};
+pub use diff::{
+    expand_tabs, get_diff_cancellable, get_diff_commit,
+    get_diff_with_options, get_diff_with_progress,
+    get_diff_workdir_vs_commit, get_hunk, get_stash_diff,
+    parse_lfs_pointer, DiffOptions, LfsPointer, TabExpansion,
+};
+pub use eol::eol_normalization;
 pub use hooks::{hooks_commit_msg, hooks_post_commit, HookResult};
-pub use hunks::{reset_hunk, stage_hunk, unstage_hunk};
-pub use ignore::add_to_ignore;
+pub use hunks::{reset_hunk, stage_hunk, stage_hunks, unstage_hunk};
+pub use ignore::{add_to_ignore, is_path_ignored};
 pub use logwalker::LogWalker;
-pub use reset::{reset_stage, reset_workdir};
-pub use stash::{get_stashes, stash_apply, stash_drop, stash_save};
-pub use tags::{get_tags, CommitTags, Tags};
+pub use maintenance::{repo_stats, run_gc, RepoSizeStats};
+pub use notes::{get_commit_note, set_commit_note};
+pub use patch::{apply_patch, format_patch, RejectedHunk};
+pub use rebase::rebase_progress;
+pub use refs::{get_all_refs, RefInfo, RefKind};
+pub use remotes::prune_remote;
+pub use reset::{reset_stage, reset_workdir, unstage_items};
+pub use search::{
+    find_in_diff, search_commits, search_content, PickaxeMode,
+    SearchFields,
+};
+pub use spellcheck::spell_check;
+pub use stash::{
+    get_stashes, stash_apply, stash_apply_as_patch, stash_branch,
+    stash_clear, stash_count, stash_drop, stash_drop_index,
+    stash_save,
+};
+pub use tags::{
+    describe, get_tags, tag_target_commit, CommitTags, Tags,
+};
+pub use tree::{
+    get_file_at_commit, list_tree, TreeEntry, TreeEntryKind,
+};
 pub use utils::{
-    get_head, is_bare_repo, is_repo, stage_add_all, stage_add_file,
-    stage_addremoved,
+    get_head, index_oid, intent_to_add, is_ancestor, is_bare_repo,
+    is_repo, resolve_revspec, set_index_flag, stage_add_all,
+    stage_add_file, stage_addremoved, stage_directory, stage_items,
+    IndexFlag, RepoCache,
+};
+pub use web::commit_web_url;
+pub use worktree::{
+    add_worktree, get_worktrees, prune_worktree, WorktreeInfo,
 };
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::status::{get_status, StatusType};
     use crate::error::Result;
     use git2::Repository;
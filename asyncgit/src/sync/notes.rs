@@ -0,0 +1,104 @@
+use super::{utils::repo, CommitId};
+use crate::error::Result;
+use git2::ErrorCode;
+use scopetime::scope_time;
+
+/// note attached to `commit` in `notes_ref`
+/// (`refs/notes/commits` if None), None if there is no note
+pub fn get_commit_note(
+    repo_path: &str,
+    commit: CommitId,
+    notes_ref: Option<&str>,
+) -> Result<Option<String>> {
+    scope_time!("get_commit_note");
+
+    let repo = repo(repo_path)?;
+
+    let note = match repo.find_note(notes_ref, commit.into()) {
+        Ok(note) => note,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(note.message().map(String::from))
+}
+
+/// attaches `note` to `commit` in `notes_ref`
+/// (`refs/notes/commits` if None), overwriting an existing one
+pub fn set_commit_note(
+    repo_path: &str,
+    commit: CommitId,
+    note: &str,
+    notes_ref: Option<&str>,
+) -> Result<()> {
+    scope_time!("set_commit_note");
+
+    let repo = repo(repo_path)?;
+
+    let signature = repo.signature()?;
+
+    repo.note(
+        &signature,
+        &signature,
+        notes_ref,
+        commit.into(),
+        note,
+        true,
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_commit_note, set_commit_note};
+    use crate::error::Result;
+    use crate::sync::{
+        commit, get_head, stage_add_file, tests::repo_init,
+    };
+    use std::{fs::File, io::Write, path::Path};
+
+    #[test]
+    fn test_notes() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let c1 = get_head(repo_path)?;
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path)?;
+        let c2 = commit(repo_path, "c2")?;
+
+        assert_eq!(get_commit_note(repo_path, c1, None)?, None);
+
+        set_commit_note(repo_path, c1, "reviewed", None)?;
+        assert_eq!(
+            get_commit_note(repo_path, c1, None)?.as_deref(),
+            Some("reviewed")
+        );
+        assert_eq!(
+            repo.find_reference("refs/notes/commits").is_ok(),
+            true
+        );
+
+        set_commit_note(repo_path, c1, "approved", None)?;
+        assert_eq!(
+            get_commit_note(repo_path, c1, None)?.as_deref(),
+            Some("approved")
+        );
+
+        assert_eq!(get_commit_note(repo_path, c2, None)?, None);
+
+        let review_ref = Some("refs/notes/review");
+        set_commit_note(repo_path, c2, "lgtm", review_ref)?;
+        assert_eq!(
+            get_commit_note(repo_path, c2, review_ref)?.as_deref(),
+            Some("lgtm")
+        );
+        assert_eq!(get_commit_note(repo_path, c2, None)?, None);
+
+        Ok(())
+    }
+}
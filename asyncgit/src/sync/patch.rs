@@ -0,0 +1,478 @@
+//! export and import of patches
+
+use super::{commit_files::get_commit_diff, utils::repo, CommitId};
+use crate::error::Result;
+use chrono::{FixedOffset, TimeZone};
+use git2::{
+    ApplyLocation, ApplyOptions, Commit, Diff, DiffFormat,
+    Repository, Sort,
+};
+use scopetime::scope_time;
+use std::{
+    cell::Cell,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// git limits the subject part of patch file names to this
+const MAX_FILENAME_SUBJECT: usize = 52;
+
+/// hunk of a patch that does not apply
+#[derive(Clone, Debug, PartialEq)]
+pub struct RejectedHunk {
+    /// file the hunk belongs to
+    pub path: String,
+    /// `@@ -1,3 +1,3 @@` line of the hunk
+    pub header: String,
+}
+
+/// applies the unified diff `patch` to the index (`to_index`) or
+/// the working tree. the patch is applied completely or not at all,
+/// if it does not apply the hunks that are rejected are returned
+pub fn apply_patch(
+    repo_path: &str,
+    patch: &[u8],
+    to_index: bool,
+) -> Result<Vec<RejectedHunk>> {
+    scope_time!("apply_patch");
+
+    let repo = repo(repo_path)?;
+
+    let diff = Diff::from_buffer(patch)?;
+
+    let location = if to_index {
+        ApplyLocation::Index
+    } else {
+        ApplyLocation::WorkDir
+    };
+
+    let mut check = ApplyOptions::new();
+    check.check(true);
+
+    if let Err(e) = repo.apply(&diff, location, Some(&mut check)) {
+        let rejected = rejected_hunks(&repo, &diff, location)?;
+
+        // every hunk applies on its own but not all of them together
+        if rejected.is_empty() {
+            return Err(e.into());
+        }
+
+        return Ok(rejected);
+    }
+
+    repo.apply(&diff, location, None)?;
+
+    Ok(Vec::new())
+}
+
+/// checks every hunk of `diff` on its own
+fn rejected_hunks(
+    repo: &Repository,
+    diff: &Diff,
+    location: ApplyLocation,
+) -> Result<Vec<RejectedHunk>> {
+    let mut hunks = Vec::new();
+    let delta_count = Cell::new(0);
+
+    diff.foreach(
+        &mut |_, _| {
+            delta_count.set(delta_count.get() + 1);
+            true
+        },
+        None,
+        Some(&mut |delta, hunk| {
+            let path = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let header = String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_string();
+            let delta_idx = delta_count.get() - 1;
+            let hunk_idx = hunks
+                .iter()
+                .filter(|(d, _, _)| *d == delta_idx)
+                .count();
+
+            hunks.push((
+                delta_idx,
+                hunk_idx,
+                RejectedHunk { path, header },
+            ));
+            true
+        }),
+        None,
+    )?;
+
+    let mut res = Vec::new();
+
+    for (delta, hunk, info) in hunks {
+        let mut current_delta = 0;
+        let mut current_hunk = 0;
+
+        let mut opt = ApplyOptions::new();
+        opt.check(true);
+        opt.delta_callback(|_| {
+            let res = current_delta == delta;
+            current_delta += 1;
+            res
+        });
+        opt.hunk_callback(|_| {
+            let res = current_hunk == hunk;
+            current_hunk += 1;
+            res
+        });
+
+        if repo.apply(diff, location, Some(&mut opt)).is_err() {
+            res.push(info);
+        }
+    }
+
+    Ok(res)
+}
+
+/// writes a `git format-patch` style `.patch` file per commit of
+/// `range` (`from..to` or a single commit) into `out_dir` and returns
+/// their paths (oldest commit first)
+pub fn format_patch(
+    repo_path: &str,
+    range: &str,
+    out_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    scope_time!("format_patch");
+
+    let repo = repo(repo_path)?;
+
+    let ids = if range.contains("..") {
+        let mut walk = repo.revwalk()?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+        walk.push_range(range)?;
+        walk.map(|id| Ok(id?.into())).collect::<Result<Vec<_>>>()?
+    } else {
+        let id = repo.revparse_single(range)?.peel_to_commit()?.id();
+        vec![CommitId::new(id)]
+    };
+
+    fs::create_dir_all(out_dir)?;
+
+    let mut res = Vec::with_capacity(ids.len());
+
+    for (idx, id) in ids.iter().enumerate() {
+        let commit = repo.find_commit((*id).into())?;
+        let subject =
+            commit.summary().unwrap_or_default().to_string();
+
+        let content =
+            patch_content(&repo, &commit, idx + 1, ids.len())?;
+
+        let path = out_dir.join(format!(
+            "{:04}-{}.patch",
+            idx + 1,
+            sanitize_subject(&subject)
+        ));
+
+        fs::write(&path, content)?;
+
+        res.push(path);
+    }
+
+    Ok(res)
+}
+
+fn patch_content(
+    repo: &Repository,
+    commit: &Commit,
+    patch_no: usize,
+    total: usize,
+) -> Result<String> {
+    let author = commit.author();
+    let time = author.when();
+    // a bogus offset in the commit is shown as utc
+    let offset = FixedOffset::east_opt(time.offset_minutes() * 60)
+        .unwrap_or_else(|| FixedOffset::east(0));
+    let date = offset.timestamp(time.seconds(), 0).to_rfc2822();
+
+    let message = commit.message().unwrap_or_default().trim();
+    let mut message = message.splitn(2, '\n');
+    let subject = message.next().unwrap_or_default();
+    let body = message.next().unwrap_or_default().trim();
+
+    let numbering = if total > 1 {
+        format!("[PATCH {}/{}]", patch_no, total)
+    } else {
+        String::from("[PATCH]")
+    };
+
+    let mut res = format!(
+        "From {} Mon Sep 17 00:00:00 2001\n\
+         From: {} <{}>\n\
+         Date: {}\n\
+         Subject: {} {}\n\n",
+        commit.id(),
+        author.name().unwrap_or_default(),
+        author.email().unwrap_or_default(),
+        date,
+        numbering,
+        subject,
+    );
+
+    if !body.is_empty() {
+        res.push_str(body);
+        res.push('\n');
+    }
+
+    res.push_str("---\n");
+
+    let diff = get_commit_diff(repo, commit.id().into(), None)?;
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if let '+' | '-' | ' ' = line.origin() {
+            res.push(line.origin());
+        }
+        res.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+
+    Ok(res)
+}
+
+/// like git: only `[a-zA-Z0-9._]` survive, everything else is
+/// collapsed into a single `-`
+fn sanitize_subject(subject: &str) -> String {
+    let mut res = String::with_capacity(subject.len());
+
+    for c in subject.chars() {
+        if c.is_ascii_alphanumeric() || c == '.' || c == '_' {
+            res.push(c);
+        } else if !res.is_empty() && !res.ends_with('-') {
+            res.push('-');
+        }
+    }
+
+    let res = res.trim_end_matches(&['-', '.'][..]);
+
+    let res = if res.len() > MAX_FILENAME_SUBJECT {
+        res[..MAX_FILENAME_SUBJECT].trim_end_matches('-')
+    } else {
+        res
+    };
+
+    if res.is_empty() {
+        String::from("patch")
+    } else {
+        res.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_patch, format_patch, sanitize_subject};
+    use crate::error::Result;
+    use crate::sync::{
+        commit, get_head, stage_add_file,
+        tests::{get_statuses, repo_init},
+    };
+    use git2::{Signature, Time};
+    use std::{
+        fs::{self, File},
+        io::Write,
+        path::Path,
+    };
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sanitize_subject() {
+        assert_eq!(
+            sanitize_subject("Fix: the parser!"),
+            "Fix-the-parser"
+        );
+        assert_eq!(
+            sanitize_subject("v1.2 -- release."),
+            "v1.2-release"
+        );
+        assert_eq!(sanitize_subject("???"), "patch");
+        assert_eq!(sanitize_subject(&"a".repeat(80)).len(), 52);
+    }
+
+    #[test]
+    fn test_format_patch_bogus_offset() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let base = get_head(repo_path)?;
+        let parent = repo.head()?.peel_to_commit()?;
+
+        // offset of more than a day
+        let sig =
+            Signature::new("name", "email", &Time::new(0, 2000))?;
+        let c = repo.commit(
+            Some("HEAD"),
+            &sig,
+            &sig,
+            "bogus offset",
+            &parent.tree()?,
+            &[&parent],
+        )?;
+
+        let out = TempDir::new()?;
+        let range = format!("{}..{}", base.to_string(), c);
+
+        let files = format_patch(repo_path, &range, out.path())?;
+
+        let patch = fs::read_to_string(&files[0])?;
+        assert_eq!(
+            patch.contains(" Jan 1970 00:00:00 +0000\n"),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_patch() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let base = get_head(repo_path)?;
+
+        File::create(&root.join(file_path))?.write_all(b"a\n")?;
+        stage_add_file(repo_path, file_path)?;
+        let c1 = commit(repo_path, "Add foo: first\n\nsome details")?;
+
+        File::create(&root.join(file_path))?.write_all(b"b\n")?;
+        stage_add_file(repo_path, file_path)?;
+        let c2 = commit(repo_path, "Change foo")?;
+
+        let out = TempDir::new()?;
+        let range =
+            format!("{}..{}", base.to_string(), c2.to_string());
+
+        let files = format_patch(repo_path, &range, out.path())?;
+
+        assert_eq!(
+            files,
+            vec![
+                out.path().join("0001-Add-foo-first.patch"),
+                out.path().join("0002-Change-foo.patch"),
+            ]
+        );
+
+        let first = fs::read_to_string(&files[0])?;
+        assert_eq!(
+            first.starts_with(&format!(
+                "From {} Mon Sep 17 00:00:00 2001\nFrom: name <email>\n",
+                c1.to_string()
+            )),
+            true
+        );
+        assert_eq!(
+            first.contains("\nSubject: [PATCH 1/2] Add foo: first\n\nsome details\n---\n"),
+            true
+        );
+        assert_eq!(first.contains("\n+a\n"), true);
+
+        let second = fs::read_to_string(&files[1])?;
+        assert_eq!(
+            second.contains("\nSubject: [PATCH 2/2] Change foo\n"),
+            true
+        );
+        assert_eq!(second.contains("\n-a\n+b\n"), true);
+
+        let single = format_patch(repo_path, "HEAD", out.path())?;
+        assert_eq!(
+            fs::read_to_string(&single[0])?
+                .contains("\nSubject: [PATCH] Change foo\n"),
+            true
+        );
+
+        Ok(())
+    }
+
+    static PATCH: &str = "diff --git a/foo.txt b/foo.txt
+--- a/foo.txt
++++ b/foo.txt
+@@ -1,3 +1,3 @@
+ a
+-b
++B
+ c
+@@ -5,3 +5,3 @@
+ e
+-f
++F
+ g
+";
+
+    #[test]
+    fn test_apply_patch() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let content = "a\nb\nc\nd\ne\nf\ng\n";
+        File::create(&root.join(file_path))?
+            .write_all(content.as_bytes())?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c1")?;
+
+        let patched = "a\nB\nc\nd\ne\nF\ng\n";
+
+        // into the working tree
+        let rejected =
+            apply_patch(repo_path, PATCH.as_bytes(), false)?;
+        assert_eq!(rejected.is_empty(), true);
+        assert_eq!(
+            fs::read_to_string(root.join(file_path))?,
+            patched
+        );
+        assert_eq!(get_statuses(repo_path), (1, 0));
+
+        File::create(&root.join(file_path))?
+            .write_all(content.as_bytes())?;
+
+        // into the index only
+        let rejected =
+            apply_patch(repo_path, PATCH.as_bytes(), true)?;
+        assert_eq!(rejected.is_empty(), true);
+        assert_eq!(
+            fs::read_to_string(root.join(file_path))?,
+            content
+        );
+        assert_eq!(get_statuses(repo_path), (1, 1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_patch_rejects() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        // the context of the second hunk does not match
+        let content = "a\nb\nc\nd\ne\nx\ng\n";
+        File::create(&root.join(file_path))?
+            .write_all(content.as_bytes())?;
+
+        let rejected =
+            apply_patch(repo_path, PATCH.as_bytes(), false)?;
+
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].path, "foo.txt");
+        assert_eq!(rejected[0].header, "@@ -5,3 +5,3 @@");
+
+        // nothing applied partially
+        assert_eq!(
+            fs::read_to_string(root.join(file_path))?,
+            content
+        );
+
+        Ok(())
+    }
+}
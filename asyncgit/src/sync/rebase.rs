@@ -0,0 +1,96 @@
+use super::utils::repo;
+use crate::error::Result;
+use git2::ErrorCode;
+use scopetime::scope_time;
+
+/// step of the rebase in progress as `(current, total)`, current is
+/// 1-based and `0` before the first commit was picked,
+/// None if no rebase is in progress
+pub fn rebase_progress(
+    repo_path: &str,
+) -> Result<Option<(usize, usize)>> {
+    scope_time!("rebase_progress");
+
+    let repo = repo(repo_path)?;
+
+    let mut rebase = match repo.open_rebase(None) {
+        Ok(rebase) => rebase,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let current = rebase.operation_current().map_or(0, |idx| idx + 1);
+
+    Ok(Some((current, rebase.len())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rebase_progress;
+    use crate::error::Result;
+    use crate::sync::{commit, stage_add_file, tests::repo_init};
+    use git2::BranchType;
+    use std::{fs::File, io::Write, path::Path};
+
+    #[test]
+    fn test_rebase_progress() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        assert_eq!(rebase_progress(repo_path)?, None);
+
+        let write = |file: &str, content: &str| -> Result<()> {
+            File::create(&root.join(file))?
+                .write_all(content.as_bytes())?;
+            stage_add_file(repo_path, Path::new(file))?;
+            Ok(())
+        };
+
+        write("foo", "base")?;
+        commit(repo_path, "base")?;
+
+        let base = repo.head()?.peel_to_commit()?;
+        repo.branch("feature", &base, false)?;
+
+        write("foo", "master")?;
+        commit(repo_path, "master")?;
+
+        repo.set_head("refs/heads/feature")?;
+        repo.checkout_head(Some(
+            git2::build::CheckoutBuilder::new().force(),
+        ))?;
+
+        write("foo", "feature")?;
+        commit(repo_path, "c1")?;
+        write("bar", "feature")?;
+        commit(repo_path, "c2")?;
+
+        // staged through another handle
+        repo.index()?.read(true)?;
+
+        let branch = repo.reference_to_annotated_commit(
+            repo.find_branch("feature", BranchType::Local)?.get(),
+        )?;
+        let upstream = repo.reference_to_annotated_commit(
+            repo.find_branch("master", BranchType::Local)?.get(),
+        )?;
+
+        let mut rebase =
+            repo.rebase(Some(&branch), Some(&upstream), None, None)?;
+
+        assert_eq!(rebase_progress(repo_path)?, Some((0, 2)));
+
+        // picking c1 conflicts
+        rebase.next().unwrap()?;
+        assert_eq!(repo.index()?.has_conflicts(), true);
+
+        assert_eq!(rebase_progress(repo_path)?, Some((1, 2)));
+
+        rebase.abort()?;
+
+        assert_eq!(rebase_progress(repo_path)?, None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+use super::{utils::repo, CommitId};
+use crate::error::Result;
+use git2::Reference;
+use scopetime::scope_time;
+
+/// what a reference is used for (derived from its name)
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RefKind {
+    /// `refs/heads/`
+    LocalBranch,
+    /// `refs/remotes/`
+    RemoteBranch,
+    /// `refs/tags/`
+    Tag,
+    /// `refs/notes/`
+    Note,
+    ///
+    Other,
+}
+
+impl RefKind {
+    fn from_refname(name: &str) -> Self {
+        if name.starts_with("refs/heads/") {
+            Self::LocalBranch
+        } else if name.starts_with("refs/remotes/") {
+            Self::RemoteBranch
+        } else if name.starts_with("refs/tags/") {
+            Self::Tag
+        } else if name.starts_with("refs/notes/") {
+            Self::Note
+        } else {
+            Self::Other
+        }
+    }
+}
+
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct RefInfo {
+    /// full refname (`refs/heads/master`)
+    pub name: String,
+    ///
+    pub kind: RefKind,
+    /// commit the ref (after resolving and peeling) points to,
+    /// None if it does not point to a commit
+    pub target: Option<CommitId>,
+    /// refname a symbolic ref points to
+    /// (like `refs/remotes/origin/HEAD`)
+    pub symbolic_target: Option<String>,
+}
+
+/// all references of the repo sorted by name
+pub fn get_all_refs(repo_path: &str) -> Result<Vec<RefInfo>> {
+    scope_time!("get_all_refs");
+
+    let repo = repo(repo_path)?;
+
+    let mut res = Vec::new();
+
+    for reference in repo.references()? {
+        let reference = reference?;
+
+        let name = match reference.name() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+
+        res.push(RefInfo {
+            kind: RefKind::from_refname(&name),
+            name,
+            target: peel_to_commit_id(&reference),
+            symbolic_target: reference
+                .symbolic_target()
+                .map(String::from),
+        });
+    }
+
+    res.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(res)
+}
+
+fn peel_to_commit_id(reference: &Reference) -> Option<CommitId> {
+    let resolved = reference.resolve().ok()?;
+    let commit = resolved.peel_to_commit().ok()?;
+    Some(commit.id().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_all_refs, RefKind};
+    use crate::error::Result;
+    use crate::sync::{get_head, set_commit_note, tests::repo_init};
+
+    #[test]
+    fn test_all_refs() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let head = get_head(repo_path)?;
+        let head_commit = repo.find_commit(head.into())?;
+
+        repo.branch("feature", &head_commit, false)?;
+        repo.reference(
+            "refs/remotes/origin/master",
+            head.into(),
+            false,
+            "",
+        )?;
+        repo.reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/master",
+            false,
+            "",
+        )?;
+        repo.tag(
+            "v1",
+            head_commit.as_object(),
+            &repo.signature()?,
+            "annotated",
+            false,
+        )?;
+        set_commit_note(repo_path, head, "note", None)?;
+
+        let refs = get_all_refs(repo_path)?;
+
+        let names =
+            refs.iter().map(|r| r.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "refs/heads/feature",
+                "refs/heads/master",
+                "refs/notes/commits",
+                "refs/remotes/origin/HEAD",
+                "refs/remotes/origin/master",
+                "refs/tags/v1",
+            ]
+        );
+
+        let kinds = refs.iter().map(|r| r.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                RefKind::LocalBranch,
+                RefKind::LocalBranch,
+                RefKind::Note,
+                RefKind::RemoteBranch,
+                RefKind::RemoteBranch,
+                RefKind::Tag,
+            ]
+        );
+
+        // annotated tag and symbolic ref are resolved
+        assert_eq!(refs[3].target, Some(head));
+        assert_eq!(
+            refs[3].symbolic_target.as_deref(),
+            Some("refs/remotes/origin/master")
+        );
+        assert_eq!(refs[4].symbolic_target, None);
+        assert_eq!(refs[5].target, Some(head));
+        assert_eq!(refs[0].target, Some(head));
+
+        Ok(())
+    }
+}
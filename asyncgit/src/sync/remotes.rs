@@ -0,0 +1,116 @@
+//! sync git api for remotes
+
+use super::utils::repo;
+use crate::error::Result;
+use git2::{Direction, ReferenceType};
+use scopetime::scope_time;
+use std::collections::HashSet;
+
+/// deletes remote-tracking refs of `remote` whose branch does not
+/// exist on the remote anymore (like `git remote prune`) and returns
+/// their short names (like `origin/feature`). connects to the remote
+/// to list its refs, credentials are not supported yet
+pub fn prune_remote(
+    repo_path: &str,
+    remote: &str,
+) -> Result<Vec<String>> {
+    scope_time!("prune_remote");
+
+    let repo = repo(repo_path)?;
+    let mut remote = repo.find_remote(remote)?;
+
+    remote.connect(Direction::Fetch)?;
+    let remote_refs = remote
+        .list()?
+        .iter()
+        .map(|head| head.name().to_string())
+        .collect::<HashSet<_>>();
+    remote.disconnect()?;
+
+    let refspecs = remote
+        .refspecs()
+        .filter(|spec| matches!(spec.direction(), Direction::Fetch))
+        .collect::<Vec<_>>();
+
+    let mut stale = Vec::new();
+
+    for reference in repo.references()? {
+        let reference = reference?;
+
+        // like `origin/HEAD`
+        if reference.kind() != Some(ReferenceType::Direct) {
+            continue;
+        }
+
+        let name = match reference.name() {
+            Some(name) => name,
+            None => continue,
+        };
+
+        for spec in refspecs.iter().filter(|s| s.dst_matches(name)) {
+            let src = spec.rtransform(name)?;
+            let src = src.as_str().unwrap_or_default();
+
+            if !remote_refs.contains(src) {
+                stale.push(name.to_string());
+                break;
+            }
+        }
+    }
+
+    let mut res = Vec::with_capacity(stale.len());
+
+    for name in stale {
+        let mut reference = repo.find_reference(&name)?;
+        res.push(reference.shorthand().unwrap_or(&name).to_string());
+        reference.delete()?;
+    }
+
+    res.sort();
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::prune_remote;
+    use crate::error::Result;
+    use crate::sync::{get_remote_branches, tests::repo_init};
+    use git2::{build::RepoBuilder, BranchType};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_prune_remote() -> Result<()> {
+        let (_remote_td, remote) = repo_init().unwrap();
+        let remote_root = remote.path().parent().unwrap();
+        let remote_path = remote_root.as_os_str().to_str().unwrap();
+
+        let head = remote.head()?.peel_to_commit()?;
+        remote.branch("feature", &head, false)?;
+        remote.branch("gone", &head, false)?;
+
+        let td = TempDir::new()?;
+        RepoBuilder::new().clone(remote_path, td.path())?;
+        let repo_path = td.path().to_str().unwrap();
+
+        assert_eq!(
+            prune_remote(repo_path, "origin")?.is_empty(),
+            true
+        );
+
+        remote.find_branch("gone", BranchType::Local)?.delete()?;
+
+        assert_eq!(
+            prune_remote(repo_path, "origin")?,
+            vec!["origin/gone"]
+        );
+        assert_eq!(
+            get_remote_branches(repo_path, "origin")?,
+            vec!["origin/feature", "origin/master"]
+        );
+
+        assert_eq!(prune_remote(repo_path, "missing").is_err(), true);
+
+        Ok(())
+    }
+}
@@ -21,7 +21,34 @@ pub fn reset_stage(repo_path: &str, path: &str) -> Result<()> {
     Ok(())
 }
 
-///
+/// unstages all `paths` at once
+pub fn unstage_items(
+    repo_path: &str,
+    paths: &[String],
+) -> Result<()> {
+    scope_time!("unstage_items");
+
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let repo = repo(repo_path)?;
+
+    if let Ok(id) = get_head_repo(&repo) {
+        let obj =
+            repo.find_object(id.into(), Some(ObjectType::Commit))?;
+
+        repo.reset_default(Some(&obj), paths)?;
+    } else {
+        repo.reset_default(None, paths)?;
+    }
+
+    Ok(())
+}
+
+/// discards the unstaged changes of `path` (file or folder) by
+/// checking out its staged state, this also restores deleted files
+/// and removes untracked ones
 pub fn reset_workdir(repo_path: &str, path: &str) -> Result<()> {
     scope_time!("reset_workdir");
 
@@ -40,7 +67,7 @@ pub fn reset_workdir(repo_path: &str, path: &str) -> Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use super::{reset_stage, reset_workdir};
+    use super::{reset_stage, reset_workdir, unstage_items};
     use crate::error::Result;
     use crate::sync::{
         commit,
@@ -48,7 +75,7 @@ mod tests {
         tests::{
             debug_cmd_print, get_statuses, repo_init, repo_init_empty,
         },
-        utils::{stage_add_all, stage_add_file},
+        utils::{stage_add_all, stage_add_file, stage_items},
     };
     use std::{
         fs::{self, File},
@@ -311,4 +338,69 @@ mod tests {
 
         assert_eq!(get_statuses(repo_path), (0, 0));
     }
+
+    #[test]
+    fn test_unstage_items() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join("modified.txt"))?.write_all(b"a")?;
+        File::create(&root.join("deleted.txt"))?.write_all(b"b")?;
+        stage_add_all(repo_path, "*")?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join("modified.txt"))?.write_all(b"c")?;
+        fs::remove_file(root.join("deleted.txt"))?;
+        File::create(&root.join("new.txt"))?.write_all(b"d")?;
+
+        let paths = vec![
+            String::from("new.txt"),
+            String::from("modified.txt"),
+            String::from("deleted.txt"),
+        ];
+
+        assert_eq!(get_statuses(repo_path), (3, 0));
+
+        stage_items(repo_path, &paths)?;
+
+        assert_eq!(get_statuses(repo_path), (0, 3));
+
+        unstage_items(repo_path, &paths[1..])?;
+
+        assert_eq!(get_statuses(repo_path), (2, 1));
+
+        unstage_items(repo_path, &paths)?;
+
+        assert_eq!(get_statuses(repo_path), (3, 0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset_deleted_file() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::create_dir(&root.join("foo"))?;
+        File::create(&root.join("foo/file.txt"))?
+            .write_all(b"content\n")?;
+        stage_add_file(repo_path, Path::new("foo/file.txt"))?;
+        commit(repo_path, "msg")?;
+
+        // the now empty parent dir is gone as well
+        fs::remove_dir_all(&root.join("foo"))?;
+        assert_eq!(get_statuses(repo_path), (1, 0));
+
+        reset_workdir(repo_path, "foo/file.txt")?;
+
+        assert_eq!(get_statuses(repo_path), (0, 0));
+        assert_eq!(
+            fs::read_to_string(root.join("foo/file.txt"))?,
+            "content\n"
+        );
+
+        Ok(())
+    }
 }
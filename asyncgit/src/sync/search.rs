@@ -0,0 +1,456 @@
+use super::{
+    commit_files::get_commit_diff,
+    diff::{DiffLineType, FileDiff},
+    mailmap::{read_mailmap, resolve_signature},
+    utils::repo,
+    CommitId,
+};
+use crate::error::{Error, Result};
+use bitflags::bitflags;
+use git2::{Commit, DiffLine, Signature};
+use regex::{Regex, RegexBuilder};
+use scopetime::scope_time;
+
+bitflags! {
+    /// fields of a commit a search query is matched against
+    pub struct SearchFields: u32 {
+        /// first line of the message
+        const SUBJECT = 0b0_0001;
+        /// the full message
+        const MESSAGE = 0b0_0010;
+        /// name and email of the author
+        const AUTHOR = 0b0_0100;
+        /// name and email of the committer
+        const COMMITTER = 0b0_1000;
+        /// interpret the query as a regular expression
+        const REGEX = 0b1_0000;
+    }
+}
+
+/// case insensitive matcher for a search query
+enum Matcher {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Matcher {
+    fn new(query: &str, regex: bool) -> Result<Self> {
+        Ok(if regex {
+            Self::Regex(
+                RegexBuilder::new(query)
+                    .case_insensitive(true)
+                    .build()?,
+            )
+        } else {
+            Self::Substring(query.to_lowercase())
+        })
+    }
+
+    fn is_match(&self, s: &str) -> bool {
+        match self {
+            Self::Substring(query) => {
+                s.to_lowercase().contains(query)
+            }
+            Self::Regex(re) => re.is_match(s),
+        }
+    }
+}
+
+/// search the history of `HEAD` (newest first) for commits matching
+/// `query` in any of the given `fields`, returning at most `limit`
+/// results
+pub fn search_commits(
+    repo_path: &str,
+    query: &str,
+    fields: SearchFields,
+    limit: usize,
+) -> Result<Vec<CommitId>> {
+    scope_time!("search_commits");
+
+    let repo = repo(repo_path)?;
+    let mailmap = read_mailmap(&repo);
+    let matcher =
+        Matcher::new(query, fields.contains(SearchFields::REGEX))?;
+
+    let signature_matches = |sig: Signature| {
+        let sig = resolve_signature(mailmap.as_ref(), &sig);
+        let name = sig.name().unwrap_or("");
+        let email = sig.email().unwrap_or("");
+
+        matcher.is_match(name) || matcher.is_match(email)
+    };
+
+    let is_match = |c: &Commit| {
+        let msg = String::from_utf8_lossy(c.message_bytes());
+        let subject = msg.trim_start().lines().next().unwrap_or("");
+
+        (fields.contains(SearchFields::SUBJECT)
+            && matcher.is_match(subject))
+            || (fields.contains(SearchFields::MESSAGE)
+                && matcher.is_match(&msg))
+            || (fields.contains(SearchFields::AUTHOR)
+                && signature_matches(c.author()))
+            || (fields.contains(SearchFields::COMMITTER)
+                && signature_matches(c.committer()))
+    };
+
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+
+    let mut res = Vec::new();
+
+    for id in walk {
+        if res.len() >= limit {
+            break;
+        }
+
+        let commit = repo.find_commit(id?)?;
+
+        if is_match(&commit) {
+            res.push(commit.id().into());
+        }
+    }
+
+    Ok(res)
+}
+
+/// how `search_content` matches the changes of a commit
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PickaxeMode {
+    /// like `git log -S`: the number of occurrences of the needle
+    /// differs between removed and added lines
+    OccurrenceCount,
+    /// like `git log -G`: an added or removed line matches the
+    /// needle as a regular expression
+    RegexMatch,
+}
+
+/// search the history of `HEAD` (newest first) for commits whose
+/// changes contain `needle` according to `mode`, returning at most
+/// `limit` results
+pub fn search_content(
+    repo_path: &str,
+    needle: &str,
+    mode: PickaxeMode,
+    limit: usize,
+) -> Result<Vec<CommitId>> {
+    scope_time!("search_content");
+
+    if needle.is_empty() {
+        return Err(Error::Generic(String::from(
+            "empty search needle",
+        )));
+    }
+
+    let regex = if mode == PickaxeMode::RegexMatch {
+        Some(Regex::new(needle)?)
+    } else {
+        None
+    };
+
+    let repo = repo(repo_path)?;
+
+    let mut walk = repo.revwalk()?;
+    walk.push_head()?;
+
+    let mut res = Vec::new();
+
+    for id in walk {
+        if res.len() >= limit {
+            break;
+        }
+
+        let id = CommitId::new(id?);
+        let diff = get_commit_diff(&repo, id, None)?;
+
+        let mut removed = 0_usize;
+        let mut added = 0_usize;
+        let mut regex_match = false;
+
+        diff.foreach(
+            &mut |_, _| true,
+            None,
+            None,
+            Some(&mut |_, _, line: DiffLine| {
+                let content = String::from_utf8_lossy(line.content());
+                let content = content.trim_end_matches('\n');
+                match (line.origin(), &regex) {
+                    ('+', Some(re)) | ('-', Some(re)) => {
+                        regex_match = re.is_match(content);
+                    }
+                    ('+', None) => {
+                        added += content.matches(needle).count()
+                    }
+                    ('-', None) => {
+                        removed += content.matches(needle).count()
+                    }
+                    _ => (),
+                }
+                // stop the iteration once a regex matched
+                !regex_match
+            }),
+        )
+        .or_else(|e| {
+            if regex_match {
+                Ok(())
+            } else {
+                Err(e)
+            }
+        })?;
+
+        if regex_match || added != removed {
+            res.push(id);
+        }
+    }
+
+    Ok(res)
+}
+
+/// positions (`(hunk index, line index)`) of all lines in `diff`
+/// matching `query` (case insensitive substring or regex),
+/// hunk headers are skipped
+pub fn find_in_diff(
+    diff: &FileDiff,
+    query: &str,
+    regex: bool,
+) -> Result<Vec<(usize, usize)>> {
+    let matcher = Matcher::new(query, regex)?;
+
+    let mut res = Vec::new();
+
+    for (hunk_idx, hunk) in diff.hunks.iter().enumerate() {
+        for (line_idx, line) in hunk.lines.iter().enumerate() {
+            if line.line_type != DiffLineType::Header
+                && matcher
+                    .is_match(line.content.trim_end_matches('\n'))
+            {
+                res.push((hunk_idx, line_idx));
+            }
+        }
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        find_in_diff, search_commits, search_content, PickaxeMode,
+        SearchFields,
+    };
+    use crate::error::Result;
+    use crate::sync::diff::{DiffLine, DiffLineType, FileDiff, Hunk};
+    use crate::sync::{
+        commit_with_options, stage_add_file, tests::repo_init_empty,
+        CommitId, CommitOptions, CommitSignature,
+    };
+    use std::{fs::File, io::Write, path::Path};
+
+    fn commit_as(
+        repo_path: &str,
+        root: &Path,
+        msg: &str,
+        author: &str,
+    ) -> CommitId {
+        let file = Path::new("foo");
+        File::create(&root.join(file))
+            .unwrap()
+            .write_all(msg.as_bytes())
+            .unwrap();
+        stage_add_file(repo_path, file).unwrap();
+
+        let options = CommitOptions {
+            author: Some(CommitSignature {
+                name: author.to_string(),
+                email: format!("{}@mail", author),
+                time: 0,
+                time_offset: 0,
+            }),
+            ..CommitOptions::default()
+        };
+
+        commit_with_options(repo_path, msg, &options).unwrap()
+    }
+
+    #[test]
+    fn test_search() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let c1 = commit_as(repo_path, root, "Fix parser", "alice");
+        let c2 = commit_as(
+            repo_path,
+            root,
+            "add tests\n\nparser too",
+            "bob",
+        );
+        let c3 = commit_as(repo_path, root, "update docs", "Alice");
+
+        assert_eq!(
+            search_commits(
+                repo_path,
+                "PARSER",
+                SearchFields::SUBJECT,
+                10
+            )?,
+            vec![c1]
+        );
+        assert_eq!(
+            search_commits(
+                repo_path,
+                "parser",
+                SearchFields::MESSAGE,
+                10
+            )?,
+            vec![c2, c1]
+        );
+        assert_eq!(
+            search_commits(
+                repo_path,
+                "alice",
+                SearchFields::AUTHOR,
+                10
+            )?,
+            vec![c3, c1]
+        );
+        assert_eq!(
+            search_commits(
+                repo_path,
+                "alice",
+                SearchFields::AUTHOR,
+                1
+            )?,
+            vec![c3]
+        );
+        assert_eq!(
+            search_commits(
+                repo_path,
+                "bob",
+                SearchFields::SUBJECT | SearchFields::COMMITTER,
+                10
+            )?,
+            vec![]
+        );
+        assert_eq!(
+            search_commits(
+                repo_path,
+                "^(fix|update) ",
+                SearchFields::SUBJECT | SearchFields::REGEX,
+                10
+            )?,
+            vec![c3, c1]
+        );
+        assert_eq!(
+            search_commits(
+                repo_path,
+                "(",
+                SearchFields::SUBJECT | SearchFields::REGEX,
+                10
+            )
+            .is_err(),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_content() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let c1 = commit_as(repo_path, root, "a\n", "alice");
+        let c2 =
+            commit_as(repo_path, root, "a\nfoo_bar()\n", "alice");
+        let c3 =
+            commit_as(repo_path, root, "b\nfoo_bar()\n", "alice");
+        let c4 = commit_as(repo_path, root, "b\n", "alice");
+
+        assert_eq!(
+            search_content(
+                repo_path,
+                "foo_bar",
+                PickaxeMode::OccurrenceCount,
+                10
+            )?,
+            vec![c4, c2]
+        );
+        assert_eq!(
+            search_content(
+                repo_path,
+                "foo_bar",
+                PickaxeMode::OccurrenceCount,
+                1
+            )?,
+            vec![c4]
+        );
+        assert_eq!(
+            search_content(
+                repo_path,
+                "fo+_ba[r]",
+                PickaxeMode::RegexMatch,
+                10
+            )?,
+            vec![c4, c2]
+        );
+        assert_eq!(
+            search_content(
+                repo_path,
+                "^a$",
+                PickaxeMode::RegexMatch,
+                10
+            )?,
+            vec![c3, c1]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_in_diff() -> Result<()> {
+        let line = |line_type, content: &str| DiffLine {
+            content: format!("{}\n", content),
+            line_type,
+            ..DiffLine::default()
+        };
+        let hunk = |lines| Hunk {
+            lines,
+            ..Hunk::default()
+        };
+
+        let diff = FileDiff {
+            hunks: vec![
+                hunk(vec![
+                    line(DiffLineType::Header, "@@ -1,2 +1,2 @@ foo"),
+                    line(DiffLineType::Delete, "let foo = 1;"),
+                    line(DiffLineType::Add, "let foo = 2;"),
+                ]),
+                hunk(vec![
+                    line(DiffLineType::Header, "@@ -10,2 +10,2 @@"),
+                    line(DiffLineType::None, "bar(Foo);"),
+                    line(DiffLineType::Add, "baz(42);"),
+                ]),
+            ],
+            ..FileDiff::default()
+        };
+
+        assert_eq!(
+            find_in_diff(&diff, "foo", false)?,
+            vec![(0, 1), (0, 2), (1, 1)]
+        );
+        assert_eq!(
+            find_in_diff(&diff, r"\(\d+\);$", true)?,
+            vec![(1, 2)]
+        );
+        assert_eq!(
+            find_in_diff(&diff, "= [0-9]", true)?,
+            vec![(0, 1), (0, 2)]
+        );
+        assert_eq!(find_in_diff(&diff, "missing", false)?, vec![]);
+        assert_eq!(find_in_diff(&diff, "(", true).is_err(), true);
+
+        Ok(())
+    }
+}
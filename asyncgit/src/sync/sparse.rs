@@ -0,0 +1,166 @@
+//! sparse-checkout patterns (`.git/info/sparse-checkout`),
+//! git2 does not know about them so they are matched here
+
+use crate::error::Result;
+use git2::{ErrorCode, Repository};
+use std::fs;
+
+struct Pattern {
+    glob: String,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+/// the sparse set of a repo with `core.sparseCheckout` enabled
+pub(crate) struct SparseCheckout {
+    patterns: Vec<Pattern>,
+}
+
+impl SparseCheckout {
+    /// None unless sparse checkout is enabled and configured
+    pub fn load(repo: &Repository) -> Result<Option<Self>> {
+        let enabled =
+            match repo.config()?.get_bool("core.sparseCheckout") {
+                Ok(enabled) => enabled,
+                Err(e) if e.code() == ErrorCode::NotFound => false,
+                Err(e) => return Err(e.into()),
+            };
+
+        let file = repo.path().join("info").join("sparse-checkout");
+
+        if !enabled || !file.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self::parse(&fs::read_to_string(file)?)))
+    }
+
+    fn parse(content: &str) -> Self {
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|line| {
+                let (negated, line) = match line.strip_prefix('!') {
+                    Some(line) => (true, line),
+                    None => (false, line),
+                };
+                let (dir_only, line) = match line.strip_suffix('/') {
+                    Some(line) => (true, line),
+                    None => (false, line),
+                };
+                let (anchored, glob) = match line.strip_prefix('/') {
+                    Some(line) => (true, line),
+                    None => (line.contains('/'), line),
+                };
+
+                Pattern {
+                    glob: glob.to_string(),
+                    negated,
+                    dir_only,
+                    anchored,
+                }
+            })
+            .collect();
+
+        Self { patterns }
+    }
+
+    /// whether the file `path` (relative to the workdir) is part of
+    /// the sparse set, the last matching pattern wins
+    pub fn is_included(&self, path: &str) -> bool {
+        let mut included = false;
+
+        for pattern in &self.patterns {
+            if pattern.matches(path) {
+                included = !pattern.negated;
+            }
+        }
+
+        included
+    }
+}
+
+impl Pattern {
+    /// matches the file itself or any of its parent dirs
+    fn matches(&self, path: &str) -> bool {
+        let dirs = path
+            .match_indices('/')
+            .map(|(idx, _)| (&path[..idx], true));
+
+        dirs.chain(std::iter::once((path, false))).any(
+            |(p, is_dir)| {
+                if self.dir_only && !is_dir {
+                    return false;
+                }
+
+                if self.anchored {
+                    glob_match(&self.glob, p)
+                } else {
+                    let name = p.rsplit('/').next().unwrap_or(p);
+                    glob_match(&self.glob, name)
+                }
+            },
+        )
+    }
+}
+
+/// `*` and `?` do not match a `/`, `**` matches anything
+fn glob_match(glob: &str, text: &str) -> bool {
+    let glob = glob.as_bytes();
+    let text = text.as_bytes();
+
+    fn matches(glob: &[u8], text: &[u8]) -> bool {
+        let mut skips = 0..=text.len();
+
+        match glob.first() {
+            None => text.is_empty(),
+            Some(b'*') if glob.get(1) == Some(&b'*') => {
+                skips.any(|i| matches(&glob[2..], &text[i..]))
+            }
+            Some(b'*') => skips
+                .take_while(|i| *i == 0 || text[i - 1] != b'/')
+                .any(|i| matches(&glob[1..], &text[i..])),
+            Some(b'?') => match text.first() {
+                Some(c) if *c != b'/' => {
+                    matches(&glob[1..], &text[1..])
+                }
+                _ => false,
+            },
+            Some(c) => {
+                text.first() == Some(c)
+                    && matches(&glob[1..], &text[1..])
+            }
+        }
+    }
+
+    matches(glob, text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SparseCheckout;
+
+    #[test]
+    fn test_cone_patterns() {
+        let sparse = SparseCheckout::parse("/*\n!/*/\n/keep/\n");
+
+        assert_eq!(sparse.is_included("top.txt"), true);
+        assert_eq!(sparse.is_included("keep/a.txt"), true);
+        assert_eq!(sparse.is_included("keep/sub/b.txt"), true);
+        assert_eq!(sparse.is_included("drop/b.txt"), false);
+    }
+
+    #[test]
+    fn test_unanchored_patterns() {
+        let sparse =
+            SparseCheckout::parse("# docs only\n*.md\n!old*\n");
+
+        assert_eq!(sparse.is_included("README.md"), true);
+        assert_eq!(sparse.is_included("docs/intro.md"), true);
+        assert_eq!(sparse.is_included("docs/old.md"), false);
+        assert_eq!(sparse.is_included("old/intro.md"), false);
+        assert_eq!(sparse.is_included("src/main.rs"), false);
+    }
+}
@@ -0,0 +1,126 @@
+//! external commit message checker
+
+use super::utils::{repo, work_dir};
+use crate::error::{Error, Result};
+use scopetime::scope_time;
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+};
+
+/// pipes `msg` into the external checker `command` (run via the
+/// platform shell in the workdir) and returns its findings (stdout),
+/// empty if there are none.
+/// checkers usually exit with an error if they found something,
+/// so this only fails if there is no output but an error message
+pub fn spell_check(
+    repo_path: &str,
+    command: &str,
+    msg: &str,
+) -> Result<String> {
+    scope_time!("spell_check");
+
+    let repo = repo(repo_path)?;
+
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+
+    let mut child = cmd
+        .arg(command)
+        .current_dir(work_dir(&repo)?)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            Error::Generic(format!("\"{}\": {}", command, e))
+        })?;
+
+    // written on its own so a checker that prints before it read
+    // everything can not block us while we block it
+    let writer = child.stdin.take().map(|mut stdin| {
+        let msg = msg.to_string();
+        thread::spawn(move || {
+            // the checker might not read everything, that's fine
+            stdin.write_all(msg.as_bytes()).ok();
+        })
+    });
+
+    let output = child.wait_with_output()?;
+
+    if let Some(writer) = writer {
+        writer.join().ok();
+    }
+
+    let findings = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    if !output.status.success()
+        && findings.trim().is_empty()
+        && !stderr.trim().is_empty()
+    {
+        return Err(Error::Generic(format!(
+            "\"{}\" failed: {}",
+            command,
+            stderr.trim()
+        )));
+    }
+
+    Ok(findings.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::spell_check;
+    use crate::sync::tests::repo_init;
+
+    #[test]
+    fn test_spell_check() {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let checker = "grep -n teh";
+
+        assert_eq!(
+            spell_check(
+                repo_path,
+                checker,
+                "subject\n\nfix teh bug\n"
+            )
+            .unwrap(),
+            "3:fix teh bug"
+        );
+        assert_eq!(
+            spell_check(repo_path, checker, "all good").unwrap(),
+            ""
+        );
+        assert_eq!(
+            spell_check(repo_path, "echo broken >&2; exit 2", "msg")
+                .is_err(),
+            true
+        );
+    }
+
+    #[test]
+    fn test_spell_check_large_output() {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        // more than fits into a pipe in both directions
+        let msg = "teh\n".repeat(100_000);
+
+        let findings = spell_check(repo_path, "cat", &msg).unwrap();
+
+        assert_eq!(findings.lines().count(), 100_000);
+    }
+}
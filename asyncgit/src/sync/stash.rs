@@ -1,13 +1,18 @@
-use super::{utils::repo, CommitId};
+use super::{
+    checkout::checkout_branch_repo, utils::open_repo, CommitId,
+};
 use crate::error::{Error, Result};
-use git2::{Oid, Repository, StashFlags};
+use git2::{
+    build::CheckoutBuilder, Oid, Repository, StashApplyOptions,
+    StashFlags, StatusOptions,
+};
 use scopetime::scope_time;
 
 ///
 pub fn get_stashes(repo_path: &str) -> Result<Vec<CommitId>> {
     scope_time!("get_stashes");
 
-    let mut repo = repo(repo_path)?;
+    let mut repo = open_repo(repo_path)?;
 
     let mut list = Vec::new();
 
@@ -32,7 +37,7 @@ pub fn is_stash_commit(
 pub fn stash_drop(repo_path: &str, stash_id: CommitId) -> Result<()> {
     scope_time!("stash_drop");
 
-    let mut repo = repo(repo_path)?;
+    let mut repo = open_repo(repo_path)?;
 
     let index = get_stash_index(&mut repo, stash_id.into())?;
 
@@ -41,6 +46,118 @@ pub fn stash_drop(repo_path: &str, stash_id: CommitId) -> Result<()> {
     Ok(())
 }
 
+/// drops the stash at `index` (0 being the most recent one as
+/// listed by `get_stashes`). the indices of all older stashes
+/// shift down by one afterwards
+pub fn stash_drop_index(repo_path: &str, index: usize) -> Result<()> {
+    scope_time!("stash_drop_index");
+
+    let mut repo = open_repo(repo_path)?;
+
+    let count = stash_count_repo(&mut repo)?;
+
+    if index >= count {
+        return Err(Error::Generic(format!(
+            "stash index {} out of range ({} stashes)",
+            index, count
+        )));
+    }
+
+    repo.stash_drop(index)?;
+
+    Ok(())
+}
+
+/// drops all stashes
+pub fn stash_clear(repo_path: &str) -> Result<()> {
+    scope_time!("stash_clear");
+
+    let mut repo = open_repo(repo_path)?;
+
+    for _ in 0..stash_count_repo(&mut repo)? {
+        repo.stash_drop(0)?;
+    }
+
+    Ok(())
+}
+
+/// like `git stash branch`: creates the branch `branch_name` at
+/// the commit the stash at `index` was based on, checks it out,
+/// applies the stash (including its index) and drops it.
+/// fails without changing anything if the working tree is dirty
+pub fn stash_branch(
+    repo_path: &str,
+    index: usize,
+    branch_name: &str,
+) -> Result<()> {
+    scope_time!("stash_branch");
+
+    let mut repo = open_repo(repo_path)?;
+
+    let mut status_opts = StatusOptions::new();
+    status_opts.include_untracked(false).include_ignored(false);
+    if !repo.statuses(Some(&mut status_opts))?.is_empty() {
+        return Err(Error::Generic(
+            "working tree has local changes".into(),
+        ));
+    }
+
+    let stash_id = get_stash_id(&mut repo, index)?;
+
+    {
+        let base = repo.find_commit(stash_id)?.parent(0)?;
+        repo.branch(branch_name, &base, false)?;
+    }
+
+    checkout_branch_repo(&repo, branch_name)?;
+
+    repo.stash_apply(
+        index,
+        Some(StashApplyOptions::new().reinstantiate_index()),
+    )?;
+    repo.stash_drop(index)?;
+
+    Ok(())
+}
+
+/// id of the stash commit at `index`
+pub(crate) fn get_stash_id(
+    repo: &mut Repository,
+    index: usize,
+) -> Result<Oid> {
+    let mut stash_id = None;
+    repo.stash_foreach(|i, _msg, id| {
+        if i == index {
+            stash_id = Some(*id);
+        }
+        i < index
+    })?;
+
+    stash_id.ok_or_else(|| {
+        Error::Generic(format!("stash index {} not found", index))
+    })
+}
+
+/// number of stashes, cheaper than `get_stashes`
+pub fn stash_count(repo_path: &str) -> Result<usize> {
+    scope_time!("stash_count");
+
+    let mut repo = open_repo(repo_path)?;
+
+    stash_count_repo(&mut repo)
+}
+
+fn stash_count_repo(repo: &mut Repository) -> Result<usize> {
+    let mut count = 0;
+
+    repo.stash_foreach(|_index, _msg, _id| {
+        count += 1;
+        true
+    })?;
+
+    Ok(count)
+}
+
 ///
 pub fn stash_apply(
     repo_path: &str,
@@ -48,7 +165,7 @@ pub fn stash_apply(
 ) -> Result<()> {
     scope_time!("stash_apply");
 
-    let mut repo = repo(repo_path)?;
+    let mut repo = open_repo(repo_path)?;
 
     let index = get_stash_index(&mut repo, stash_id.get_oid())?;
 
@@ -57,6 +174,53 @@ pub fn stash_apply(
     Ok(())
 }
 
+/// applies the changes of the stash at `index` (relative to the
+/// commit it was based on) onto the current `HEAD` using a three-way
+/// merge, even if `HEAD` moved on since. returns the conflicting
+/// paths (written with conflict markers), untracked files of the
+/// stash are not applied. the stash itself is kept
+pub fn stash_apply_as_patch(
+    repo_path: &str,
+    index: usize,
+) -> Result<Vec<String>> {
+    scope_time!("stash_apply_as_patch");
+
+    let mut repo = open_repo(repo_path)?;
+
+    let stash_id = get_stash_id(&mut repo, index)?;
+    let stash = repo.find_commit(stash_id)?;
+    let base = stash.parent(0)?.tree()?;
+    let head = repo.head()?.peel_to_tree()?;
+
+    let mut merged =
+        repo.merge_trees(&base, &head, &stash.tree()?, None)?;
+
+    let mut conflicts = Vec::new();
+    if merged.has_conflicts() {
+        for conflict in merged.conflicts()? {
+            let conflict = conflict?;
+            let entry =
+                conflict.our.or(conflict.their).or(conflict.ancestor);
+            if let Some(entry) = entry {
+                conflicts.push(
+                    String::from_utf8_lossy(&entry.path).into(),
+                );
+            }
+        }
+    }
+
+    repo.checkout_index(
+        Some(&mut merged),
+        Some(
+            CheckoutBuilder::new()
+                .allow_conflicts(true)
+                .conflict_style_merge(true),
+        ),
+    )?;
+
+    Ok(conflicts)
+}
+
 fn get_stash_index(
     repo: &mut Repository,
     stash_id: Oid,
@@ -86,7 +250,7 @@ pub fn stash_save(
 ) -> Result<CommitId> {
     scope_time!("stash_save");
 
-    let mut repo = repo(repo_path)?;
+    let mut repo = open_repo(repo_path)?;
 
     let sig = repo.signature()?;
 
@@ -108,10 +272,15 @@ pub fn stash_save(
 mod tests {
     use super::*;
     use crate::sync::{
-        commit, get_commit_files, get_commits_info, stage_add_file,
+        commit, get_commit_files, get_commits_info, get_head,
+        stage_add_file,
         tests::{debug_cmd_print, get_statuses, repo_init},
     };
-    use std::{fs::File, io::Write, path::Path};
+    use std::{
+        fs::{self, File},
+        io::Write,
+        path::Path,
+    };
 
     #[test]
     fn test_smoke() {
@@ -211,4 +380,152 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_stash_drop_index() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let stashes = ["a", "b", "c"]
+            .iter()
+            .map(|content| {
+                File::create(&root.join("foo.txt"))
+                    .unwrap()
+                    .write_all(content.as_bytes())
+                    .unwrap();
+                stash_save(repo_path, Some(content), true, false)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            get_stashes(repo_path)?,
+            vec![stashes[2], stashes[1], stashes[0]]
+        );
+
+        assert_eq!(stash_drop_index(repo_path, 3).is_err(), true);
+
+        stash_drop_index(repo_path, 1)?;
+
+        assert_eq!(
+            get_stashes(repo_path)?,
+            vec![stashes[2], stashes[0]]
+        );
+
+        stash_clear(repo_path)?;
+
+        assert_eq!(get_stashes(repo_path)?.is_empty(), true);
+        assert_eq!(stash_drop_index(repo_path, 0).is_err(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stash_branch() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path)?;
+        let base = commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?.write_all(b"stashed")?;
+        stash_save(repo_path, None, false, false)?;
+
+        File::create(&root.join(file_path))?.write_all(b"b")?;
+        stage_add_file(repo_path, file_path)?;
+        commit(repo_path, "c2")?;
+
+        // dirty working tree
+        File::create(&root.join(file_path))?.write_all(b"dirty")?;
+        assert_eq!(stash_branch(repo_path, 0, "wip").is_err(), true);
+        assert_eq!(
+            repo.find_branch("wip", git2::BranchType::Local).is_err(),
+            true
+        );
+
+        File::create(&root.join(file_path))?.write_all(b"b")?;
+
+        assert_eq!(stash_branch(repo_path, 1, "wip").is_err(), true);
+
+        stash_branch(repo_path, 0, "wip")?;
+
+        assert_eq!(repo.head()?.shorthand(), Some("wip"));
+        assert_eq!(get_head(repo_path)?, base);
+        assert_eq!(
+            std::fs::read_to_string(root.join(file_path))?,
+            "stashed"
+        );
+        assert_eq!(get_stashes(repo_path)?.is_empty(), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stash_count() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        assert_eq!(stash_count(repo_path)?, 0);
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stash_save(repo_path, None, true, false)?;
+        assert_eq!(stash_count(repo_path)?, 1);
+
+        File::create(&root.join(file_path))?.write_all(b"b")?;
+        stash_save(repo_path, None, true, false)?;
+        assert_eq!(stash_count(repo_path)?, 2);
+
+        stash_drop_index(repo_path, 0)?;
+        assert_eq!(stash_count(repo_path)?, 1);
+
+        stash_clear(repo_path)?;
+        assert_eq!(stash_count(repo_path)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stash_apply_as_patch() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join("foo.txt"))?
+            .write_all(b"a\nb\nc\n")?;
+        File::create(&root.join("bar.txt"))?.write_all(b"x\n")?;
+        stage_add_file(repo_path, Path::new("foo.txt"))?;
+        stage_add_file(repo_path, Path::new("bar.txt"))?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join("foo.txt"))?
+            .write_all(b"A\nb\nc\n")?;
+        File::create(&root.join("bar.txt"))?.write_all(b"y\n")?;
+        stash_save(repo_path, None, true, false)?;
+
+        // HEAD moves on, touching the same line
+        File::create(&root.join("foo.txt"))?
+            .write_all(b"Z\nb\nc\n")?;
+        stage_add_file(repo_path, Path::new("foo.txt"))?;
+        commit(repo_path, "c2")?;
+
+        let conflicts = stash_apply_as_patch(repo_path, 0)?;
+
+        assert_eq!(conflicts, vec![String::from("foo.txt")]);
+        assert_eq!(fs::read_to_string(root.join("bar.txt"))?, "y\n");
+
+        let foo = fs::read_to_string(root.join("foo.txt"))?;
+        assert_eq!(foo.contains("<<<<<<<"), true);
+        assert_eq!(foo.contains("A\n"), true);
+        assert_eq!(foo.contains("Z\n"), true);
+
+        assert_eq!(stash_count(repo_path)?, 1);
+
+        Ok(())
+    }
 }
@@ -1,7 +1,11 @@
 //! sync git api for fetching a status
 
-use crate::{error::Error, error::Result, sync::utils};
-use git2::{Delta, Status, StatusOptions, StatusShow};
+use crate::{
+    error::Error,
+    error::Result,
+    sync::{sparse::SparseCheckout, utils},
+};
+use git2::{Delta, Status, StatusShow};
 use scopetime::scope_time;
 use std::path::Path;
 
@@ -39,7 +43,7 @@ impl From<Status> for StatusItemType {
 impl From<Delta> for StatusItemType {
     fn from(d: Delta) -> Self {
         match d {
-            Delta::Added => StatusItemType::New,
+            Delta::Added | Delta::Untracked => StatusItemType::New,
             Delta::Deleted => StatusItemType::Deleted,
             Delta::Renamed => StatusItemType::Renamed,
             Delta::Typechange => StatusItemType::Typechange,
@@ -84,25 +88,71 @@ impl Into<StatusShow> for StatusType {
     }
 }
 
+/// tuning of `get_status_with_options`, mainly for large repos
+#[derive(Copy, Clone, Hash, PartialEq, Debug)]
+pub struct StatusOptions {
+    /// write refreshed stat info back into the index
+    pub update_index: bool,
+    /// do not reload the index from disk before computing the status
+    pub no_refresh: bool,
+    /// list the files inside of untracked dirs instead of just
+    /// the dir itself (usually the slowest part of a status)
+    pub recurse_untracked_dirs: bool,
+    /// skip checking submodules for changes
+    pub exclude_submodules: bool,
+}
+
+impl Default for StatusOptions {
+    fn default() -> Self {
+        Self {
+            update_index: true,
+            no_refresh: false,
+            recurse_untracked_dirs: true,
+            exclude_submodules: false,
+        }
+    }
+}
+
 ///
 pub fn get_status(
     repo_path: &str,
     status_type: StatusType,
     include_untracked: bool,
+) -> Result<Vec<StatusItem>> {
+    get_status_with_options(
+        repo_path,
+        status_type,
+        include_untracked,
+        StatusOptions::default(),
+    )
+}
+
+/// like `get_status` but allows tuning via `options`
+pub fn get_status_with_options(
+    repo_path: &str,
+    status_type: StatusType,
+    include_untracked: bool,
+    options: StatusOptions,
 ) -> Result<Vec<StatusItem>> {
     scope_time!("get_status");
 
     let repo = utils::repo(repo_path)?;
 
     let statuses = repo.statuses(Some(
-        StatusOptions::default()
+        git2::StatusOptions::default()
             .show(status_type.into())
-            .update_index(true)
+            .update_index(options.update_index)
+            .no_refresh(options.no_refresh)
             .include_untracked(include_untracked)
             .renames_head_to_index(true)
-            .recurse_untracked_dirs(true),
+            .recurse_untracked_dirs(options.recurse_untracked_dirs)
+            .exclude_submodules(options.exclude_submodules),
     ))?;
 
+    // paths outside of the sparse set are not materialized, their
+    // absence in the workdir is no change (the index still counts)
+    let sparse = SparseCheckout::load(&repo)?;
+
     let mut res = Vec::with_capacity(statuses.len());
 
     for e in statuses.iter() {
@@ -128,6 +178,14 @@ pub fn get_status(
             })?,
         };
 
+        if let Some(sparse) = &sparse {
+            if status == Status::WT_DELETED
+                && !sparse.is_included(&path)
+            {
+                continue;
+            }
+        }
+
         res.push(StatusItem {
             path,
             status: StatusItemType::from(status),
@@ -140,3 +198,216 @@ pub fn get_status(
 
     Ok(res)
 }
+
+/// like `get_status` but only returns items of one of the `types`
+pub fn get_status_filtered(
+    repo_path: &str,
+    status_type: StatusType,
+    include_untracked: bool,
+    types: &[StatusItemType],
+) -> Result<Vec<StatusItem>> {
+    scope_time!("get_status_filtered");
+
+    let mut res =
+        get_status(repo_path, status_type, include_untracked)?;
+
+    res.retain(|item| types.contains(&item.status));
+
+    Ok(res)
+}
+
+/// whether `path` (relative to the workdir) is known to git, either
+/// in the index or in the `HEAD` commit (staged for deletion)
+pub fn is_tracked(repo_path: &str, path: &str) -> Result<bool> {
+    scope_time!("is_tracked");
+
+    let repo = utils::repo(repo_path)?;
+
+    let path = Path::new(path);
+
+    if repo.index()?.get_path(path, 0).is_some() {
+        return Ok(true);
+    }
+
+    let head = match repo.head() {
+        Ok(head) => head.peel_to_tree()?,
+        Err(_) => return Ok(false),
+    };
+
+    let in_head = head.get_path(path).is_ok();
+
+    Ok(in_head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        get_status, get_status_filtered, get_status_with_options,
+        is_tracked, StatusItemType, StatusOptions, StatusType,
+    };
+    use crate::error::Result;
+    use crate::sync::{
+        commit, stage_add_file, stage_addremoved, tests::repo_init,
+    };
+    use std::{
+        fs::{self, File},
+        io::Write,
+        path::Path,
+    };
+
+    #[test]
+    fn test_no_untracked_recursion() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::create_dir_all(root.join("dir/sub"))?;
+        File::create(&root.join("dir/a.txt"))?.write_all(b"a")?;
+        File::create(&root.join("dir/sub/b.txt"))?.write_all(b"b")?;
+        File::create(&root.join("c.txt"))?.write_all(b"c")?;
+
+        let paths = |items: Vec<super::StatusItem>| {
+            items.into_iter().map(|i| i.path).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            paths(get_status(
+                repo_path,
+                StatusType::WorkingDir,
+                true
+            )?),
+            vec!["c.txt", "dir/a.txt", "dir/sub/b.txt"]
+        );
+
+        let options = StatusOptions {
+            recurse_untracked_dirs: false,
+            ..StatusOptions::default()
+        };
+
+        assert_eq!(
+            paths(get_status_with_options(
+                repo_path,
+                StatusType::WorkingDir,
+                true,
+                options
+            )?),
+            vec!["c.txt", "dir/"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_tracked() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join("committed.txt"))?.write_all(b"a")?;
+        stage_add_file(repo_path, Path::new("committed.txt"))?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join("new.txt"))?.write_all(b"b")?;
+        File::create(&root.join("staged.txt"))?.write_all(b"c")?;
+        stage_add_file(repo_path, Path::new("staged.txt"))?;
+        fs::create_dir_all(root.join("dir"))?;
+        File::create(&root.join("dir/file.txt"))?.write_all(b"d")?;
+
+        assert_eq!(is_tracked(repo_path, "committed.txt")?, true);
+        assert_eq!(is_tracked(repo_path, "new.txt")?, false);
+        assert_eq!(is_tracked(repo_path, "staged.txt")?, true);
+        assert_eq!(is_tracked(repo_path, "dir/file.txt")?, false);
+
+        // staged for deletion but still in HEAD
+        fs::remove_file(root.join("committed.txt"))?;
+        stage_addremoved(repo_path, Path::new("committed.txt"))?;
+        assert_eq!(is_tracked(repo_path, "committed.txt")?, true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_status_filtered() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join("modified.txt"))?.write_all(b"a")?;
+        File::create(&root.join("deleted.txt"))?.write_all(b"b")?;
+        stage_add_file(repo_path, Path::new("modified.txt"))?;
+        stage_add_file(repo_path, Path::new("deleted.txt"))?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join("modified.txt"))?.write_all(b"c")?;
+        fs::remove_file(root.join("deleted.txt"))?;
+        File::create(&root.join("new.txt"))?.write_all(b"d")?;
+
+        assert_eq!(
+            get_status(repo_path, StatusType::WorkingDir, true)?
+                .len(),
+            3
+        );
+
+        let res = get_status_filtered(
+            repo_path,
+            StatusType::WorkingDir,
+            true,
+            &[StatusItemType::Deleted],
+        )?;
+
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].path, "deleted.txt");
+        assert_eq!(res[0].status, StatusItemType::Deleted);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse_checkout() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::create_dir_all(root.join("keep"))?;
+        fs::create_dir_all(root.join("drop"))?;
+        File::create(&root.join("top.txt"))?.write_all(b"a")?;
+        File::create(&root.join("keep/a.txt"))?.write_all(b"b")?;
+        File::create(&root.join("drop/b.txt"))?.write_all(b"c")?;
+        stage_add_file(repo_path, Path::new("top.txt"))?;
+        stage_add_file(repo_path, Path::new("keep/a.txt"))?;
+        stage_add_file(repo_path, Path::new("drop/b.txt"))?;
+        commit(repo_path, "c1")?;
+
+        // any of gits boolean spellings enables it
+        repo.config()?.set_str("core.sparseCheckout", "yes")?;
+        fs::create_dir_all(repo.path().join("info"))?;
+        fs::write(
+            repo.path().join("info/sparse-checkout"),
+            "/*\n!/*/\n/keep/\n",
+        )?;
+
+        // a staged change outside of the sparse set
+        File::create(&root.join("drop/b.txt"))?.write_all(b"f")?;
+        stage_add_file(repo_path, Path::new("drop/b.txt"))?;
+
+        // not materialized outside of the sparse set
+        fs::remove_dir_all(root.join("drop"))?;
+        File::create(&root.join("top.txt"))?.write_all(b"d")?;
+        File::create(&root.join("keep/a.txt"))?.write_all(b"e")?;
+
+        let paths =
+            get_status(repo_path, StatusType::WorkingDir, true)?
+                .into_iter()
+                .map(|i| i.path)
+                .collect::<Vec<_>>();
+
+        assert_eq!(paths, vec!["keep/a.txt", "top.txt"]);
+
+        let staged = get_status(repo_path, StatusType::Stage, true)?;
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].path, "drop/b.txt");
+        assert_eq!(staged[0].status, StatusItemType::Modified);
+
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use super::{utils::repo, CommitId};
-use crate::error::Result;
+use crate::error::{Error, Result};
+use git2::{DescribeFormatOptions, DescribeOptions, ObjectType};
 use scopetime::scope_time;
 use std::collections::BTreeMap;
 
@@ -44,11 +45,66 @@ pub fn get_tags(repo_path: &str) -> Result<Tags> {
     Ok(res)
 }
 
+/// like `git describe --tags`: nearest tag, distance and
+/// abbreviated sha (`v1.2.0-5-gabcdef0`), only the tag if `commit`
+/// is tagged and only the abbreviated sha if no tag is reachable.
+/// describes `HEAD` if `commit` is None
+pub fn describe(
+    repo_path: &str,
+    commit: Option<CommitId>,
+) -> Result<String> {
+    scope_time!("describe");
+
+    let repo = repo(repo_path)?;
+
+    let commit = match commit {
+        Some(id) => repo.find_commit(id.into())?,
+        None => repo.head()?.peel_to_commit()?,
+    };
+
+    let describe = commit.as_object().describe(
+        DescribeOptions::new()
+            .describe_tags()
+            .show_commit_oid_as_fallback(true),
+    )?;
+
+    Ok(describe.format(Some(
+        DescribeFormatOptions::new().abbreviated_size(7),
+    ))?)
+}
+
+/// commit the tag `tag_name` points to, annotated tags (even
+/// nested ones) are peeled. fails if it points to a tree or blob
+pub fn tag_target_commit(
+    repo_path: &str,
+    tag_name: &str,
+) -> Result<CommitId> {
+    scope_time!("tag_target_commit");
+
+    let repo = repo(repo_path)?;
+
+    // peeling a reference to `Any` dereferences all tag objects
+    let target = repo
+        .find_reference(&format!("refs/tags/{}", tag_name))?
+        .peel(ObjectType::Any)?;
+
+    match target.kind() {
+        Some(ObjectType::Commit) => Ok(target.id().into()),
+        _ => Err(Error::Generic(format!(
+            "tag '{}' does not point to a commit",
+            tag_name
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::sync::tests::repo_init;
-    use git2::ObjectType;
+    use crate::sync::{
+        commit, stage_add_file,
+        tests::{repo_init, repo_init_empty},
+    };
+    use std::{fs::File, io::Write, path::Path};
 
     #[test]
     fn test_smoke() {
@@ -82,4 +138,80 @@ mod tests {
             vec!["a", "b"]
         );
     }
+
+    #[test]
+    fn test_describe() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init_empty()?;
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let commit_content = |content: &str| {
+            File::create(&root.join(file_path))
+                .unwrap()
+                .write_all(content.as_bytes())
+                .unwrap();
+            stage_add_file(repo_path, file_path).unwrap();
+            commit(repo_path, content).unwrap()
+        };
+
+        let c1 = commit_content("a");
+
+        let short = |id: CommitId| id.to_string()[..7].to_string();
+
+        // no tags at all
+        assert_eq!(describe(repo_path, None)?, short(c1));
+
+        let target =
+            repo.find_object(c1.into(), Some(ObjectType::Commit))?;
+        repo.tag_lightweight("v1.0.0", &target, false)?;
+
+        assert_eq!(describe(repo_path, Some(c1))?, "v1.0.0");
+
+        commit_content("b");
+        commit_content("c");
+        let c4 = commit_content("d");
+
+        assert_eq!(
+            describe(repo_path, None)?,
+            format!("v1.0.0-3-g{}", short(c4))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_target_commit() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let sig = repo.signature()?;
+        let head = repo.head()?.peel_to_commit()?;
+
+        repo.tag_lightweight("light", head.as_object(), false)?;
+        repo.tag("annotated", head.as_object(), &sig, "msg", false)?;
+
+        let blob = repo.find_object(repo.blob(b"foo")?, None)?;
+        repo.tag("blob", &blob, &sig, "msg", false)?;
+
+        assert_eq!(
+            tag_target_commit(repo_path, "light")?,
+            head.id().into()
+        );
+        assert_eq!(
+            tag_target_commit(repo_path, "annotated")?,
+            head.id().into()
+        );
+        assert_eq!(
+            tag_target_commit(repo_path, "blob").is_err(),
+            true
+        );
+        assert_eq!(
+            tag_target_commit(repo_path, "missing").is_err(),
+            true
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,228 @@
+use super::{utils::repo, CommitId};
+use crate::error::{Error, Result};
+use git2::{ErrorCode, ObjectType};
+use scopetime::scope_time;
+use std::path::Path;
+
+/// what a `TreeEntry` points to
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TreeEntryKind {
+    /// file (or symlink)
+    Blob,
+    /// directory
+    Tree,
+    /// commit of a submodule
+    Submodule,
+}
+
+/// single child of a directory in a commits tree
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeEntry {
+    /// file name (without the path of its directory)
+    pub name: String,
+    ///
+    pub kind: TreeEntryKind,
+    /// git filemode (like `0o100644`)
+    pub mode: i32,
+    /// hex id of the object
+    pub oid: String,
+}
+
+/// immediate children of the directory `path` (empty for the root)
+/// in the tree of commit `id`, in tree order (sorted by name).
+/// fails if `path` does not exist there or is no directory
+pub fn list_tree(
+    repo_path: &str,
+    id: CommitId,
+    path: &str,
+) -> Result<Vec<TreeEntry>> {
+    scope_time!("list_tree");
+
+    let repo = repo(repo_path)?;
+
+    let root = repo.find_commit(id.into())?.tree()?;
+
+    let path = path.trim_matches('/');
+    let tree = if path.is_empty() {
+        root
+    } else {
+        let entry = match root.get_path(Path::new(path)) {
+            Ok(entry) => entry,
+            Err(e) if e.code() == ErrorCode::NotFound => {
+                return Err(Error::Generic(format!(
+                    "'{}' does not exist",
+                    path
+                )));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if entry.kind() != Some(ObjectType::Tree) {
+            return Err(Error::Generic(format!(
+                "'{}' is no directory",
+                path
+            )));
+        }
+
+        repo.find_tree(entry.id())?
+    };
+
+    let res = tree
+        .iter()
+        .filter_map(|entry| {
+            let kind = match entry.kind()? {
+                ObjectType::Blob => TreeEntryKind::Blob,
+                ObjectType::Tree => TreeEntryKind::Tree,
+                ObjectType::Commit => TreeEntryKind::Submodule,
+                _ => return None,
+            };
+
+            Some(TreeEntry {
+                name: String::from_utf8_lossy(entry.name_bytes())
+                    .into(),
+                kind,
+                mode: entry.filemode(),
+                oid: entry.id().to_string(),
+            })
+        })
+        .collect();
+
+    Ok(res)
+}
+
+/// raw content of the file at `path` in commit `id`,
+/// None if it does not exist there (or is no file)
+pub fn get_file_at_commit(
+    repo_path: &str,
+    id: CommitId,
+    path: &str,
+) -> Result<Option<Vec<u8>>> {
+    scope_time!("get_file_at_commit");
+
+    let repo = repo(repo_path)?;
+
+    let tree = repo.find_commit(id.into())?.tree()?;
+
+    let entry = match tree.get_path(Path::new(path)) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    if entry.kind() != Some(ObjectType::Blob) {
+        return Ok(None);
+    }
+
+    let blob = repo.find_blob(entry.id())?;
+
+    Ok(Some(blob.content().to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_file_at_commit, list_tree, TreeEntryKind};
+    use crate::error::Result;
+    use crate::sync::{
+        commit, stage_add_file, tests::repo_init_empty,
+    };
+    use std::{
+        fs::{self, File},
+        io::Write,
+        path::Path,
+    };
+
+    #[test]
+    fn test_file_at_commit() -> Result<()> {
+        let file_path = Path::new("sub/dir/foo.bin");
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::create_dir_all(root.join("sub/dir"))?;
+
+        File::create(&root.join(file_path))?
+            .write_all(b"a\x00\xff")?;
+        stage_add_file(repo_path, file_path)?;
+        let c1 = commit(repo_path, "c1")?;
+
+        File::create(&root.join(file_path))?.write_all(b"b")?;
+        stage_add_file(repo_path, file_path)?;
+        let c2 = commit(repo_path, "c2")?;
+
+        assert_eq!(
+            get_file_at_commit(repo_path, c1, "sub/dir/foo.bin")?,
+            Some(b"a\x00\xff".to_vec())
+        );
+        assert_eq!(
+            get_file_at_commit(repo_path, c2, "sub/dir/foo.bin")?,
+            Some(b"b".to_vec())
+        );
+        assert_eq!(
+            get_file_at_commit(repo_path, c2, "sub/dir/bar")?,
+            None
+        );
+        assert_eq!(get_file_at_commit(repo_path, c2, "sub")?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_tree() -> Result<()> {
+        let (_td, repo) = repo_init_empty().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::create_dir_all(root.join("sub/dir"))?;
+        fs::write(root.join("top.txt"), "a")?;
+        fs::write(root.join("sub/b.txt"), "b")?;
+        fs::write(root.join("sub/dir/c.txt"), "c")?;
+
+        stage_add_file(repo_path, Path::new("top.txt"))?;
+        stage_add_file(repo_path, Path::new("sub/b.txt"))?;
+        stage_add_file(repo_path, Path::new("sub/dir/c.txt"))?;
+        let c1 = commit(repo_path, "c1")?;
+
+        let names = |path: &str| -> Result<Vec<_>> {
+            Ok(list_tree(repo_path, c1, path)?
+                .into_iter()
+                .map(|e| (e.name, e.kind))
+                .collect())
+        };
+
+        assert_eq!(
+            names("")?,
+            vec![
+                ("sub".to_string(), TreeEntryKind::Tree),
+                ("top.txt".to_string(), TreeEntryKind::Blob),
+            ]
+        );
+        assert_eq!(
+            names("sub/")?,
+            vec![
+                ("b.txt".to_string(), TreeEntryKind::Blob),
+                ("dir".to_string(), TreeEntryKind::Tree),
+            ]
+        );
+
+        let entries = list_tree(repo_path, c1, "sub/dir")?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].mode, 0o100_644);
+        assert_eq!(
+            entries[0].oid,
+            repo.revparse_single("HEAD:sub/dir/c.txt")?
+                .id()
+                .to_string()
+        );
+
+        assert_eq!(
+            list_tree(repo_path, c1, "top.txt").is_err(),
+            true
+        );
+        assert_eq!(
+            list_tree(repo_path, c1, "missing").is_err(),
+            true
+        );
+
+        Ok(())
+    }
+}
@@ -2,9 +2,89 @@
 
 use super::CommitId;
 use crate::error::{Error, Result};
-use git2::{IndexAddOption, Repository, RepositoryOpenFlags};
+use git2::{
+    ErrorCode, IndexAddOption, IndexEntry, IndexEntryExtendedFlag,
+    IndexEntryFlag, IndexTime, ObjectType, Oid, Repository,
+    RepositoryOpenFlags,
+};
 use scopetime::scope_time;
-use std::path::Path;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    marker::PhantomData,
+    ops::Deref,
+    path::Path,
+    rc::Rc,
+};
+
+thread_local! {
+    static REPO_CACHE_DEPTH: Cell<usize> = Cell::new(0);
+    static REPO_CACHE: RefCell<HashMap<String, Rc<Repository>>> =
+        RefCell::new(HashMap::new());
+}
+
+#[cfg(test)]
+thread_local! {
+    static REPO_OPENS: Cell<usize> = Cell::new(0);
+}
+
+/// while alive, sync calls on the current thread reuse the
+/// repositories they open instead of reopening them on every call.
+///
+/// `Repository` is not `Send` so the cache is thread local and this
+/// guard can not leave the thread it was created on. the cached
+/// handles are dropped together with the (outermost) guard, so keep
+/// it around a batch of calls only (like a single refresh).
+pub struct RepoCache {
+    _not_send: PhantomData<Rc<()>>,
+}
+
+impl RepoCache {
+    ///
+    pub fn new() -> Self {
+        REPO_CACHE_DEPTH.with(|d| d.set(d.get() + 1));
+        Self {
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Default for RepoCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for RepoCache {
+    fn drop(&mut self) {
+        let depth = REPO_CACHE_DEPTH.with(|d| {
+            d.set(d.get().saturating_sub(1));
+            d.get()
+        });
+
+        if depth == 0 {
+            REPO_CACHE.with(|c| c.borrow_mut().clear());
+        }
+    }
+}
+
+/// repository handle either opened for a single call or shared
+/// through the `RepoCache`
+pub(crate) enum RepoRef {
+    Owned(Repository),
+    Cached(Rc<Repository>),
+}
+
+impl Deref for RepoRef {
+    type Target = Repository;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Owned(repo) => repo,
+            Self::Cached(repo) => repo,
+        }
+    }
+}
 
 ///
 pub fn is_repo(repo_path: &str) -> bool {
@@ -27,8 +107,40 @@ pub fn is_bare_repo(repo_path: &str) -> Result<bool> {
     Ok(repo.is_bare())
 }
 
-///
-pub(crate) fn repo(repo_path: &str) -> Result<Repository> {
+/// opens the repo at `repo_path`, reusing a cached handle if a
+/// `RepoCache` is alive on this thread
+pub(crate) fn repo(repo_path: &str) -> Result<RepoRef> {
+    if REPO_CACHE_DEPTH.with(Cell::get) == 0 {
+        return Ok(RepoRef::Owned(open_repo(repo_path)?));
+    }
+
+    if let Some(repo) =
+        REPO_CACHE.with(|c| c.borrow().get(repo_path).cloned())
+    {
+        return Ok(RepoRef::Cached(repo));
+    }
+
+    let repo = Rc::new(open_repo(repo_path)?);
+
+    REPO_CACHE.with(|c| {
+        c.borrow_mut()
+            .insert(repo_path.to_string(), Rc::clone(&repo))
+    });
+
+    Ok(RepoRef::Cached(repo))
+}
+
+/// number of repos opened on this thread so far
+#[cfg(test)]
+pub(crate) fn repo_opens() -> usize {
+    REPO_OPENS.with(Cell::get)
+}
+
+/// always opens the repo at `repo_path` (needed for exclusive access)
+pub(crate) fn open_repo(repo_path: &str) -> Result<Repository> {
+    #[cfg(test)]
+    REPO_OPENS.with(|o| o.set(o.get() + 1));
+
     let repo = Repository::open_ext(
         repo_path,
         RepositoryOpenFlags::empty(),
@@ -42,15 +154,18 @@ pub(crate) fn repo(repo_path: &str) -> Result<Repository> {
     Ok(repo)
 }
 
-///
-pub(crate) fn work_dir(repo: &Repository) -> &Path {
-    repo.workdir().expect("unable to query workdir")
+/// the root of the working tree (which might also be the root of
+/// the filesystem `/`)
+pub(crate) fn work_dir(repo: &Repository) -> Result<&Path> {
+    repo.workdir().ok_or_else(|| {
+        Error::Generic("unable to query workdir".into())
+    })
 }
 
 ///
 pub fn repo_work_dir(repo_path: &str) -> Result<String> {
     let repo = repo(repo_path)?;
-    if let Some(workdir) = work_dir(&repo).to_str() {
+    if let Some(workdir) = work_dir(&repo)?.to_str() {
         Ok(workdir.to_string())
     } else {
         Err(Error::Generic("invalid workdir".to_string()))
@@ -76,6 +191,99 @@ pub fn get_head_repo(repo: &Repository) -> Result<CommitId> {
     }
 }
 
+/// oid of the tree the index would be committed as (stat data
+/// is not part of it so refreshing the index does not change it).
+/// a conflicted index can not be written as a tree, then its
+/// entries (path, blob id, mode and stage) are hashed instead.
+/// together with `get_head` this tells whether anything got
+/// staged or committed
+pub fn index_oid(repo_path: &str) -> Result<Oid> {
+    scope_time!("index_oid");
+
+    let repo = repo(repo_path)?;
+    let mut index = repo.index()?;
+
+    if !index.has_conflicts() {
+        return Ok(index.write_tree()?);
+    }
+
+    let mut entries = Vec::new();
+    for e in index.iter() {
+        entries.extend_from_slice(&e.path);
+        entries.push(0);
+        entries.extend_from_slice(e.id.as_bytes());
+        entries.extend_from_slice(&e.mode.to_be_bytes());
+        entries.extend_from_slice(&e.flags.to_be_bytes());
+    }
+
+    Ok(Oid::hash_object(ObjectType::Blob, &entries)?)
+}
+
+/// resolves anything `git rev-parse` understands (`HEAD~3`,
+/// `main@{yesterday}`, tags, abbreviated shas..) to a commit
+pub fn resolve_revspec(
+    repo_path: &str,
+    spec: &str,
+) -> Result<CommitId> {
+    scope_time!("resolve_revspec");
+
+    let repo = repo(repo_path)?;
+
+    let object = match repo.revparse_single(spec) {
+        Ok(object) => object,
+        Err(e) if e.code() == ErrorCode::Ambiguous => {
+            return Err(Error::Generic(format!(
+                "ambiguous revision '{}'",
+                spec
+            )));
+        }
+        Err(e)
+            if e.code() == ErrorCode::NotFound
+                || e.code() == ErrorCode::InvalidSpec =>
+        {
+            return Err(Error::Generic(format!(
+                "unknown revision '{}'",
+                spec
+            )));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let id = object.peel_to_commit()?.id();
+
+    Ok(id.into())
+}
+
+/// true if `maybe_ancestor` is reachable from `descendant`
+/// (like `git merge-base --is-ancestor`, so a commit is its own ancestor)
+pub fn is_ancestor(
+    repo_path: &str,
+    maybe_ancestor: CommitId,
+    descendant: CommitId,
+) -> Result<bool> {
+    scope_time!("is_ancestor");
+
+    let repo = repo(repo_path)?;
+
+    is_ancestor_repo(&repo, maybe_ancestor, descendant)
+}
+
+///
+pub(crate) fn is_ancestor_repo(
+    repo: &Repository,
+    maybe_ancestor: CommitId,
+    descendant: CommitId,
+) -> Result<bool> {
+    if maybe_ancestor == descendant {
+        return Ok(true);
+    }
+
+    Ok(repo.graph_descendant_of(
+        descendant.into(),
+        maybe_ancestor.into(),
+    )?)
+}
+
 /// add a file diff from workingdir to stage (will not add removed files see `stage_addremoved`)
 pub fn stage_add_file(repo_path: &str, path: &Path) -> Result<()> {
     scope_time!("stage_add_file");
@@ -118,12 +326,176 @@ pub fn stage_addremoved(repo_path: &str, path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// records `path` in the index without its content like
+/// `git add -N`, so a new file shows up in diffs against the index
+pub fn intent_to_add(repo_path: &str, path: &Path) -> Result<()> {
+    scope_time!("intent_to_add");
+
+    let repo = repo(repo_path)?;
+
+    let meta = work_dir(&repo)?.join(path).symlink_metadata()?;
+
+    let mode = if meta.file_type().is_symlink() {
+        0o120_000
+    } else if is_executable(&meta) {
+        0o100_755
+    } else {
+        0o100_644
+    };
+
+    let path = path
+        .to_str()
+        .ok_or_else(|| Error::Generic("invalid path".to_string()))?;
+
+    let entry = IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode,
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id: repo.blob(&[])?,
+        flags: IndexEntryFlag::EXTENDED.bits(),
+        flags_extended: IndexEntryExtendedFlag::INTENT_TO_ADD.bits(),
+        path: path.replace('\\', "/").into_bytes(),
+    };
+
+    let mut index = repo.index()?;
+
+    index.add(&entry)?;
+    index.write()?;
+
+    Ok(())
+}
+
+/// per file bits of an index entry that hide changes from status
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum IndexFlag {
+    /// `git update-index --assume-unchanged`
+    AssumeUnchanged,
+    /// `git update-index --skip-worktree`
+    SkipWorktree,
+}
+
+/// sets or clears `flag` on the index entry of the tracked `path`
+pub fn set_index_flag(
+    repo_path: &str,
+    path: &Path,
+    flag: IndexFlag,
+    value: bool,
+) -> Result<()> {
+    scope_time!("set_index_flag");
+
+    let repo = repo(repo_path)?;
+
+    let mut index = repo.index()?;
+
+    let mut entry = index.get_path(path, 0).ok_or_else(|| {
+        Error::Generic(format!("'{}' is not tracked", path.display()))
+    })?;
+
+    let mut flags = IndexEntryFlag::from_bits_truncate(entry.flags);
+    let mut flags_extended =
+        IndexEntryExtendedFlag::from_bits_truncate(
+            entry.flags_extended,
+        );
+
+    match flag {
+        IndexFlag::AssumeUnchanged => {
+            flags.set(IndexEntryFlag::VALID, value);
+        }
+        IndexFlag::SkipWorktree => {
+            flags_extended
+                .set(IndexEntryExtendedFlag::SKIP_WORKTREE, value);
+        }
+    }
+
+    // extended flags are only stored if the entry says so
+    flags.set(IndexEntryFlag::EXTENDED, !flags_extended.is_empty());
+
+    // the other bits hold the stage and the path length
+    entry.flags =
+        flags.bits() | (entry.flags & !IndexEntryFlag::all().bits());
+    entry.flags_extended = flags_extended.bits();
+
+    index.add(&entry)?;
+    index.write()?;
+
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn is_executable(meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(windows)]
+fn is_executable(_: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// stages all `paths` at once, paths missing in the workdir are
+/// staged as deletions
+pub fn stage_items(repo_path: &str, paths: &[String]) -> Result<()> {
+    scope_time!("stage_items");
+
+    let repo = repo(repo_path)?;
+    let work_dir = work_dir(&repo)?;
+
+    let mut index = repo.index()?;
+
+    for path in paths {
+        let path = Path::new(path);
+
+        if work_dir.join(path).symlink_metadata().is_ok() {
+            index.add_path(path)?;
+        } else {
+            index.remove_path(path)?;
+        }
+    }
+
+    index.write()?;
+
+    Ok(())
+}
+
+/// stages everything below `dir_path` like `git add <dir>`:
+/// untracked files (unless ignored), changes and deletions
+pub fn stage_directory(
+    repo_path: &str,
+    dir_path: &Path,
+) -> Result<()> {
+    scope_time!("stage_directory");
+
+    let repo = repo(repo_path)?;
+
+    let dir = dir_path
+        .to_str()
+        .ok_or_else(|| Error::Generic("invalid path".to_string()))?
+        .replace('\\', "/");
+    let pathspec = vec![dir.trim_end_matches('/')];
+
+    let mut index = repo.index()?;
+
+    index.add_all(&pathspec, IndexAddOption::DEFAULT, None)?;
+    index.update_all(&pathspec, None)?;
+    index.write()?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::sync::{
         commit,
-        status::{get_status, StatusType},
+        diff::get_diff,
+        status::{
+            get_status, StatusItem, StatusItemType, StatusType,
+        },
         tests::{
             debug_cmd_print, get_statuses, repo_init, repo_init_empty,
         },
@@ -284,4 +656,361 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_repo_cache() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+
+        let batch = || {
+            get_head(repo_path).unwrap();
+            get_status(repo_path, StatusType::WorkingDir, true)
+                .unwrap();
+            stage_add_file(repo_path, file_path).unwrap();
+            get_status(repo_path, StatusType::Stage, true).unwrap()
+        };
+
+        let opens = repo_opens;
+
+        let before = opens();
+        let uncached = batch();
+        let uncached_opens = opens() - before;
+
+        remove_file(&root.join(file_path))?;
+        File::create(&root.join(file_path))?.write_all(b"b")?;
+
+        let before = opens();
+        let cached = {
+            let _cache = RepoCache::new();
+            {
+                // nested guards share the cache
+                let _inner = RepoCache::new();
+                get_head(repo_path).unwrap();
+            }
+            batch()
+        };
+        let cached_opens = opens() - before;
+
+        assert_eq!(uncached_opens, 4);
+        assert_eq!(cached_opens, 1);
+        assert_eq!(uncached.len(), cached.len());
+        assert_eq!(REPO_CACHE.with(|c| c.borrow().is_empty()), true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_ancestor() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let parent = get_head(repo_path)?;
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path)?;
+        let child = commit(repo_path, "child")?;
+
+        // a root commit without any history in common
+        let sig = repo.signature()?;
+        let tree = repo.head()?.peel_to_tree()?;
+        let unrelated: CommitId = repo
+            .commit(None, &sig, &sig, "unrelated", &tree, &[])?
+            .into();
+
+        assert_eq!(is_ancestor(repo_path, parent, child)?, true);
+        assert_eq!(is_ancestor(repo_path, child, parent)?, false);
+        assert_eq!(is_ancestor(repo_path, child, child)?, true);
+        assert_eq!(is_ancestor(repo_path, unrelated, child)?, false);
+        assert_eq!(is_ancestor(repo_path, child, unrelated)?, false);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_revspec() -> Result<()> {
+        let file_path = Path::new("foo");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let c1 = get_head(repo_path)?;
+
+        let sig = repo.signature()?;
+        repo.tag(
+            "v1",
+            &repo.find_object(c1.into(), None)?,
+            &sig,
+            "",
+            false,
+        )?;
+
+        File::create(&root.join(file_path))?.write_all(b"a")?;
+        stage_add_file(repo_path, file_path)?;
+        let c2 = commit(repo_path, "c2")?;
+
+        assert_eq!(resolve_revspec(repo_path, "HEAD")?, c2);
+        assert_eq!(resolve_revspec(repo_path, "HEAD~1")?, c1);
+        assert_eq!(resolve_revspec(repo_path, "v1")?, c1);
+        assert_eq!(
+            resolve_revspec(repo_path, &c2.to_string()[..8])?,
+            c2
+        );
+
+        assert_eq!(
+            resolve_revspec(repo_path, "no/such:rev~~").is_err(),
+            true
+        );
+        assert_eq!(
+            resolve_revspec(repo_path, "HEAD~5").is_err(),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_items() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join("modified.txt"))?.write_all(b"a")?;
+        File::create(&root.join("deleted.txt"))?.write_all(b"b")?;
+        stage_add_all(repo_path, "*")?;
+        commit(repo_path, "c1")?;
+
+        File::create(&root.join("modified.txt"))?.write_all(b"c")?;
+        fs::remove_file(root.join("deleted.txt"))?;
+        File::create(&root.join("new.txt"))?.write_all(b"d")?;
+
+        let paths = vec![
+            String::from("new.txt"),
+            String::from("modified.txt"),
+            String::from("deleted.txt"),
+        ];
+
+        assert_eq!(get_statuses(repo_path), (3, 0));
+
+        stage_items(repo_path, &paths)?;
+
+        assert_eq!(get_statuses(repo_path), (0, 3));
+
+        let staged = get_status(repo_path, StatusType::Stage, true)?;
+        assert_eq!(
+            staged
+                .iter()
+                .find(|item| item.path == "deleted.txt")
+                .map(|item| item.status),
+            Some(StatusItemType::Deleted)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_intent_to_add() -> Result<()> {
+        let file_path = Path::new("foo.txt");
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join(file_path))?
+            .write_all(b"line1\nline2\n")?;
+
+        assert_eq!(get_statuses(repo_path), (1, 0));
+
+        intent_to_add(repo_path, file_path)?;
+
+        debug_cmd_print(repo_path, "git status");
+
+        let staged = get_status(repo_path, StatusType::Stage, true)?;
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].path, "foo.txt");
+
+        // the content is not staged but shows up as a modification
+        let diff = get_diff(repo_path, "foo.txt".to_string(), true)?;
+        assert_eq!(diff.lines, 0);
+        let diff = get_diff(repo_path, "foo.txt".to_string(), false)?;
+        assert_eq!(diff.untracked, false);
+        assert_eq!(diff.lines, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_directory() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        fs::create_dir_all(root.join("dir/sub"))?;
+        File::create(&root.join("dir/old.txt"))?
+            .write_all(b"old content")?;
+        stage_add_file(repo_path, Path::new("dir/old.txt"))?;
+        commit(repo_path, "c1")?;
+
+        remove_file(root.join("dir/old.txt"))?;
+        File::create(&root.join(".gitignore"))?
+            .write_all(b"*.log\n")?;
+        File::create(&root.join("dir/a.txt"))?.write_all(b"a")?;
+        File::create(&root.join("dir/sub/b.txt"))?.write_all(b"b")?;
+        File::create(&root.join("dir/ignored.log"))?
+            .write_all(b"c")?;
+        File::create(&root.join("outside.txt"))?.write_all(b"d")?;
+
+        stage_directory(repo_path, Path::new("dir"))?;
+
+        let mut staged =
+            get_status(repo_path, StatusType::Stage, true)?
+                .into_iter()
+                .map(|item| (item.path, item.status))
+                .collect::<Vec<_>>();
+        staged.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            staged,
+            vec![
+                ("dir/a.txt".to_string(), StatusItemType::New),
+                ("dir/old.txt".to_string(), StatusItemType::Deleted),
+                ("dir/sub/b.txt".to_string(), StatusItemType::New),
+            ]
+        );
+        assert_eq!(get_statuses(repo_path), (2, 3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_index_flag() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        File::create(&root.join("vendored.txt"))?.write_all(b"a")?;
+        File::create(&root.join("other.txt"))?.write_all(b"b")?;
+        stage_add_file(repo_path, Path::new("vendored.txt"))?;
+        stage_add_file(repo_path, Path::new("other.txt"))?;
+        commit(repo_path, "c1")?;
+
+        for flag in
+            &[IndexFlag::AssumeUnchanged, IndexFlag::SkipWorktree]
+        {
+            set_index_flag(
+                repo_path,
+                Path::new("vendored.txt"),
+                *flag,
+                true,
+            )?;
+
+            File::create(&root.join("vendored.txt"))?
+                .write_all(b"changed")?;
+            File::create(&root.join("other.txt"))?
+                .write_all(b"changed")?;
+
+            let paths = |items: Vec<StatusItem>| {
+                items.into_iter().map(|i| i.path).collect::<Vec<_>>()
+            };
+
+            assert_eq!(
+                paths(get_status(
+                    repo_path,
+                    StatusType::WorkingDir,
+                    true
+                )?),
+                vec!["other.txt"]
+            );
+
+            set_index_flag(
+                repo_path,
+                Path::new("vendored.txt"),
+                *flag,
+                false,
+            )?;
+
+            assert_eq!(
+                paths(get_status(
+                    repo_path,
+                    StatusType::WorkingDir,
+                    true
+                )?),
+                vec!["other.txt", "vendored.txt"]
+            );
+
+            File::create(&root.join("vendored.txt"))?
+                .write_all(b"a")?;
+            File::create(&root.join("other.txt"))?.write_all(b"b")?;
+        }
+
+        assert_eq!(
+            set_index_flag(
+                repo_path,
+                Path::new("untracked.txt"),
+                IndexFlag::AssumeUnchanged,
+                true
+            )
+            .is_err(),
+            true
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_oid() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let initial = index_oid(repo_path)?;
+        assert_eq!(initial, repo.head()?.peel_to_tree()?.id());
+
+        fs::write(root.join("foo.txt"), "a")?;
+        get_status(repo_path, StatusType::WorkingDir, true)?;
+        get_status(repo_path, StatusType::Stage, true)?;
+
+        assert_eq!(index_oid(repo_path)?, initial);
+
+        stage_add_file(repo_path, Path::new("foo.txt"))?;
+        let staged = index_oid(repo_path)?;
+        assert_ne!(staged, initial);
+
+        // same content again is no change
+        stage_add_file(repo_path, Path::new("foo.txt"))?;
+        assert_eq!(index_oid(repo_path)?, staged);
+
+        fs::write(root.join("foo.txt"), "b")?;
+        stage_add_file(repo_path, Path::new("foo.txt"))?;
+        let changed = index_oid(repo_path)?;
+        assert_ne!(changed, staged);
+
+        // turn foo into a conflict (ours and theirs)
+        let mut index = repo.index()?;
+        index.read(true)?;
+        let path = Path::new("foo.txt");
+        let conflicts = (2..=3_u16)
+            .map(|stage| {
+                let mut entry = index.get_path(path, 0).unwrap();
+                entry.flags |= stage << 12;
+                entry
+            })
+            .collect::<Vec<_>>();
+        index.remove_path(path)?;
+        for entry in &conflicts {
+            index.add(entry)?;
+        }
+        index.write()?;
+        assert_eq!(index.has_conflicts(), true);
+
+        let conflicted = index_oid(repo_path)?;
+        assert_ne!(conflicted, changed);
+        assert_eq!(index_oid(repo_path)?, conflicted);
+
+        Ok(())
+    }
 }
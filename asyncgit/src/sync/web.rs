@@ -0,0 +1,134 @@
+//! links to commits on hosting providers
+
+use super::{branch::get_upstream_url_repo, utils::repo, CommitId};
+use crate::error::Result;
+use scopetime::scope_time;
+
+/// remote used if the current branch has no upstream
+const DEFAULT_REMOTE: &str = "origin";
+
+/// https link to `commit` on github, gitlab or bitbucket based on the
+/// url of the upstream remote (`origin` if there is none),
+/// None for unknown hosts
+pub fn commit_web_url(
+    repo_path: &str,
+    commit: CommitId,
+) -> Result<Option<String>> {
+    scope_time!("commit_web_url");
+
+    let repo = repo(repo_path)?;
+
+    let head = repo.head().ok();
+    let branch = head
+        .as_ref()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand());
+
+    let url = match branch {
+        Some(branch) => get_upstream_url_repo(&repo, branch)?,
+        None => None,
+    };
+
+    let url = match url {
+        Some(url) => url,
+        None => match repo.find_remote(DEFAULT_REMOTE) {
+            Ok(remote) => match remote.url() {
+                Some(url) => url.to_string(),
+                None => return Ok(None),
+            },
+            Err(_) => return Ok(None),
+        },
+    };
+
+    Ok(web_url(&url, &commit.to_string()))
+}
+
+fn web_url(remote_url: &str, sha: &str) -> Option<String> {
+    let (host, path) = split_remote_url(remote_url)?;
+
+    let base = format!("https://{}/{}", host, path);
+
+    if host.contains("github") {
+        Some(format!("{}/commit/{}", base, sha))
+    } else if host.contains("gitlab") {
+        Some(format!("{}/-/commit/{}", base, sha))
+    } else if host.contains("bitbucket") {
+        Some(format!("{}/commits/{}", base, sha))
+    } else {
+        None
+    }
+}
+
+/// host and repo path of ssh (`git@host:org/repo.git`,
+/// `ssh://git@host:22/org/repo.git`) and http(s) urls
+fn split_remote_url(url: &str) -> Option<(String, String)> {
+    let (authority, path) = if let Some(pos) = url.find("://") {
+        let rest = &url[pos + 3..];
+        let slash = rest.find('/')?;
+        (&rest[..slash], &rest[slash + 1..])
+    } else {
+        // scp like syntax
+        let colon = url.find(':')?;
+        (&url[..colon], &url[colon + 1..])
+    };
+
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    if host.is_empty() || path.is_empty() {
+        return None;
+    }
+
+    Some((host.to_lowercase(), path.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{commit_web_url, web_url};
+    use crate::error::Result;
+    use crate::sync::{tests::repo_init, utils::get_head_repo};
+
+    #[test]
+    fn test_github_ssh() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let head = get_head_repo(&repo)?;
+
+        assert_eq!(commit_web_url(repo_path, head)?, None);
+
+        repo.remote("origin", "git@github.com:org/repo.git")?;
+
+        assert_eq!(
+            commit_web_url(repo_path, head)?,
+            Some(format!(
+                "https://github.com/org/repo/commit/{}",
+                head.to_string()
+            ))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gitlab_https() {
+        assert_eq!(
+            web_url("https://gitlab.com/group/sub/repo.git", "abc")
+                .as_deref(),
+            Some("https://gitlab.com/group/sub/repo/-/commit/abc")
+        );
+        assert_eq!(
+            web_url("ssh://git@bitbucket.org:22/org/repo", "abc")
+                .as_deref(),
+            Some("https://bitbucket.org/org/repo/commits/abc")
+        );
+        assert_eq!(
+            web_url("https://user@example.com/org/repo.git", "abc"),
+            None
+        );
+    }
+}
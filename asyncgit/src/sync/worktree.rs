@@ -0,0 +1,287 @@
+//! linked worktrees (git2 does not expose the worktree api yet,
+//! so this reads/writes the layout used by `git worktree` directly)
+
+use super::utils::repo;
+use crate::error::{Error, Result};
+use git2::{build::CheckoutBuilder, BranchType, Repository};
+use scopetime::scope_time;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const WORKTREES_DIR: &str = "worktrees";
+
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorktreeInfo {
+    ///
+    pub name: String,
+    /// root of the worktree checkout
+    pub path: String,
+    /// branch checked out, None if `HEAD` is detached
+    pub branch: Option<String>,
+    ///
+    pub locked: bool,
+}
+
+/// lists the linked worktrees (not including the main one)
+pub fn get_worktrees(repo_path: &str) -> Result<Vec<WorktreeInfo>> {
+    scope_time!("get_worktrees");
+
+    let repo = repo(repo_path)?;
+
+    let dir = common_dir(&repo)?.join(WORKTREES_DIR);
+
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut res = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        res.push(worktree_info(&entry.path())?);
+    }
+
+    res.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(res)
+}
+
+/// creates a new worktree `name` at `path` checking out the
+/// existing local `branch`, which must not be checked out elsewhere
+pub fn add_worktree(
+    repo_path: &str,
+    name: &str,
+    path: &str,
+    branch: &str,
+) -> Result<()> {
+    scope_time!("add_worktree");
+
+    let repo = repo(repo_path)?;
+
+    let refname = repo
+        .find_branch(branch, BranchType::Local)?
+        .get()
+        .name()
+        .map(String::from)
+        .ok_or_else(|| {
+            Error::Generic("invalid branch name".into())
+        })?;
+
+    if let Some(location) = branch_checked_out_at(&repo, &refname)? {
+        return Err(Error::Generic(format!(
+            "branch '{}' is already checked out at '{}'",
+            branch, location
+        )));
+    }
+
+    let meta_dir = common_dir(&repo)?.join(WORKTREES_DIR).join(name);
+    if meta_dir.exists() {
+        return Err(Error::Generic(format!(
+            "worktree '{}' already exists",
+            name
+        )));
+    }
+
+    let path = Path::new(path);
+    if path.exists() && fs::read_dir(path)?.next().is_some() {
+        return Err(Error::Generic(format!(
+            "'{}' already exists",
+            path.display()
+        )));
+    }
+
+    fs::create_dir_all(path)?;
+    let path = path.canonicalize()?;
+
+    fs::create_dir_all(&meta_dir)?;
+    fs::write(meta_dir.join("HEAD"), format!("ref: {}\n", refname))?;
+    fs::write(meta_dir.join("commondir"), "../..\n")?;
+    fs::write(
+        meta_dir.join("gitdir"),
+        format!("{}\n", path.join(".git").display()),
+    )?;
+    fs::write(
+        path.join(".git"),
+        format!("gitdir: {}\n", meta_dir.display()),
+    )?;
+
+    let worktree = Repository::open(&path)?;
+    worktree.checkout_head(Some(CheckoutBuilder::new().force()))?;
+
+    Ok(())
+}
+
+/// removes the administrative data of worktree `name` if its
+/// checkout does not exist anymore (and it is not locked)
+pub fn prune_worktree(repo_path: &str, name: &str) -> Result<()> {
+    scope_time!("prune_worktree");
+
+    let repo = repo(repo_path)?;
+
+    let meta_dir = common_dir(&repo)?.join(WORKTREES_DIR).join(name);
+    if !meta_dir.is_dir() {
+        return Err(Error::Generic(format!(
+            "worktree '{}' not found",
+            name
+        )));
+    }
+
+    let info = worktree_info(&meta_dir)?;
+
+    if info.locked {
+        return Err(Error::Generic(format!(
+            "worktree '{}' is locked",
+            name
+        )));
+    }
+
+    if Path::new(&info.path).exists() {
+        return Err(Error::Generic(format!(
+            "worktree '{}' is not stale",
+            name
+        )));
+    }
+
+    fs::remove_dir_all(meta_dir)?;
+
+    Ok(())
+}
+
+/// the `.git` dir shared by all worktrees
+fn common_dir(repo: &Repository) -> Result<PathBuf> {
+    let commondir = repo.path().join("commondir");
+
+    if commondir.exists() {
+        let rel = fs::read_to_string(commondir)?;
+        Ok(repo.path().join(rel.trim()).canonicalize()?)
+    } else {
+        Ok(repo.path().to_path_buf())
+    }
+}
+
+/// reads the `HEAD` of a git dir returning the symbolic ref if any
+fn head_ref(git_dir: &Path) -> Option<String> {
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    head.trim().strip_prefix("ref: ").map(String::from)
+}
+
+fn worktree_info(meta_dir: &Path) -> Result<WorktreeInfo> {
+    let gitdir = fs::read_to_string(meta_dir.join("gitdir"))?;
+    let gitdir = Path::new(gitdir.trim());
+    let path = gitdir.parent().unwrap_or(gitdir);
+
+    Ok(WorktreeInfo {
+        name: meta_dir
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        branch: head_ref(meta_dir)
+            .map(|r| r.trim_start_matches("refs/heads/").to_string()),
+        locked: meta_dir.join("locked").exists(),
+    })
+}
+
+/// returns the location `refname` is checked out at (if any)
+fn branch_checked_out_at(
+    repo: &Repository,
+    refname: &str,
+) -> Result<Option<String>> {
+    let common = common_dir(repo)?;
+
+    if head_ref(&common).as_deref() == Some(refname) {
+        let main = common.parent().unwrap_or(&common);
+        return Ok(Some(main.to_string_lossy().to_string()));
+    }
+
+    let dir = common.join(WORKTREES_DIR);
+    if dir.exists() {
+        for entry in fs::read_dir(dir)? {
+            let meta_dir = entry?.path();
+            if head_ref(&meta_dir).as_deref() == Some(refname) {
+                return Ok(Some(worktree_info(&meta_dir)?.path));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_worktree, get_worktrees, prune_worktree};
+    use crate::error::Result;
+    use crate::sync::tests::repo_init;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_worktrees() -> Result<()> {
+        let (_td, repo) = repo_init().unwrap();
+        let root = repo.path().parent().unwrap();
+        let repo_path = root.as_os_str().to_str().unwrap();
+
+        let head = repo.head()?.peel_to_commit()?;
+        repo.branch("feature", &head, false)?;
+
+        assert_eq!(get_worktrees(repo_path)?.is_empty(), true);
+
+        let wt_dir = TempDir::new()?;
+        let wt_path = wt_dir.path().join("wt");
+        let wt_path_str = wt_path.to_str().unwrap();
+
+        assert_eq!(
+            add_worktree(repo_path, "wt", wt_path_str, "master")
+                .is_err(),
+            true
+        );
+
+        add_worktree(repo_path, "wt", wt_path_str, "feature")?;
+
+        let list = get_worktrees(repo_path)?;
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].name, "wt");
+        assert_eq!(
+            list[0].path,
+            wt_path.canonicalize()?.to_string_lossy()
+        );
+        assert_eq!(list[0].branch.as_deref(), Some("feature"));
+        assert_eq!(list[0].locked, false);
+
+        let wt_repo = git2::Repository::open(&wt_path)?;
+        assert_eq!(wt_repo.is_worktree(), true);
+        assert_eq!(wt_repo.head()?.shorthand(), Some("feature"));
+
+        // already checked out in the new worktree
+        let other = wt_dir.path().join("other");
+        assert_eq!(
+            add_worktree(
+                repo_path,
+                "other",
+                other.to_str().unwrap(),
+                "feature"
+            )
+            .is_err(),
+            true
+        );
+
+        // still exists, so not stale
+        assert_eq!(prune_worktree(repo_path, "wt").is_err(), true);
+
+        fs::remove_dir_all(&wt_path)?;
+
+        prune_worktree(repo_path, "wt")?;
+
+        assert_eq!(get_worktrees(repo_path)?.is_empty(), true);
+
+        Ok(())
+    }
+}
@@ -4,11 +4,13 @@ use crate::{
     components::{
         event_pump, CommandBlocking, CommandInfo, CommitComponent,
         Component, DrawableComponent, ExternalEditorComponent,
-        HelpComponent, InspectCommitComponent, MsgComponent,
-        ResetComponent, StashMsgComponent, TagCommitComponent,
+        GcComponent, HelpComponent, InspectCommitComponent,
+        MsgComponent, ResetComponent, StashMsgComponent,
+        TagCommitComponent,
     },
     input::{Input, InputEvent, InputState},
     keys,
+    options::Options,
     queue::{Action, InternalEvent, NeedsUpdate, Queue},
     strings::{self, commands, order},
     tabs::{Revlog, StashList, Stashing, Status},
@@ -41,6 +43,7 @@ pub struct App {
     inspect_commit_popup: InspectCommitComponent,
     external_editor_popup: ExternalEditorComponent,
     tag_commit_popup: TagCommitComponent,
+    gc_popup: GcComponent,
     cmdbar: RefCell<CommandBar>,
     tab: usize,
     revlog: Revlog,
@@ -66,13 +69,17 @@ impl App {
         let queue = Queue::default();
 
         let theme = Rc::new(Theme::init());
+        let options = Rc::new(Options::init());
 
         Self {
             input,
             reset: ResetComponent::new(queue.clone(), theme.clone()),
             commit: CommitComponent::new(
+                CWD,
                 queue.clone(),
+                sender,
                 theme.clone(),
+                options.clone(),
             ),
             stashmsg_popup: StashMsgComponent::new(
                 queue.clone(),
@@ -90,13 +97,19 @@ impl App {
                 queue.clone(),
                 theme.clone(),
             ),
+            gc_popup: GcComponent::new(sender, theme.clone()),
             do_quit: false,
             cmdbar: RefCell::new(CommandBar::new(theme.clone())),
             help: HelpComponent::new(theme.clone()),
             msg: MsgComponent::new(theme.clone()),
             tab: 0,
             revlog: Revlog::new(&queue, sender, theme.clone()),
-            status_tab: Status::new(&queue, sender, theme.clone()),
+            status_tab: Status::new(
+                &queue,
+                sender,
+                theme.clone(),
+                &options,
+            ),
             stashing_tab: Stashing::new(
                 sender,
                 &queue,
@@ -150,11 +163,20 @@ impl App {
     pub fn event(&mut self, ev: InputEvent) -> Result<()> {
         log::trace!("event: {:?}", ev);
 
-        if let InputEvent::Input(ev) = ev {
+        let input = match ev {
+            InputEvent::Input(ev) => Some((ev, false)),
+            InputEvent::Pasted(ev) => Some((ev, true)),
+            InputEvent::State(_) => None,
+        };
+
+        if let Some((ev, pasted)) = input {
             if self.check_quit_key(ev) {
                 return Ok(());
             }
 
+            // only the commit message takes multi-line text
+            self.commit.set_pasting(pasted);
+
             let mut flags = NeedsUpdate::empty();
 
             if event_pump(ev, self.components_mut().as_mut_slice())? {
@@ -183,6 +205,13 @@ impl App {
                         NeedsUpdate::empty()
                     }
 
+                    keys::OPEN_GC => {
+                        self.queue.borrow_mut().push_back(
+                            InternalEvent::ConfirmAction(Action::Gc),
+                        );
+                        NeedsUpdate::COMMANDS
+                    }
+
                     _ => NeedsUpdate::empty(),
                 };
 
@@ -257,6 +286,12 @@ impl App {
         self.stashing_tab.update_git(ev)?;
         self.revlog.update_git(ev)?;
         self.inspect_commit_popup.update_git(ev)?;
+        self.commit.update_git(ev)?;
+
+        // finished jobs might have queued something to show
+        if self.process_queue()?.contains(NeedsUpdate::ALL) {
+            self.update()?;
+        }
 
         //TODO: better system for this
         // can we simply process the queue here and everyone just uses the queue to schedule a cmd update?
@@ -276,6 +311,8 @@ impl App {
             || self.revlog.any_work_pending()
             || self.stashing_tab.anything_pending()
             || self.inspect_commit_popup.any_work_pending()
+            || self.gc_popup.any_work_pending()
+            || self.commit.any_work_pending()
             || self.input.is_state_changing()
     }
 
@@ -302,6 +339,7 @@ impl App {
             inspect_commit_popup,
             external_editor_popup,
             tag_commit_popup,
+            gc_popup,
             help,
             revlog,
             status_tab,
@@ -409,6 +447,14 @@ impl App {
                     sync::reset_hunk(CWD, path, hash)?;
                     flags.insert(NeedsUpdate::ALL);
                 }
+                Action::Gc => {
+                    self.gc_popup.open()?;
+                    flags.insert(NeedsUpdate::COMMANDS);
+                }
+                Action::CommitEmpty(sticky) => {
+                    self.commit.commit_empty(sticky)?;
+                    flags.insert(NeedsUpdate::COMMANDS);
+                }
             },
             InternalEvent::ConfirmAction(action) => {
                 self.reset.open(action)?;
@@ -419,6 +465,11 @@ impl App {
                 flags
                     .insert(NeedsUpdate::ALL | NeedsUpdate::COMMANDS);
             }
+            InternalEvent::ShowInfoMsg(msg) => {
+                self.msg.show_info(msg.as_str())?;
+                flags
+                    .insert(NeedsUpdate::ALL | NeedsUpdate::COMMANDS);
+            }
             InternalEvent::Update(u) => flags.insert(u),
             InternalEvent::OpenCommit => self.commit.show()?,
             InternalEvent::PopupStashing(opts) => {
@@ -439,6 +490,10 @@ impl App {
                 self.file_to_open = path;
                 flags.insert(NeedsUpdate::COMMANDS)
             }
+            InternalEvent::ToggleDiffStage => {
+                self.status_tab.toggle_diff_stage()?;
+                flags.insert(NeedsUpdate::COMMANDS);
+            }
         };
 
         Ok(flags)
@@ -473,6 +528,12 @@ impl App {
             .order(order::NAV),
         );
 
+        res.push(CommandInfo::new(
+            commands::OPEN_GC,
+            true,
+            !self.any_popup_visible(),
+        ));
+
         res.push(
             CommandInfo::new(
                 commands::QUIT,
@@ -494,6 +555,7 @@ impl App {
             || self.inspect_commit_popup.is_visible()
             || self.external_editor_popup.is_visible()
             || self.tag_commit_popup.is_visible()
+            || self.gc_popup.is_visible()
     }
 
     fn draw_popups<B: Backend>(
@@ -519,6 +581,7 @@ impl App {
         self.inspect_commit_popup.draw(f, size)?;
         self.external_editor_popup.draw(f, size)?;
         self.tag_commit_popup.draw(f, size)?;
+        self.gc_popup.draw(f, size)?;
 
         Ok(())
     }
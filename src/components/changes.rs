@@ -99,6 +99,11 @@ impl ChangesComponent {
         self.files.is_empty()
     }
 
+    /// selects the file at `path`, returns false if it is not listed
+    pub fn select_file(&mut self, path: &str) -> bool {
+        self.files.select_file(path)
+    }
+
     ///
     pub fn is_file_seleted(&self) -> bool {
         self.files.is_file_seleted()
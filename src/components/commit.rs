@@ -1,19 +1,22 @@
 use super::{
-    textinput::TextInputComponent, visibility_blocking,
-    CommandBlocking, CommandInfo, Component, DrawableComponent,
-    ExternalEditorComponent,
+    textinput::TextInputComponent, utils::commitmsg,
+    visibility_blocking, CommandBlocking, CommandInfo, Component,
+    DrawableComponent, ExternalEditorComponent,
 };
 use crate::{
     get_app_config_path, keys,
-    queue::{InternalEvent, NeedsUpdate, Queue},
+    options::SharedOptions,
+    queue::{Action, InternalEvent, NeedsUpdate, Queue},
     strings::{self, commands},
     ui::style::SharedTheme,
 };
 use anyhow::Result;
 use asyncgit::{
-    sync::{self, CommitId, HookResult},
-    CWD,
+    cached,
+    sync::{self, CommitId, CommitOptions, HookResult},
+    AsyncNotification, AsyncSpellCheck, SpellCheckResult,
 };
+use crossbeam_channel::Sender;
 use crossterm::event::Event;
 use std::{
     fs::File,
@@ -23,9 +26,13 @@ use std::{
 use tui::{backend::Backend, layout::Rect, Frame};
 
 pub struct CommitComponent {
+    repo: String,
     input: TextInputComponent,
     amend: Option<CommitId>,
     queue: Queue,
+    options: SharedOptions,
+    branch_name: cached::BranchName,
+    git_spell_check: AsyncSpellCheck,
 }
 
 impl DrawableComponent for CommitComponent {
@@ -55,6 +62,12 @@ impl Component for CommitComponent {
                 true,
             ));
 
+            out.push(CommandInfo::new(
+                commands::COMMIT_ENTER_STICKY,
+                self.can_commit(),
+                !self.options.sticky_commit,
+            ));
+
             out.push(CommandInfo::new(
                 commands::COMMIT_AMEND,
                 self.can_amend(),
@@ -66,6 +79,12 @@ impl Component for CommitComponent {
                 true,
                 true,
             ));
+
+            out.push(CommandInfo::new(
+                commands::COMMIT_SPELL_CHECK,
+                self.can_spell_check(),
+                self.options.commit_spell_check.is_some(),
+            ));
         }
 
         visibility_blocking(self)
@@ -80,13 +99,23 @@ impl Component for CommitComponent {
             if let Event::Key(e) = ev {
                 match e {
                     keys::ENTER if self.can_commit() => {
-                        self.commit()?;
+                        self.commit(self.options.sticky_commit)?;
+                    }
+
+                    keys::COMMIT_STICKY if self.can_commit() => {
+                        self.commit(true)?;
                     }
 
                     keys::COMMIT_AMEND if self.can_amend() => {
                         self.amend()?;
                     }
 
+                    keys::COMMIT_SPELL_CHECK
+                        if self.can_spell_check() =>
+                    {
+                        self.spell_check()?;
+                    }
+
                     keys::OPEN_COMMIT_EDITOR => {
                         self.queue.borrow_mut().push_back(
                             InternalEvent::OpenExternalEditor(None),
@@ -117,7 +146,15 @@ impl Component for CommitComponent {
         self.amend = None;
 
         self.input.clear();
+        self.input.reset_history();
         self.input.set_title(strings::COMMIT_TITLE.into());
+
+        if let Some(msg) = self.merge_message() {
+            self.input.set_text(msg);
+        } else if let Some(template) = self.template() {
+            self.input.set_text(template);
+        }
+
         self.input.show()?;
 
         Ok(())
@@ -126,10 +163,20 @@ impl Component for CommitComponent {
 
 impl CommitComponent {
     ///
-    pub fn new(queue: Queue, theme: SharedTheme) -> Self {
+    pub fn new(
+        repo: &str,
+        queue: Queue,
+        sender: &Sender<AsyncNotification>,
+        theme: SharedTheme,
+        options: SharedOptions,
+    ) -> Self {
         Self {
+            repo: repo.to_string(),
             queue,
             amend: None,
+            options,
+            branch_name: cached::BranchName::new(repo),
+            git_spell_check: AsyncSpellCheck::new(sender),
             input: TextInputComponent::new(
                 theme,
                 "",
@@ -138,6 +185,27 @@ impl CommitComponent {
         }
     }
 
+    ///
+    pub fn update_git(&self, ev: AsyncNotification) -> Result<()> {
+        if ev == AsyncNotification::SpellCheck {
+            if let Some(res) = self.git_spell_check.take_last()? {
+                self.spell_checked(res);
+            }
+        }
+
+        Ok(())
+    }
+
+    ///
+    pub fn any_work_pending(&self) -> bool {
+        self.git_spell_check.is_pending()
+    }
+
+    /// pasted newlines go into the message instead of committing
+    pub fn set_pasting(&mut self, pasting: bool) {
+        self.input.set_pasting(pasting);
+    }
+
     pub fn show_editor(&mut self) -> Result<()> {
         const COMMIT_MSG_FILE_NAME: &str = "COMMITMSG_EDITOR";
         //TODO: use a tmpfile here
@@ -181,30 +249,103 @@ impl CommitComponent {
         Ok(())
     }
 
-    fn commit(&mut self) -> Result<()> {
-        self.commit_msg(self.input.get_text().clone())
+    /// message prepared by git for a merge (or squash) in progress
+    fn merge_message(&self) -> Option<String> {
+        match sync::get_merge_message(&self.repo) {
+            Ok(msg) => msg.map(|msg| commitmsg::strip_comments(&msg)),
+            Err(e) => {
+                log::error!("merge message error: {}", e);
+                None
+            }
+        }
     }
 
-    fn commit_msg(&mut self, msg: String) -> Result<()> {
+    /// the configured `commit.template` with expanded placeholders
+    /// and without its comment lines
+    fn template(&mut self) -> Option<String> {
+        let path =
+            sync::get_config_string(&self.repo, "commit.template")
+                .ok()??;
+
+        let branch = self.branch_name.lookup().ok();
+
+        match commitmsg::load_template(&path, branch.as_deref()) {
+            Ok(template) => {
+                Some(commitmsg::strip_comments(&template))
+            }
+            Err(e) => {
+                log::error!("commit template error: {}", e);
+                None
+            }
+        }
+    }
+
+    fn commit(&mut self, sticky: bool) -> Result<()> {
+        self.commit_msg(self.input.get_text().clone(), sticky, false)
+    }
+
+    /// commits the current message even though nothing is staged
+    /// (after the user confirmed it)
+    pub fn commit_empty(&mut self, sticky: bool) -> Result<()> {
+        self.commit_msg(self.input.get_text().clone(), sticky, true)
+    }
+
+    fn commit_msg(
+        &mut self,
+        msg: String,
+        sticky: bool,
+        allow_empty: bool,
+    ) -> Result<()> {
+        // amending with an empty input keeps the original message
+        let keep_message = self.amend.is_some() && msg.is_empty();
+
         let mut msg = msg;
-        if let HookResult::NotOk(e) =
-            sync::hooks_commit_msg(CWD, &mut msg)?
-        {
-            log::error!("commit-msg hook error: {}", e);
-            self.queue.borrow_mut().push_back(
-                InternalEvent::ShowErrorMsg(format!(
-                    "commit-msg hook error:\n{}",
-                    e
-                )),
-            );
-            return Ok(());
+        if !keep_message {
+            if let Some(width) = self.options.commit_body_width {
+                msg = commitmsg::reflow_commit_msg(&msg, width);
+            }
+
+            if let HookResult::NotOk(e) =
+                sync::hooks_commit_msg(&self.repo, &mut msg)?
+            {
+                log::error!("commit-msg hook error: {}", e);
+                self.queue.borrow_mut().push_back(
+                    InternalEvent::ShowErrorMsg(format!(
+                        "commit-msg hook error:\n{}",
+                        e
+                    )),
+                );
+                return Ok(());
+            }
         }
 
+        let amend_msg = if keep_message {
+            None
+        } else {
+            Some(msg.as_str())
+        };
         let res = if let Some(amend) = self.amend {
-            sync::amend(CWD, amend, &msg)
+            sync::amend(&self.repo, amend, amend_msg)
         } else {
-            sync::commit(CWD, &msg)
+            sync::commit_with_options(
+                &self.repo,
+                &msg,
+                &CommitOptions {
+                    allow_empty,
+                    parents: self.merge_parents()?,
+                    ..CommitOptions::default()
+                },
+            )
         };
+        if matches!(res, Err(asyncgit::Error::NothingToCommit)) {
+            // let the user decide whether an empty commit is wanted
+            self.queue.borrow_mut().push_back(
+                InternalEvent::ConfirmAction(Action::CommitEmpty(
+                    sticky,
+                )),
+            );
+            return Ok(());
+        }
         if let Err(e) = res {
             log::error!("commit error: {}", &e);
             self.queue.borrow_mut().push_back(
@@ -216,7 +357,13 @@ impl CommitComponent {
             return Ok(());
         }
 
-        if let HookResult::NotOk(e) = sync::hooks_post_commit(CWD)? {
+        if self.amend.is_none() {
+            sync::cleanup_merge_state(&self.repo)?;
+        }
+
+        if let HookResult::NotOk(e) =
+            sync::hooks_post_commit(&self.repo)?
+        {
             log::error!("post-commit hook error: {}", e);
             self.queue.borrow_mut().push_back(
                 InternalEvent::ShowErrorMsg(format!(
@@ -226,7 +373,7 @@ impl CommitComponent {
             );
         }
 
-        self.hide();
+        self.committed(sticky)?;
 
         self.queue
             .borrow_mut()
@@ -235,28 +382,357 @@ impl CommitComponent {
         Ok(())
     }
 
-    fn can_commit(&self) -> bool {
+    /// `HEAD` and the commits being merged into it while a merge
+    /// is in progress so the commit concludes it
+    fn merge_parents(&self) -> Result<Option<Vec<CommitId>>> {
+        let merge_heads = sync::merge_heads(&self.repo)?;
+        if merge_heads.is_empty() {
+            return Ok(None);
+        }
+
+        let mut parents = vec![sync::get_head(&self.repo)?];
+        parents.extend(merge_heads);
+
+        Ok(Some(parents))
+    }
+
+    /// after a successful commit: either close or (`sticky`) stay
+    /// open with a fresh input ready for the next commit
+    fn committed(&mut self, sticky: bool) -> Result<()> {
+        if sticky {
+            self.show()
+        } else {
+            self.hide();
+            Ok(())
+        }
+    }
+
+    fn can_spell_check(&self) -> bool {
         !self.input.get_text().is_empty()
+            && !self.git_spell_check.is_pending()
+    }
+
+    /// runs the configured checker on the current message in the
+    /// background, the commit itself is not affected
+    fn spell_check(&mut self) -> Result<()> {
+        if let Some(command) = &self.options.commit_spell_check {
+            self.git_spell_check
+                .run(command, self.input.get_text())?;
+        }
+
+        Ok(())
+    }
+
+    /// shows the findings of a finished spell check
+    fn spell_checked(&self, res: SpellCheckResult) {
+        let event = match res {
+            Ok(findings) if findings.is_empty() => {
+                InternalEvent::ShowInfoMsg(
+                    strings::SPELL_CHECK_NO_FINDINGS.into(),
+                )
+            }
+            Ok(findings) => InternalEvent::ShowInfoMsg(findings),
+            Err(e) => {
+                log::error!("spell check error: {}", e);
+                InternalEvent::ShowErrorMsg(format!(
+                    "spell check failed:\n{}",
+                    e
+                ))
+            }
+        };
+
+        self.queue.borrow_mut().push_back(event);
+    }
+
+    fn can_commit(&self) -> bool {
+        !self.input.get_text().is_empty() || self.amend.is_some()
     }
 
     fn can_amend(&self) -> bool {
         self.amend.is_none()
-            && sync::get_head(CWD).is_ok()
+            && sync::get_head(&self.repo).is_ok()
             && self.input.get_text().is_empty()
     }
 
     fn amend(&mut self) -> Result<()> {
-        let id = sync::get_head(CWD)?;
+        let id = sync::get_head(&self.repo)?;
         self.amend = Some(id);
 
-        let details = sync::get_commit_details(CWD, id)?;
+        let details = sync::get_commit_details(&self.repo, id)?;
 
         self.input.set_title(strings::COMMIT_TITLE_AMEND.into());
 
         if let Some(msg) = details.message {
-            self.input.set_text(msg.combine());
+            let (subject, body) =
+                sync::split_commit_message(&msg.combine());
+            self.input.set_text(if body.is_empty() {
+                subject
+            } else {
+                format!("{}\n\n{}", subject, body)
+            });
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::Options;
+    use crossbeam_channel::{unbounded, Receiver};
+    use crossterm::event::KeyCode;
+    use git2::Repository;
+    use std::{
+        cell::RefCell, collections::VecDeque, fs, path::Path, rc::Rc,
+    };
+    use tempfile::TempDir;
+
+    /// a repo with an initial commit
+    fn repo_init() -> (TempDir, String) {
+        let td = TempDir::new().unwrap();
+        let repo = Repository::init(td.path()).unwrap();
+
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "name").unwrap();
+        config.set_str("user.email", "email").unwrap();
+
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let sig = repo.signature().unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[])
+            .unwrap();
+
+        let path = td.path().to_str().unwrap().to_string();
+        (td, path)
+    }
+
+    fn component(
+        repo_path: &str,
+        spell_check: Option<&str>,
+    ) -> (CommitComponent, Receiver<AsyncNotification>) {
+        let (tx, rx) = unbounded();
+        let comp = CommitComponent::new(
+            repo_path,
+            Rc::new(RefCell::new(VecDeque::new())),
+            &tx,
+            SharedTheme::default(),
+            Rc::new(Options {
+                commit_spell_check: spell_check.map(String::from),
+                ..Options::default()
+            }),
+        );
+        (comp, rx)
+    }
+
+    #[test]
+    fn test_spell_check() {
+        let (_td, repo_path) = repo_init();
+        let (mut comp, rx) = component(
+            &repo_path,
+            Some("echo 'teh: did you mean the'"),
+        );
+        comp.show().unwrap();
+        comp.input.set_text("fix teh bug".into());
+
+        comp.event(Event::Key(keys::COMMIT_SPELL_CHECK)).unwrap();
+
+        // the check runs in the background
+        assert_eq!(comp.queue.borrow().is_empty(), true);
+        comp.update_git(rx.recv().unwrap()).unwrap();
+        assert_eq!(comp.any_work_pending(), false);
+
+        let event = comp.queue.borrow_mut().pop_front();
+        let msg = match event {
+            Some(InternalEvent::ShowInfoMsg(msg)) => Some(msg),
+            _ => None,
+        };
+        assert_eq!(msg.as_deref(), Some("teh: did you mean the"));
+
+        // the message is left alone and can still be committed
+        assert_eq!(comp.is_visible(), true);
+        assert_eq!(comp.input.get_text(), "fix teh bug");
+    }
+
+    #[test]
+    fn test_spell_check_not_configured() {
+        let (_td, repo_path) = repo_init();
+        let (mut comp, _rx) = component(&repo_path, None);
+        comp.show().unwrap();
+        comp.input.set_text("fix teh bug".into());
+
+        comp.event(Event::Key(keys::COMMIT_SPELL_CHECK)).unwrap();
+
+        assert_eq!(comp.any_work_pending(), false);
+        assert_eq!(comp.queue.borrow().is_empty(), true);
+    }
+
+    #[test]
+    fn test_merge_message_prefill() {
+        let (_td, repo_path) = repo_init();
+        let git_dir = Path::new(&repo_path).join(".git");
+
+        // a side commit to merge
+        let repo = Repository::open(&repo_path).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let sig = repo.signature().unwrap();
+        let feature = repo
+            .commit(
+                None,
+                &sig,
+                &sig,
+                "feature",
+                &head.tree().unwrap(),
+                &[&head],
+            )
+            .unwrap();
+
+        // a merge that stopped with conflicts
+        fs::write(
+            git_dir.join("MERGE_HEAD"),
+            format!("{}\n", feature),
+        )
+        .unwrap();
+        fs::write(
+            git_dir.join("MERGE_MSG"),
+            "Merge branch 'feature'\n\n# Conflicts:\n#\tfoo.txt\n",
+        )
+        .unwrap();
+
+        let (mut comp, _rx) = component(&repo_path, None);
+        comp.show().unwrap();
+
+        assert_eq!(comp.input.get_text(), "Merge branch 'feature'");
+
+        comp.commit(true).unwrap();
+
+        // concludes the merge
+        let merge = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(
+            merge.parent_ids().collect::<Vec<_>>(),
+            vec![head.id(), feature]
+        );
+        assert_eq!(git_dir.join("MERGE_HEAD").exists(), false);
+
+        // the sticky popup starts over
+        assert_eq!(comp.is_visible(), true);
+        assert_eq!(comp.input.get_text().is_empty(), true);
+    }
+
+    #[test]
+    fn test_squash_message_prefill() {
+        let (_td, repo_path) = repo_init();
+        let git_dir = Path::new(&repo_path).join(".git");
+
+        fs::write(git_dir.join("SQUASH_MSG"), "Squashed commit\n")
+            .unwrap();
+
+        let (mut comp, _rx) = component(&repo_path, None);
+        comp.show().unwrap();
+
+        assert_eq!(comp.input.get_text(), "Squashed commit");
+
+        comp.commit_empty(false).unwrap();
+
+        assert_eq!(git_dir.join("SQUASH_MSG").exists(), false);
+    }
+
+    #[test]
+    fn test_commit_empty() {
+        let (_td, repo_path) = repo_init();
+        let (mut comp, _rx) = component(&repo_path, None);
+        let head = sync::get_head(&repo_path).unwrap();
+        comp.show().unwrap();
+        comp.input.set_text("empty".into());
+
+        comp.event(Event::Key(keys::ENTER)).unwrap();
+
+        // nothing is staged, so it asks first
+        let event = comp.queue.borrow_mut().pop_front();
+        let sticky = match event {
+            Some(InternalEvent::ConfirmAction(
+                Action::CommitEmpty(sticky),
+            )) => Some(sticky),
+            _ => None,
+        };
+        assert_eq!(sticky, Some(false));
+        assert_eq!(comp.is_visible(), true);
+        assert_eq!(sync::get_head(&repo_path).unwrap(), head);
+
+        comp.commit_empty(false).unwrap();
+
+        assert_eq!(comp.is_visible(), false);
+        assert_ne!(sync::get_head(&repo_path).unwrap(), head);
+    }
+
+    #[test]
+    fn test_amend_prefill() {
+        let (_td, repo_path) = repo_init();
+
+        let repo = Repository::open(&repo_path).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let sig = repo.signature().unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &sig,
+            &sig,
+            "subject  \r\n\r\nbody\r\nend\r\n",
+            &head.tree().unwrap(),
+            &[&head],
+        )
+        .unwrap();
+
+        let (mut comp, _rx) = component(&repo_path, None);
+        comp.show().unwrap();
+
+        comp.event(Event::Key(keys::COMMIT_AMEND)).unwrap();
+
+        assert_eq!(comp.input.get_text(), "subject\n\nbody\nend");
+    }
+
+    #[test]
+    fn test_pasted_enter() {
+        let (_td, repo_path) = repo_init();
+        let (mut comp, _rx) = component(&repo_path, None);
+        let head = sync::get_head(&repo_path).unwrap();
+        comp.show().unwrap();
+
+        comp.set_pasting(true);
+        for code in
+            &[KeyCode::Char('a'), KeyCode::Enter, KeyCode::Char('b')]
+        {
+            comp.event(Event::Key((*code).into())).unwrap();
+        }
+
+        assert_eq!(comp.input.get_text(), "a\nb");
+        assert_eq!(comp.queue.borrow().is_empty(), true);
+
+        // a typed enter commits (asking first as nothing is staged)
+        comp.set_pasting(false);
+        comp.event(Event::Key(keys::ENTER)).unwrap();
+
+        assert_eq!(comp.queue.borrow().is_empty(), false);
+        assert_eq!(sync::get_head(&repo_path).unwrap(), head);
+    }
+
+    #[test]
+    fn test_sticky_commit() {
+        let (_td, repo_path) = repo_init();
+        let (mut comp, _rx) = component(&repo_path, None);
+        comp.show().unwrap();
+        comp.amend = sync::get_head(&repo_path).ok();
+        comp.input.set_text("first commit".into());
+
+        comp.committed(true).unwrap();
+
+        assert_eq!(comp.is_visible(), true);
+        assert_eq!(comp.input.get_text().is_empty(), true);
+        assert_eq!(comp.amend.is_none(), true);
+
+        comp.input.set_text("second commit".into());
+
+        comp.committed(false).unwrap();
+
+        assert_eq!(comp.is_visible(), false);
+    }
+}
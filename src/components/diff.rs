@@ -332,20 +332,20 @@ impl DiffComponent {
         false
     }
 
-    fn unstage_hunk(&mut self) -> Result<()> {
+    fn unstage_hunk(&mut self) {
         if let Some(diff) = &self.diff {
             if let Some(hunk) = self.selected_hunk {
                 let hash = diff.hunks[hunk].header_hash;
-                sync::unstage_hunk(
+                let res = sync::unstage_hunk(
                     CWD,
                     self.current.path.clone(),
                     hash,
-                )?;
-                self.queue_update();
+                );
+                self.queue_hunk_result(
+                    res.map(|_| ()).map_err(Into::into),
+                );
             }
         }
-
-        Ok(())
     }
 
     fn stage_hunk(&mut self) -> Result<()> {
@@ -354,18 +354,36 @@ impl DiffComponent {
                 let path = self.current.path.clone();
                 if diff.untracked {
                     sync::stage_add_file(CWD, Path::new(&path))?;
+                    self.queue_update();
                 } else {
                     let hash = diff.hunks[hunk].header_hash;
-                    sync::stage_hunk(CWD, path, hash)?;
+                    let res = sync::stage_hunk(CWD, path, hash);
+                    self.queue_hunk_result(res.map_err(Into::into));
                 }
-
-                self.queue_update();
             }
         }
 
         Ok(())
     }
 
+    /// a failed hunk operation usually means the diff is outdated,
+    /// so it is refreshed either way
+    fn queue_hunk_result(&mut self, res: Result<()>) {
+        if let Err(e) = res {
+            log::error!("hunk error: {}", e);
+            self.queue
+                .as_ref()
+                .expect("try using queue in immutable diff")
+                .borrow_mut()
+                .push_back(InternalEvent::ShowErrorMsg(format!(
+                    "hunk operation failed:\n{}",
+                    e
+                )));
+        }
+
+        self.queue_update();
+    }
+
     fn queue_update(&mut self) {
         self.queue
             .as_ref()
@@ -409,6 +427,14 @@ impl DiffComponent {
         Ok(())
     }
 
+    fn toggle_stage(&self) {
+        self.queue
+            .as_ref()
+            .expect("try using queue in immutable diff")
+            .borrow_mut()
+            .push_back(InternalEvent::ToggleDiffStage);
+    }
+
     fn is_immutable(&self) -> bool {
         self.queue.is_none()
     }
@@ -499,6 +525,11 @@ impl Component for DiffComponent {
                 self.selected_hunk.is_some(),
                 self.focused && !self.is_stage(),
             ));
+            out.push(CommandInfo::new(
+                commands::DIFF_TOGGLE_STAGE,
+                self.diff.is_some(),
+                self.focused,
+            ));
         }
 
         CommandBlocking::PassingOn
@@ -534,7 +565,7 @@ impl Component for DiffComponent {
                     }
                     keys::ENTER if !self.is_immutable() => {
                         if self.current.is_stage {
-                            self.unstage_hunk()?;
+                            self.unstage_hunk();
                         } else {
                             self.stage_hunk()?;
                         }
@@ -553,6 +584,13 @@ impl Component for DiffComponent {
                         }
                         Ok(true)
                     }
+                    keys::DIFF_TOGGLE_STAGE
+                        if !self.is_immutable()
+                            && self.diff.is_some() =>
+                    {
+                        self.toggle_stage();
+                        Ok(true)
+                    }
                     _ => Ok(false),
                 };
             }
@@ -582,6 +620,7 @@ mod tests {
             &DiffLine {
                 content: String::from("line 1\r\n"),
                 line_type: DiffLineType::None,
+                ..DiffLine::default()
             },
             false,
             false,
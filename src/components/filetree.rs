@@ -91,6 +91,11 @@ impl FileTreeComponent {
         self.tree.is_empty()
     }
 
+    /// selects the file at `path`, returns false if it is not listed
+    pub fn select_file(&mut self, path: &str) -> bool {
+        self.tree.select_path(path)
+    }
+
     ///
     pub const fn file_count(&self) -> usize {
         self.tree.tree.file_count()
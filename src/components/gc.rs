@@ -0,0 +1,147 @@
+use super::{
+    visibility_blocking, CommandBlocking, CommandInfo, Component,
+    DrawableComponent,
+};
+use crate::{
+    keys,
+    strings::{self, commands},
+    ui,
+};
+use anyhow::Result;
+use asyncgit::{AsyncGc, AsyncNotification};
+use crossbeam_channel::Sender;
+use crossterm::event::Event;
+use std::borrow::Cow;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Text},
+    Frame,
+};
+use ui::style::SharedTheme;
+
+/// runs a gc and shows its progress
+pub struct GcComponent {
+    git_gc: AsyncGc,
+    visible: bool,
+    theme: SharedTheme,
+}
+
+impl DrawableComponent for GcComponent {
+    fn draw<B: Backend>(
+        &self,
+        f: &mut Frame<B>,
+        _rect: Rect,
+    ) -> Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+
+        let (msg, style) = if self.git_gc.is_pending() {
+            (
+                self.git_gc.progress()?.unwrap_or_else(|| {
+                    strings::GC_MSG_STARTING.to_string()
+                }),
+                self.theme.text(true, false),
+            )
+        } else if let Some(error) = self.git_gc.last_error()? {
+            (error, self.theme.text_danger())
+        } else {
+            (
+                strings::GC_MSG_DONE.to_string(),
+                self.theme.text(true, false),
+            )
+        };
+
+        let txt = [Text::Styled(Cow::from(msg), style)];
+
+        let area = ui::centered_rect_absolute(50, 5, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(txt.iter())
+                .block(
+                    Block::default()
+                        .title(strings::GC_TITLE)
+                        .title_style(self.theme.title(true))
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Thick),
+                )
+                .alignment(Alignment::Left)
+                .wrap(true),
+            area,
+        );
+
+        Ok(())
+    }
+}
+
+impl Component for GcComponent {
+    fn commands(
+        &self,
+        out: &mut Vec<CommandInfo>,
+        _force_all: bool,
+    ) -> CommandBlocking {
+        out.push(CommandInfo::new(
+            commands::CLOSE_POPUP,
+            !self.git_gc.is_pending(),
+            self.visible,
+        ));
+
+        visibility_blocking(self)
+    }
+
+    fn event(&mut self, ev: Event) -> Result<bool> {
+        if self.visible {
+            if ev == Event::Key(keys::EXIT_POPUP) {
+                // a running gc can not be cancelled
+                if !self.git_gc.is_pending() {
+                    self.hide();
+                }
+            }
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    fn show(&mut self) -> Result<()> {
+        self.visible = true;
+
+        Ok(())
+    }
+}
+
+impl GcComponent {
+    ///
+    pub fn new(
+        sender: &Sender<AsyncNotification>,
+        theme: SharedTheme,
+    ) -> Self {
+        Self {
+            git_gc: AsyncGc::new(sender),
+            visible: false,
+            theme,
+        }
+    }
+
+    /// starts the gc and shows its progress
+    pub fn open(&mut self) -> Result<()> {
+        self.git_gc.run(false)?;
+        self.show()?;
+
+        Ok(())
+    }
+
+    ///
+    pub fn any_work_pending(&self) -> bool {
+        self.git_gc.is_pending()
+    }
+}
@@ -6,6 +6,7 @@ mod commitlist;
 mod diff;
 mod externaleditor;
 mod filetree;
+mod gc;
 mod help;
 mod inspect_commit;
 mod msg;
@@ -26,6 +27,7 @@ pub use commitlist::CommitList;
 pub use diff::DiffComponent;
 pub use externaleditor::ExternalEditorComponent;
 pub use filetree::FileTreeComponent;
+pub use gc::GcComponent;
 pub use help::HelpComponent;
 pub use inspect_commit::InspectCommitComponent;
 pub use msg::MsgComponent;
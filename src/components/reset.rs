@@ -152,6 +152,14 @@ impl ResetComponent {
                     strings::CONFIRM_TITLE_RESET,
                     strings::CONFIRM_MSG_RESETHUNK,
                 ),
+                Action::Gc => (
+                    strings::CONFIRM_TITLE_GC,
+                    strings::CONFIRM_MSG_GC,
+                ),
+                Action::CommitEmpty(_) => (
+                    strings::CONFIRM_TITLE_COMMIT_EMPTY,
+                    strings::CONFIRM_MSG_COMMIT_EMPTY,
+                ),
             };
         }
 
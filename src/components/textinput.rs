@@ -3,6 +3,7 @@ use crate::{
         popup_paragraph, visibility_blocking, CommandBlocking,
         CommandInfo, Component, DrawableComponent,
     },
+    keys,
     strings::commands,
     ui::{self, style::SharedTheme},
 };
@@ -15,6 +16,20 @@ use tui::{
     widgets::{Clear, Text},
     Frame,
 };
+use unicode_segmentation::UnicodeSegmentation;
+
+/// kind of the last edit, consecutive inserts form one undo step
+#[derive(PartialEq, Clone, Copy)]
+enum EditKind {
+    Insert,
+    Other,
+}
+
+/// state of the text to return to on undo/redo
+struct Snapshot {
+    msg: String,
+    cursor_position: usize,
+}
 
 /// primarily a subcomponet for user input of text (used in `CommitComponent`)
 pub struct TextInputComponent {
@@ -24,6 +39,10 @@ pub struct TextInputComponent {
     visible: bool,
     theme: SharedTheme,
     cursor_position: usize,
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+    last_edit: Option<EditKind>,
+    pasting: bool,
 }
 
 impl TextInputComponent {
@@ -40,11 +59,69 @@ impl TextInputComponent {
             title: title.to_string(),
             default_msg: default_msg.to_string(),
             cursor_position: 0,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            last_edit: None,
+            pasting: false,
+        }
+    }
+
+    /// whether the following events are part of a paste:
+    /// a pasted enter is a newline in the text, not a confirmation
+    pub fn set_pasting(&mut self, pasting: bool) {
+        self.pasting = pasting;
+    }
+
+    /// Forget all undo/redo history.
+    pub fn reset_history(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.last_edit = None;
+    }
+
+    /// Record the current state before an edit of `kind`.
+    fn snapshot(&mut self, kind: EditKind) {
+        let coalesce = kind == EditKind::Insert
+            && self.last_edit == Some(EditKind::Insert);
+
+        if !coalesce {
+            self.undo_stack.push(self.current_state());
+        }
+
+        self.redo_stack.clear();
+        self.last_edit = Some(kind);
+    }
+
+    fn current_state(&self) -> Snapshot {
+        Snapshot {
+            msg: self.msg.clone(),
+            cursor_position: self.cursor_position,
+        }
+    }
+
+    fn restore(&mut self, state: Snapshot) {
+        self.msg = state.msg;
+        self.cursor_position = state.cursor_position;
+        self.last_edit = None;
+    }
+
+    fn undo(&mut self) {
+        if let Some(state) = self.undo_stack.pop() {
+            self.redo_stack.push(self.current_state());
+            self.restore(state);
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(state) = self.redo_stack.pop() {
+            self.undo_stack.push(self.current_state());
+            self.restore(state);
         }
     }
 
     /// Clear the `msg`.
     pub fn clear(&mut self) {
+        self.snapshot(EditKind::Other);
         self.msg.clear();
         self.cursor_position = 0;
     }
@@ -86,8 +163,59 @@ impl TextInputComponent {
         Some(index)
     }
 
+    /// Move the cursor, ending the current run of inserts.
+    fn move_cursor(&mut self, pos: usize) {
+        self.cursor_position = pos;
+        self.last_edit = None;
+    }
+
+    /// Get the start of the word left of the cursor (or 0).
+    fn prev_word_position(&self) -> usize {
+        self.words()
+            .map(|(start, _)| start)
+            .filter(|start| *start < self.cursor_position)
+            .last()
+            .unwrap_or(0)
+    }
+
+    /// Get the end of the word right of the cursor (or `msg.len()`).
+    fn next_word_position(&self) -> usize {
+        self.words()
+            .map(|(start, word)| start + word.len())
+            .find(|end| *end > self.cursor_position)
+            .unwrap_or(self.msg.len())
+    }
+
+    /// Iterate the words of `msg` using unicode word boundaries,
+    /// skipping whitespace and punctuation segments.
+    fn words(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.msg
+            .split_word_bound_indices()
+            .filter(|(_, w)| w.chars().any(char::is_alphanumeric))
+    }
+
+    fn delete_word_left(&mut self) {
+        self.snapshot(EditKind::Other);
+        let start = self.prev_word_position();
+        self.msg.replace_range(start..self.cursor_position, "");
+        self.cursor_position = start;
+    }
+
+    fn delete_word_right(&mut self) {
+        self.snapshot(EditKind::Other);
+        let end = self.next_word_position();
+        self.msg.replace_range(self.cursor_position..end, "");
+    }
+
+    fn insert_char(&mut self, c: char) {
+        self.snapshot(EditKind::Insert);
+        self.msg.insert(self.cursor_position, c);
+        self.incr_cursor();
+    }
+
     fn backspace(&mut self) {
         if self.cursor_position > 0 {
+            self.snapshot(EditKind::Other);
             self.decr_cursor();
             self.msg.remove(self.cursor_position);
         }
@@ -95,6 +223,7 @@ impl TextInputComponent {
 
     /// Set the `msg`.
     pub fn set_text(&mut self, msg: String) {
+        self.snapshot(EditKind::Other);
         self.msg = msg;
         self.cursor_position = 0;
     }
@@ -208,6 +337,34 @@ impl Component for TextInputComponent {
     fn event(&mut self, ev: Event) -> Result<bool> {
         if self.visible {
             if let Event::Key(e) = ev {
+                match e {
+                    keys::WORD_LEFT => {
+                        self.move_cursor(self.prev_word_position());
+                        return Ok(true);
+                    }
+                    keys::WORD_RIGHT => {
+                        self.move_cursor(self.next_word_position());
+                        return Ok(true);
+                    }
+                    keys::DELETE_WORD_LEFT => {
+                        self.delete_word_left();
+                        return Ok(true);
+                    }
+                    keys::DELETE_WORD_RIGHT => {
+                        self.delete_word_right();
+                        return Ok(true);
+                    }
+                    keys::UNDO => {
+                        self.undo();
+                        return Ok(true);
+                    }
+                    keys::REDO => {
+                        self.redo();
+                        return Ok(true);
+                    }
+                    _ => (),
+                }
+
                 let is_ctrl =
                     e.modifiers.contains(KeyModifiers::CONTROL);
                 match e.code {
@@ -216,12 +373,22 @@ impl Component for TextInputComponent {
                         return Ok(true);
                     }
                     KeyCode::Char(c) if !is_ctrl => {
-                        self.msg.insert(self.cursor_position, c);
-                        self.incr_cursor();
+                        self.insert_char(if c == '\r' {
+                            '\n'
+                        } else {
+                            c
+                        });
+                        return Ok(true);
+                    }
+                    KeyCode::Enter
+                        if self.pasting && e.modifiers.is_empty() =>
+                    {
+                        self.insert_char('\n');
                         return Ok(true);
                     }
                     KeyCode::Delete => {
                         if self.cursor_position < self.msg.len() {
+                            self.snapshot(EditKind::Other);
                             self.msg.remove(self.cursor_position);
                         }
                         return Ok(true);
@@ -232,18 +399,20 @@ impl Component for TextInputComponent {
                     }
                     KeyCode::Left => {
                         self.decr_cursor();
+                        self.last_edit = None;
                         return Ok(true);
                     }
                     KeyCode::Right => {
                         self.incr_cursor();
+                        self.last_edit = None;
                         return Ok(true);
                     }
                     KeyCode::Home => {
-                        self.cursor_position = 0;
+                        self.move_cursor(0);
                         return Ok(true);
                     }
                     KeyCode::End => {
-                        self.cursor_position = self.msg.len();
+                        self.move_cursor(self.msg.len());
                         return Ok(true);
                     }
                     _ => (),
@@ -313,4 +482,193 @@ mod tests {
         assert_eq!(get_text(&txt[2]), Some("\n"));
         assert_eq!(get_text(&txt[3]), Some("b"));
     }
+
+    /// sends `text` the way a paste arrives
+    fn paste(comp: &mut TextInputComponent, text: &str) {
+        comp.set_pasting(true);
+        for c in text.chars() {
+            let code = if c == '\n' {
+                KeyCode::Enter
+            } else {
+                KeyCode::Char(c)
+            };
+            comp.event(Event::Key(code.into())).unwrap();
+        }
+        comp.set_pasting(false);
+    }
+
+    #[test]
+    fn test_paste_multiline() {
+        let mut comp =
+            TextInputComponent::new(SharedTheme::default(), "", "");
+        comp.show().unwrap();
+
+        let text = "subject\n\nbody line1\nbody line2";
+
+        paste(&mut comp, text);
+
+        assert_eq!(comp.get_text(), text);
+        assert_eq!(comp.cursor_position, text.len());
+    }
+
+    #[test]
+    fn test_enter_not_consumed() {
+        let mut comp =
+            TextInputComponent::new(SharedTheme::default(), "", "");
+        comp.show().unwrap();
+
+        paste(&mut comp, "a");
+
+        assert_eq!(
+            comp.event(Event::Key(KeyCode::Enter.into())).unwrap(),
+            false
+        );
+        assert_eq!(comp.get_text(), "a");
+    }
+
+    #[test]
+    fn test_word_jumps() {
+        let mut comp =
+            TextInputComponent::new(SharedTheme::default(), "", "");
+
+        comp.set_text(String::from("fix(ui): foo, bar"));
+
+        comp.cursor_position = comp.next_word_position();
+        assert_eq!(comp.cursor_position, 3);
+
+        comp.cursor_position = comp.next_word_position();
+        assert_eq!(comp.cursor_position, 6);
+
+        comp.cursor_position = comp.next_word_position();
+        assert_eq!(comp.cursor_position, 12);
+
+        comp.cursor_position = comp.next_word_position();
+        assert_eq!(comp.cursor_position, 17);
+
+        comp.cursor_position = comp.next_word_position();
+        assert_eq!(comp.cursor_position, 17);
+
+        comp.cursor_position = comp.prev_word_position();
+        assert_eq!(comp.cursor_position, 14);
+
+        comp.cursor_position = comp.prev_word_position();
+        assert_eq!(comp.cursor_position, 9);
+
+        comp.cursor_position = comp.prev_word_position();
+        assert_eq!(comp.cursor_position, 4);
+
+        comp.cursor_position = comp.prev_word_position();
+        assert_eq!(comp.cursor_position, 0);
+    }
+
+    #[test]
+    fn test_word_jumps_multibyte() {
+        let mut comp =
+            TextInputComponent::new(SharedTheme::default(), "", "");
+
+        comp.set_text(String::from("über straße—größe"));
+
+        comp.cursor_position = comp.next_word_position();
+        assert_eq!(&comp.msg[..comp.cursor_position], "über");
+
+        comp.cursor_position = comp.next_word_position();
+        assert_eq!(&comp.msg[..comp.cursor_position], "über straße");
+
+        comp.cursor_position = comp.next_word_position();
+        assert_eq!(comp.cursor_position, comp.msg.len());
+
+        comp.cursor_position = comp.prev_word_position();
+        assert_eq!(&comp.msg[comp.cursor_position..], "größe");
+    }
+
+    #[test]
+    fn test_delete_words() {
+        let mut comp =
+            TextInputComponent::new(SharedTheme::default(), "", "");
+        comp.show().unwrap();
+
+        comp.set_text(String::from("foo bär, baz"));
+        comp.event(Event::Key(keys::END)).unwrap();
+
+        comp.event(Event::Key(keys::DELETE_WORD_LEFT)).unwrap();
+        assert_eq!(comp.get_text(), "foo bär, ");
+
+        comp.event(Event::Key(keys::DELETE_WORD_LEFT)).unwrap();
+        assert_eq!(comp.get_text(), "foo ");
+
+        comp.event(Event::Key(keys::HOME)).unwrap();
+        comp.event(Event::Key(keys::DELETE_WORD_RIGHT)).unwrap();
+        assert_eq!(comp.get_text(), " ");
+        assert_eq!(comp.cursor_position, 0);
+    }
+
+    fn type_text(comp: &mut TextInputComponent, text: &str) {
+        for c in text.chars() {
+            comp.event(Event::Key(KeyCode::Char(c).into())).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut comp =
+            TextInputComponent::new(SharedTheme::default(), "", "");
+        comp.show().unwrap();
+
+        type_text(&mut comp, "foo bar");
+        assert_eq!(comp.get_text(), "foo bar");
+
+        comp.event(Event::Key(keys::DELETE_WORD_LEFT)).unwrap();
+        assert_eq!(comp.get_text(), "foo ");
+
+        comp.event(Event::Key(KeyCode::Backspace.into())).unwrap();
+        assert_eq!(comp.get_text(), "foo");
+
+        comp.event(Event::Key(keys::UNDO)).unwrap();
+        assert_eq!(comp.get_text(), "foo ");
+
+        comp.event(Event::Key(keys::UNDO)).unwrap();
+        assert_eq!(comp.get_text(), "foo bar");
+        assert_eq!(comp.cursor_position, 7);
+
+        // consecutive inserts are undone in one step
+        comp.event(Event::Key(keys::UNDO)).unwrap();
+        assert_eq!(comp.get_text(), "");
+
+        comp.event(Event::Key(keys::UNDO)).unwrap();
+        assert_eq!(comp.get_text(), "");
+
+        comp.event(Event::Key(keys::REDO)).unwrap();
+        assert_eq!(comp.get_text(), "foo bar");
+
+        comp.event(Event::Key(keys::REDO)).unwrap();
+        assert_eq!(comp.get_text(), "foo ");
+
+        // a new edit drops the redo history
+        type_text(&mut comp, "x");
+        comp.event(Event::Key(keys::REDO)).unwrap();
+        assert_eq!(comp.get_text(), "foo x");
+    }
+
+    #[test]
+    fn test_undo_insert_runs() {
+        let mut comp =
+            TextInputComponent::new(SharedTheme::default(), "", "");
+        comp.show().unwrap();
+
+        type_text(&mut comp, "ab");
+        comp.event(Event::Key(keys::HOME)).unwrap();
+        type_text(&mut comp, "cd");
+        assert_eq!(comp.get_text(), "cdab");
+
+        comp.event(Event::Key(keys::UNDO)).unwrap();
+        assert_eq!(comp.get_text(), "ab");
+
+        comp.clear();
+        comp.event(Event::Key(keys::UNDO)).unwrap();
+        assert_eq!(comp.get_text(), "ab");
+
+        comp.reset_history();
+        comp.event(Event::Key(keys::UNDO)).unwrap();
+        assert_eq!(comp.get_text(), "ab");
+    }
 }
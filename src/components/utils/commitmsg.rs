@@ -0,0 +1,323 @@
+use anyhow::{anyhow, Result};
+use std::{fs, path::PathBuf};
+use unicode_width::UnicodeWidthStr;
+
+/// placeholder in commit templates replaced by the current branch
+const TEMPLATE_BRANCH: &str = "$BRANCH";
+
+/// reads the commit template at `path` (`commit.template`, may start
+/// with `~/`) and expands its placeholders, see `expand_template`
+pub fn load_template(
+    path: &str,
+    branch: Option<&str>,
+) -> Result<String> {
+    let path = match path.strip_prefix("~/") {
+        Some(rel) => dirs::home_dir()
+            .ok_or_else(|| anyhow!("failed to find home dir"))?
+            .join(rel),
+        None => PathBuf::from(path),
+    };
+
+    let template = fs::read_to_string(&path)
+        .map_err(|e| anyhow!("\"{}\": {}", path.display(), e))?;
+
+    Ok(expand_template(&template, branch))
+}
+
+/// drops git's `#` comment lines and trailing empty lines
+pub fn strip_comments(msg: &str) -> String {
+    msg.lines()
+        .filter(|l| !l.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end_matches('\n')
+        .to_string()
+}
+
+/// replaces `$BRANCH` with `branch`, any other `$`-token (or
+/// `$BRANCH` without a known branch) is kept as it is
+pub fn expand_template(
+    template: &str,
+    branch: Option<&str>,
+) -> String {
+    let branch = match branch {
+        Some(branch) => branch,
+        None => return template.to_string(),
+    };
+
+    let mut res = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(idx) = rest.find(TEMPLATE_BRANCH) {
+        let end = idx + TEMPLATE_BRANCH.len();
+        let is_token_end = rest[end..]
+            .chars()
+            .next()
+            .map_or(true, |c| !c.is_alphanumeric() && c != '_');
+
+        res.push_str(&rest[..idx]);
+        res.push_str(if is_token_end {
+            branch
+        } else {
+            TEMPLATE_BRANCH
+        });
+        rest = &rest[end..];
+    }
+
+    res.push_str(rest);
+
+    res
+}
+
+/// reflows the body of a commit message to `width` columns,
+/// the subject (first line) is never touched.
+/// blank lines and indented (code) lines are kept as they are,
+/// list items are wrapped individually using a hanging indent.
+/// a final block of trailers (`Signed-off-by: ..`) is kept as is
+pub fn reflow_commit_msg(msg: &str, width: usize) -> String {
+    let lines = msg.lines().collect::<Vec<_>>();
+    let trailers = trailers_start(&lines);
+
+    let mut res =
+        vec![lines.first().copied().unwrap_or_default().to_string()];
+
+    let mut paragraph = Paragraph::default();
+
+    for line in lines.iter().take(trailers).skip(1) {
+        if line.trim().is_empty() {
+            paragraph.flush(&mut res, width);
+            res.push(String::new());
+        } else if line.starts_with(char::is_whitespace) {
+            paragraph.flush(&mut res, width);
+            res.push(line.to_string());
+        } else if let Some(marker) = list_marker(line) {
+            paragraph.flush(&mut res, width);
+            paragraph.prefix = marker.to_string();
+            paragraph.add(&line[marker.len()..]);
+        } else {
+            paragraph.add(line);
+        }
+    }
+
+    paragraph.flush(&mut res, width);
+
+    res.extend(lines[trailers..].iter().map(|l| (*l).to_string()));
+
+    let mut res = res.join("\n");
+    if msg.ends_with('\n') {
+        res.push('\n');
+    }
+    res
+}
+
+/// index of the first line of the trailer block (the last
+/// paragraph if it only consists of `Key: value` lines),
+/// `lines.len()` if there is none
+fn trailers_start(lines: &[&str]) -> usize {
+    let end = lines
+        .iter()
+        .rposition(|l| !l.trim().is_empty())
+        .map_or(0, |idx| idx + 1);
+
+    let start = lines[..end]
+        .iter()
+        .rposition(|l| l.trim().is_empty())
+        .map_or(end, |idx| idx + 1);
+
+    // the subject is never a trailer
+    let is_trailer_block = start > 1
+        && start < end
+        && is_trailer(lines[start])
+        && lines[start..end].iter().all(|l| {
+            // git allows folding long values
+            is_trailer(l) || l.starts_with(char::is_whitespace)
+        });
+
+    if is_trailer_block {
+        start
+    } else {
+        lines.len()
+    }
+}
+
+/// `Key: value` with a key made of alphanumerics and dashes
+fn is_trailer(line: &str) -> bool {
+    line.find(':').map_or(false, |idx| {
+        idx > 0
+            && line[..idx]
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-')
+    })
+}
+
+#[derive(Default)]
+struct Paragraph<'a> {
+    prefix: String,
+    words: Vec<&'a str>,
+}
+
+impl<'a> Paragraph<'a> {
+    fn add(&mut self, line: &'a str) {
+        self.words.extend(line.split_whitespace());
+    }
+
+    fn flush(&mut self, out: &mut Vec<String>, width: usize) {
+        if self.words.is_empty() && self.prefix.is_empty() {
+            return;
+        }
+
+        let indent = " ".repeat(self.prefix.width());
+        let mut line = std::mem::take(&mut self.prefix);
+        let mut line_has_words = false;
+
+        for word in self.words.drain(..) {
+            if line_has_words
+                && line.width() + 1 + word.width() > width
+            {
+                out.push(std::mem::take(&mut line));
+                line.push_str(&indent);
+                line_has_words = false;
+            }
+
+            if line_has_words {
+                line.push(' ');
+            }
+            line.push_str(word);
+            line_has_words = true;
+        }
+
+        out.push(line);
+    }
+}
+
+/// returns the list marker (including trailing whitespace)
+/// if `line` starts with one (`- `, `* `, `+ `, `1. `, `1) `)
+fn list_marker(line: &str) -> Option<&str> {
+    let digits =
+        line.chars().take_while(char::is_ascii_digit).count();
+
+    let marker_len = if digits > 0 {
+        match line[digits..].chars().next() {
+            Some(c) if c == '.' || c == ')' => digits + 1,
+            _ => return None,
+        }
+    } else if line.starts_with(&['-', '*', '+'][..]) {
+        1
+    } else {
+        return None;
+    };
+
+    let rest = &line[marker_len..];
+    let whitespace = rest.len() - rest.trim_start().len();
+
+    if whitespace > 0 {
+        Some(&line[..marker_len + whitespace])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        expand_template, load_template, reflow_commit_msg,
+        strip_comments,
+    };
+    use std::{env, fs, process};
+
+    #[test]
+    fn test_wrap_paragraph() {
+        let msg = "subject\n\nthis is a rather long paragraph \
+                   that needs to be wrapped\nat twenty columns";
+
+        assert_eq!(
+            reflow_commit_msg(msg, 20),
+            "subject\n\nthis is a rather\nlong paragraph that\n\
+             needs to be wrapped\nat twenty columns"
+        );
+    }
+
+    #[test]
+    fn test_keep_subject() {
+        let msg = "a subject line that is way longer than the width";
+
+        assert_eq!(reflow_commit_msg(msg, 10), msg);
+    }
+
+    #[test]
+    fn test_keep_list() {
+        let msg = "subject\n\n- first item\n- second item\n\n1. one\n2. two";
+
+        assert_eq!(reflow_commit_msg(msg, 72), msg);
+    }
+
+    #[test]
+    fn test_wrap_list_item() {
+        let msg = "subject\n\n* a list item with many words";
+
+        assert_eq!(
+            reflow_commit_msg(msg, 16),
+            "subject\n\n* a list item\n  with many\n  words"
+        );
+    }
+
+    #[test]
+    fn test_keep_code_and_blank_lines() {
+        let msg = "subject\n\nfoo\nbar\n\n\n    let a = some_long_code();\n";
+
+        assert_eq!(
+            reflow_commit_msg(msg, 10),
+            "subject\n\nfoo bar\n\n\n    let a = some_long_code();\n"
+        );
+    }
+
+    #[test]
+    fn test_keep_trailers() {
+        let msg = "subject\n\nsome words\nto join\n\n\
+                   Signed-off-by: Some Author <author@example.com>\n\
+                   Co-authored-by: Another Author <another@example.com>\n";
+
+        assert_eq!(
+            reflow_commit_msg(msg, 20),
+            "subject\n\nsome words to join\n\n\
+             Signed-off-by: Some Author <author@example.com>\n\
+             Co-authored-by: Another Author <another@example.com>\n"
+        );
+
+        // not all lines are trailers
+        assert_eq!(
+            reflow_commit_msg("subject\n\nNote: this\nis text", 72),
+            "subject\n\nNote: this is text"
+        );
+    }
+
+    #[test]
+    fn test_expand_template() {
+        assert_eq!(
+            expand_template("$BRANCH: $FOO $BRANCHES $", Some("fix")),
+            "fix: $FOO $BRANCHES $"
+        );
+        assert_eq!(expand_template("($BRANCH)", None), "($BRANCH)");
+    }
+
+    #[test]
+    fn test_load_template() {
+        let path = env::temp_dir()
+            .join(format!("gitui-commit-template-{}", process::id()));
+        fs::write(&path, "feat($BRANCH): \n\n# $USER\n").unwrap();
+
+        let res = load_template(path.to_str().unwrap(), Some("ui"));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(res.unwrap(), "feat(ui): \n\n# $USER\n");
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let msg =
+            "Merge branch 'feature'\n\n# Conflicts:\n#\tfoo.txt\n";
+
+        assert_eq!(strip_comments(msg), "Merge branch 'feature'");
+        assert_eq!(strip_comments("a\n #b\n"), "a\n #b");
+    }
+}
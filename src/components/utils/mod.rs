@@ -1,5 +1,6 @@
 use chrono::{DateTime, Local, NaiveDateTime, Utc};
 
+pub mod commitmsg;
 pub mod filetree;
 pub mod logitems;
 pub mod statustree;
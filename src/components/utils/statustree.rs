@@ -117,6 +117,41 @@ impl StatusTree {
         self.tree.items().is_empty()
     }
 
+    /// selects the item at `path` expanding collapsed parents,
+    /// returns false if there is no such item
+    pub fn select_path(&mut self, path: &str) -> bool {
+        // items are in path component order with folders in
+        // between, which is not the order of their full path strings
+        let index = match self
+            .tree
+            .items()
+            .iter()
+            .position(|e| e.info.full_path == path)
+        {
+            Some(index) => index,
+            None => return false,
+        };
+
+        for parent in 0..index {
+            let parent_path =
+                self.tree[parent].info.full_path.clone();
+            let is_collapsed = matches!(
+                self.tree[parent].kind,
+                FileTreeItemKind::Path(PathCollapsed(true))
+            );
+
+            if is_collapsed
+                && path.starts_with(&format!("{}/", parent_path))
+            {
+                self.expand(&parent_path, parent);
+            }
+        }
+
+        self.selection = Some(index);
+
+        true
+    }
+
     fn all_collapsed(&self) -> BTreeSet<&String> {
         let mut res = BTreeSet::new();
 
@@ -389,6 +424,51 @@ mod tests {
         assert_eq!(res.selection, Some(0));
     }
 
+    #[test]
+    fn test_select_path() {
+        let mut res = StatusTree::default();
+        res.update(&string_vec_to_status(&["a/b/c", "a/d", "e"]))
+            .unwrap();
+
+        //0 a/
+        //1   b/
+        //2     c
+        //3   d
+        //4 e
+
+        res.collapse("a", 0);
+
+        assert_eq!(res.select_path("x"), false);
+        assert_eq!(res.selection, Some(0));
+
+        assert_eq!(res.select_path("a/b/c"), true);
+        assert_eq!(res.selection, Some(2));
+        assert_eq!(
+            get_visibles(&res),
+            vec![true, true, true, true, true]
+        );
+
+        assert_eq!(res.select_path("e"), true);
+        assert_eq!(res.selection, Some(4));
+    }
+
+    #[test]
+    fn test_select_path_next_to_folder() {
+        let mut res = StatusTree::default();
+        res.update(&string_vec_to_status(&["a/b.txt", "a.txt"]))
+            .unwrap();
+
+        //0 a/
+        //1   b.txt
+        //2 a.txt
+
+        assert_eq!(res.select_path("a.txt"), true);
+        assert_eq!(res.selection, Some(2));
+
+        assert_eq!(res.select_path("a/b.txt"), true);
+        assert_eq!(res.selection, Some(1));
+    }
+
     #[test]
     fn test_keep_selected_item() {
         let mut res = StatusTree::default();
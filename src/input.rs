@@ -23,6 +23,9 @@ pub enum InputState {
 #[derive(Clone, Copy, Debug)]
 pub enum InputEvent {
     Input(Event),
+    /// input that came along with more buffered input, most likely
+    /// part of a paste (crossterm does not support bracketed paste)
+    Pasted(Event),
     State(InputState),
 }
 
@@ -44,33 +47,51 @@ impl Input {
         let arc_desired = Arc::clone(&desired_state);
         let arc_current = Arc::clone(&current_state);
 
-        thread::spawn(move || loop {
-            if arc_desired.get() {
-                if !arc_current.load(Ordering::Relaxed) {
-                    log::info!("input polling resumed");
+        thread::spawn(move || {
+            let mut buffered = false;
+            loop {
+                if arc_desired.get() {
+                    if !arc_current.load(Ordering::Relaxed) {
+                        log::info!("input polling resumed");
 
-                    tx.send(InputEvent::State(InputState::Polling))
+                        tx.send(InputEvent::State(
+                            InputState::Polling,
+                        ))
                         .expect("send state failed");
-                }
-                arc_current.store(true, Ordering::Relaxed);
-
-                if let Some(e) = Self::poll(POLL_DURATION)
-                    .expect("failed to pull events.")
-                {
-                    tx.send(InputEvent::Input(e))
+                    }
+                    arc_current.store(true, Ordering::Relaxed);
+
+                    if let Some(e) = Self::poll(POLL_DURATION)
+                        .expect("failed to pull events.")
+                    {
+                        // read before we got to it or followed by more
+                        let more =
+                            event::poll(Duration::from_millis(0))
+                                .expect("failed to pull events.");
+                        let pasted = buffered || more;
+                        buffered = more;
+
+                        tx.send(if pasted {
+                            InputEvent::Pasted(e)
+                        } else {
+                            InputEvent::Input(e)
+                        })
                         .expect("send input failed");
-                }
-            } else {
-                if arc_current.load(Ordering::Relaxed) {
-                    log::info!("input polling suspended");
-
-                    tx.send(InputEvent::State(InputState::Paused))
+                    }
+                } else {
+                    if arc_current.load(Ordering::Relaxed) {
+                        log::info!("input polling suspended");
+
+                        tx.send(InputEvent::State(
+                            InputState::Paused,
+                        ))
                         .expect("send state failed");
-                }
+                    }
 
-                arc_current.store(false, Ordering::Relaxed);
+                    arc_current.store(false, Ordering::Relaxed);
 
-                arc_desired.wait(true);
+                    arc_desired.wait(true);
+                }
             }
         });
 
@@ -39,6 +39,18 @@ pub const MOVE_LEFT: KeyEvent = no_mod(KeyCode::Left);
 pub const MOVE_RIGHT: KeyEvent = no_mod(KeyCode::Right);
 pub const HOME: KeyEvent = no_mod(KeyCode::Home);
 pub const END: KeyEvent = no_mod(KeyCode::End);
+pub const WORD_LEFT: KeyEvent =
+    with_mod(KeyCode::Left, KeyModifiers::CONTROL);
+pub const WORD_RIGHT: KeyEvent =
+    with_mod(KeyCode::Right, KeyModifiers::CONTROL);
+pub const DELETE_WORD_LEFT: KeyEvent =
+    with_mod(KeyCode::Char('w'), KeyModifiers::CONTROL);
+pub const DELETE_WORD_RIGHT: KeyEvent =
+    with_mod(KeyCode::Delete, KeyModifiers::CONTROL);
+pub const UNDO: KeyEvent =
+    with_mod(KeyCode::Char('z'), KeyModifiers::CONTROL);
+pub const REDO: KeyEvent =
+    with_mod(KeyCode::Char('y'), KeyModifiers::CONTROL);
 pub const MOVE_UP: KeyEvent = no_mod(KeyCode::Up);
 pub const MOVE_DOWN: KeyEvent = no_mod(KeyCode::Down);
 pub const PAGE_DOWN: KeyEvent = no_mod(KeyCode::PageDown);
@@ -54,6 +66,7 @@ pub const STATUS_STAGE_ALL: KeyEvent = no_mod(KeyCode::Char('a'));
 pub const STATUS_RESET_FILE: KeyEvent =
     with_mod(KeyCode::Char('D'), KeyModifiers::SHIFT);
 pub const DIFF_RESET_HUNK: KeyEvent = STATUS_RESET_FILE;
+pub const DIFF_TOGGLE_STAGE: KeyEvent = no_mod(KeyCode::Char('t'));
 pub const STATUS_IGNORE_FILE: KeyEvent = no_mod(KeyCode::Char('i'));
 pub const STASHING_SAVE: KeyEvent = no_mod(KeyCode::Char('s'));
 pub const STASHING_TOGGLE_UNTRACKED: KeyEvent =
@@ -69,3 +82,9 @@ pub const LOG_COMMIT_DETAILS: KeyEvent = no_mod(KeyCode::Enter);
 pub const LOG_TAG_COMMIT: KeyEvent = no_mod(KeyCode::Char('t'));
 pub const COMMIT_AMEND: KeyEvent =
     with_mod(KeyCode::Char('a'), KeyModifiers::CONTROL);
+pub const COMMIT_STICKY: KeyEvent =
+    with_mod(KeyCode::Enter, KeyModifiers::ALT);
+pub const COMMIT_SPELL_CHECK: KeyEvent =
+    with_mod(KeyCode::Char('k'), KeyModifiers::CONTROL);
+pub const OPEN_GC: KeyEvent =
+    with_mod(KeyCode::Char('g'), KeyModifiers::CONTROL);
@@ -13,6 +13,7 @@ mod components;
 mod input;
 mod keys;
 mod notify_mutex;
+mod options;
 mod profiler;
 mod queue;
 mod spinner;
@@ -0,0 +1,72 @@
+use crate::get_app_config_path;
+use anyhow::Result;
+use ron::{
+    de::from_bytes,
+    ser::{to_string_pretty, PrettyConfig},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::PathBuf,
+    rc::Rc,
+};
+
+pub type SharedOptions = Rc<Options>;
+
+/// user settings persisted in `options.ron` next to the theme
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Options {
+    /// reflow the commit body (never the subject) to this width
+    pub commit_body_width: Option<usize>,
+    /// external checker the commit message is piped into (stdin)
+    /// on demand, whatever it prints is shown as its findings
+    pub commit_spell_check: Option<String>,
+    /// keep the commit input open (and cleared) after committing
+    pub sticky_commit: bool,
+    /// only the last diff/status refresh of a burst within this
+    /// many milliseconds is computed (100ms if not set)
+    pub refresh_debounce_ms: Option<u64>,
+}
+
+impl Options {
+    fn save(&self) -> Result<()> {
+        let options_file = Self::get_options_file()?;
+        let mut file = File::create(options_file)?;
+        let data = to_string_pretty(self, PrettyConfig::default())?;
+        file.write_all(data.as_bytes())?;
+        Ok(())
+    }
+
+    fn get_options_file() -> Result<PathBuf> {
+        let app_home = get_app_config_path()?;
+        Ok(app_home.join("options.ron"))
+    }
+
+    fn read_file(options_file: PathBuf) -> Result<Self> {
+        let mut f = File::open(options_file)?;
+        let mut buffer = Vec::new();
+        f.read_to_end(&mut buffer)?;
+        Ok(from_bytes(&buffer)?)
+    }
+
+    fn init_internal() -> Result<Self> {
+        let file = Self::get_options_file()?;
+        if file.exists() {
+            Ok(Self::read_file(file)?)
+        } else {
+            let def = Self::default();
+            if def.save().is_err() {
+                log::warn!(
+                    "failed to store default options to disk."
+                );
+            }
+            Ok(def)
+        }
+    }
+
+    pub fn init() -> Self {
+        Self::init_internal().unwrap_or_default()
+    }
+}
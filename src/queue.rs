@@ -28,6 +28,10 @@ pub enum Action {
     Reset(ResetItem),
     ResetHunk(String, u64),
     StashDrop(CommitId),
+    /// retry the commit (sticky or not) allowing it to be empty
+    CommitEmpty(bool),
+    /// `git gc` including reflog expiry and pruning
+    Gc,
 }
 
 ///
@@ -39,6 +43,8 @@ pub enum InternalEvent {
     ///
     ShowErrorMsg(String),
     ///
+    ShowInfoMsg(String),
+    ///
     Update(NeedsUpdate),
     /// open commit msg input
     OpenCommit,
@@ -52,6 +58,9 @@ pub enum InternalEvent {
     TagCommit(CommitId),
     ///
     OpenExternalEditor(Option<String>),
+    /// show the staged diff of the file in the diff view
+    /// instead of the unstaged one (or vice versa)
+    ToggleDiffStage,
 }
 
 ///
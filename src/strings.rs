@@ -12,6 +12,8 @@ pub static CMD_SPLITTER: &str = " ";
 
 pub static MSG_OPENING_EDITOR: &str = "opening editor...";
 pub static MSG_TITLE_ERROR: &str = "Error";
+pub static MSG_TITLE_INFO: &str = "Info";
+pub static SPELL_CHECK_NO_FINDINGS: &str = "spell check: no findings";
 pub static COMMIT_TITLE: &str = "Commit";
 pub static COMMIT_TITLE_AMEND: &str = "Commit (Amend)";
 pub static COMMIT_MSG: &str = "type commit message..";
@@ -25,6 +27,12 @@ pub static CONFIRM_TITLE_STASHDROP: &str = "Drop";
 pub static CONFIRM_MSG_RESET: &str = "confirm file reset?";
 pub static CONFIRM_MSG_STASHDROP: &str = "confirm stash drop?";
 pub static CONFIRM_MSG_RESETHUNK: &str = "confirm reset hunk?";
+pub static CONFIRM_TITLE_COMMIT_EMPTY: &str = "Nothing to commit";
+pub static CONFIRM_TITLE_GC: &str = "Garbage collect";
+pub static CONFIRM_MSG_GC: &str =
+    "run git gc? this expires reflogs and prunes unreachable objects";
+pub static CONFIRM_MSG_COMMIT_EMPTY: &str =
+    "nothing staged, create an empty commit?";
 
 pub static LOG_TITLE: &str = "Commit";
 
@@ -40,6 +48,10 @@ pub static STASHING_OPTIONS_TITLE: &str = "Options";
 
 pub static LOADING_TEXT: &str = "Loading ...";
 
+pub static GC_TITLE: &str = "Garbage Collection";
+pub static GC_MSG_STARTING: &str = "starting gc...";
+pub static GC_MSG_DONE: &str = "gc finished";
+
 pub mod commit {
     pub static DETAILS_AUTHOR: &str = "Author: ";
     pub static DETAILS_COMMITTER: &str = "Committer: ";
@@ -116,6 +128,12 @@ pub mod commands {
         CMD_GROUP_DIFF,
     );
     ///
+    pub static DIFF_TOGGLE_STAGE: CommandText = CommandText::new(
+        "Toggle Stage [t]",
+        "show the other (staged/unstaged) diff of the file",
+        CMD_GROUP_DIFF,
+    );
+    ///
     pub static DIFF_HUNK_REMOVE: CommandText = CommandText::new(
         "Remove hunk [enter]",
         "removes selected hunk from stage",
@@ -171,12 +189,24 @@ pub mod commands {
         CMD_GROUP_COMMIT,
     );
     ///
+    pub static COMMIT_ENTER_STICKY: CommandText = CommandText::new(
+        "Commit & continue [M-enter]",
+        "commit and keep the input open for the next commit",
+        CMD_GROUP_COMMIT,
+    );
+    ///
     pub static COMMIT_AMEND: CommandText = CommandText::new(
         "Amend [^a]",
         "amend last commit",
         CMD_GROUP_COMMIT,
     );
     ///
+    pub static COMMIT_SPELL_CHECK: CommandText = CommandText::new(
+        "Spell check [^k]",
+        "check the commit message with the configured checker",
+        CMD_GROUP_COMMIT,
+    );
+    ///
     pub static EDIT_ITEM: CommandText = CommandText::new(
         "Edit Item [e]",
         "edit the currently selected file in an external editor",
@@ -237,6 +267,12 @@ pub mod commands {
         CMD_GROUP_GENERAL,
     );
     ///
+    pub static OPEN_GC: CommandText = CommandText::new(
+        "GC [^g]",
+        "garbage collect the repository (repack and prune objects)",
+        CMD_GROUP_GENERAL,
+    );
+    ///
     pub static RESET_CONFIRM: CommandText = CommandText::new(
         "Confirm [enter]",
         "resets the file in question",
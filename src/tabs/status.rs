@@ -6,6 +6,7 @@ use crate::{
         DiffComponent, DrawableComponent, FileTreeItemKind,
     },
     keys,
+    options::SharedOptions,
     queue::{InternalEvent, Queue, ResetItem},
     strings::{self, commands, order},
     ui::style::SharedTheme,
@@ -18,6 +19,7 @@ use asyncgit::{
 };
 use crossbeam_channel::Sender;
 use crossterm::event::Event;
+use std::time::Duration;
 use tui::layout::{Constraint, Direction, Layout};
 
 ///
@@ -106,7 +108,19 @@ impl Status {
         queue: &Queue,
         sender: &Sender<AsyncNotification>,
         theme: SharedTheme,
+        options: &SharedOptions,
     ) -> Self {
+        let mut git_diff = AsyncDiff::new(sender.clone());
+        let mut git_status_workdir = AsyncStatus::new(sender.clone());
+        let mut git_status_stage = AsyncStatus::new(sender.clone());
+
+        if let Some(ms) = options.refresh_debounce_ms {
+            let debounce = Duration::from_millis(ms);
+            git_diff.set_debounce(debounce);
+            git_status_workdir.set_debounce(debounce);
+            git_status_stage.set_debounce(debounce);
+        }
+
         Self {
             queue: queue.clone(),
             visible: true,
@@ -127,9 +141,9 @@ impl Status {
                 theme.clone(),
             ),
             diff: DiffComponent::new(Some(queue.clone()), theme),
-            git_diff: AsyncDiff::new(sender.clone()),
-            git_status_workdir: AsyncStatus::new(sender.clone()),
-            git_status_stage: AsyncStatus::new(sender.clone()),
+            git_diff,
+            git_status_workdir,
+            git_status_stage,
         }
     }
 
@@ -178,6 +192,32 @@ impl Status {
         self.index.focus_select(is_stage);
     }
 
+    /// shows the diff of the current file on the other side
+    /// (stage vs. workdir) if it has changes there
+    pub fn toggle_diff_stage(&mut self) -> Result<()> {
+        let (path, is_stage) = self.diff.current();
+
+        let (target, list) = if is_stage {
+            (DiffTarget::WorkingDir, &mut self.index_wd)
+        } else {
+            (DiffTarget::Stage, &mut self.index)
+        };
+
+        if list.select_file(&path) {
+            self.set_diff_target(target);
+
+            // keep the diff focused
+            if self.focus == Focus::Diff {
+                self.index.focus(false);
+                self.index_wd.focus(false);
+            }
+
+            self.update_diff()?;
+        }
+
+        Ok(())
+    }
+
     pub fn selected_path(&self) -> Option<(String, bool)> {
         let (idx, is_stage) = match self.diff_target {
             DiffTarget::Stage => (&self.index, true),